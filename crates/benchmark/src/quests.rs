@@ -42,6 +42,7 @@ pub fn random_quest() -> CreateQuestRequest {
                 id: format!("step-{step_id}-task-{task_id}"),
                 description: format!("step-{step_id}-task-{task_id}-description"),
                 action_items,
+                ordered: false,
             };
             tasks.push(task);
         }
@@ -49,6 +50,8 @@ pub fn random_quest() -> CreateQuestRequest {
             id: format!("step-{step_id}"),
             description: create_random_string(50),
             tasks,
+            optional: false,
+            branch_group: String::new(),
         };
         steps.push(step);
     }
@@ -89,8 +92,11 @@ pub fn grab_some_apples() -> Quest {
                         id: "A_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -98,8 +104,11 @@ pub fn grab_some_apples() -> Quest {
                         id: "B_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(13, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -107,8 +116,11 @@ pub fn grab_some_apples() -> Quest {
                         id: "C_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 24))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -116,8 +128,11 @@ pub fn grab_some_apples() -> Quest {
                         id: "D_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(40, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         }),
@@ -142,8 +157,11 @@ pub fn grab_some_pies() -> CreateQuestRequest {
                         id: "A_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -151,8 +169,11 @@ pub fn grab_some_pies() -> CreateQuestRequest {
                         id: "B_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(30, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -160,8 +181,11 @@ pub fn grab_some_pies() -> CreateQuestRequest {
                         id: "C_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -169,8 +193,11 @@ pub fn grab_some_pies() -> CreateQuestRequest {
                         id: "D_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(14, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         },
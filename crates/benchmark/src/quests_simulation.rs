@@ -136,7 +136,7 @@ impl ClientState {
         let current_state_discriminant = std::mem::discriminant(&self);
         let state = match self {
             ClientState::Start => {
-                let result = quests_service.subscribe().await;
+                let result = quests_service.subscribe(SubscribeRequest::default()).await;
                 match result {
                     Ok(response) => ClientState::Subscribed { updates: response },
                     Err(_) => ClientState::Start,
@@ -219,6 +219,7 @@ impl ClientState {
                 let event = quests_service
                     .send_event(EventRequest {
                         action: action.cloned(),
+                        ..Default::default()
                     })
                     .await;
                 match event {
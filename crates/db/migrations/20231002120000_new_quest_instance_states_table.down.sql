@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS events_quest_instance_id_timestamp_idx;
+DROP TABLE IF EXISTS quest_instance_states;
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS quest_instance_states (
+  quest_instance_id UUID PRIMARY KEY NOT NULL references quest_instances(ID),
+  state bytea NOT NULL,
+  last_event_id UUID NOT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX events_quest_instance_id_timestamp_idx ON events(quest_instance_id, timestamp);
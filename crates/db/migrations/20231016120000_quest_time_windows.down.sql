@@ -0,0 +1,5 @@
+ALTER TABLE quest_instances DROP COLUMN IF EXISTS deadline;
+
+ALTER TABLE quests DROP COLUMN IF EXISTS instance_duration;
+ALTER TABLE quests DROP COLUMN IF EXISTS ends_at;
+ALTER TABLE quests DROP COLUMN IF EXISTS starts_at;
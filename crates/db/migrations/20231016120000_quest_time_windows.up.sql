@@ -0,0 +1,5 @@
+ALTER TABLE quests ADD COLUMN IF NOT EXISTS starts_at TIMESTAMP NULL;
+ALTER TABLE quests ADD COLUMN IF NOT EXISTS ends_at TIMESTAMP NULL;
+ALTER TABLE quests ADD COLUMN IF NOT EXISTS instance_duration BIGINT NULL;
+
+ALTER TABLE quest_instances ADD COLUMN IF NOT EXISTS deadline TIMESTAMP NULL;
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS quest_prerequisites (
+  quest_id UUID NOT NULL references quests(ID),
+  prerequisite_quest_id UUID NOT NULL references quests(ID),
+  UNIQUE(quest_id, prerequisite_quest_id)
+);
@@ -0,0 +1 @@
+ALTER TABLE quests ADD COLUMN IF NOT EXISTS repeat_policy JSON NOT NULL DEFAULT '{"type": "never"}';
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS quest_instances_user_address_quest_id_idx;
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS quest_instances_user_address_quest_id_idx ON quest_instances(user_address, quest_id);
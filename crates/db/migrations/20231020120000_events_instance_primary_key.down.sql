@@ -0,0 +1,7 @@
+-- An event applied to several instances is kept once per instance, so the copies get new IDs to be unique again
+UPDATE events AS copy SET id = gen_random_uuid()
+FROM events AS original
+WHERE copy.id = original.id AND copy.ctid > original.ctid;
+
+ALTER TABLE events DROP CONSTRAINT IF EXISTS events_pkey;
+ALTER TABLE events ADD PRIMARY KEY (id);
@@ -0,0 +1,2 @@
+ALTER TABLE events DROP CONSTRAINT IF EXISTS events_pkey;
+ALTER TABLE events ADD PRIMARY KEY (id, quest_instance_id);
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use super::errors::{DBError, DBResult};
-use crate::{date_time_to_unix, parse_uuid_to_str};
+use crate::{date_time_to_unix, optional_date_time_to_unix, parse_uuid_to_str};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgRow, Row};
@@ -35,6 +35,12 @@ pub trait QuestsDatabase: Send + Sync + CloneDatabase {
     async fn is_quest_creator(&self, quest_id: &str, creator_address: &str) -> DBResult<bool>;
 
     async fn start_quest(&self, quest_id: &str, user_address: &str) -> DBResult<String>;
+    /// Counts the instances of any version of the quest the user completed
+    async fn get_user_quest_completions(
+        &self,
+        user_address: &str,
+        quest_id: &str,
+    ) -> DBResult<QuestCompletions>;
     async fn abandon_quest_instance(&self, quest_instance_id: &str) -> DBResult<String>;
     async fn complete_quest_instance(&self, quest_instance_id: &str) -> DBResult<String>;
     async fn is_completed_instance(&self, quest_instance_id: &str) -> DBResult<bool>;
@@ -46,10 +52,15 @@ pub trait QuestsDatabase: Send + Sync + CloneDatabase {
 
     async fn get_quest_instance(&self, id: &str) -> DBResult<QuestInstance>;
     async fn is_active_quest_instance(&self, quest_instance_id: &str) -> DBResult<bool>;
+    /// Returns the instances the user is playing, excluding the abandoned, completed and expired ones
     async fn get_active_user_quest_instances(
         &self,
         user_address: &str,
     ) -> DBResult<Vec<QuestInstance>>;
+    async fn get_completed_user_quest_instances(
+        &self,
+        user_address: &str,
+    ) -> DBResult<Vec<QuestInstance>>;
 
     async fn get_all_quest_instances_by_quest_id(
         &self,
@@ -63,10 +74,41 @@ pub trait QuestsDatabase: Send + Sync + CloneDatabase {
     ) -> DBResult<Vec<QuestInstance>>;
     async fn count_active_quest_instances_by_quest_id(&self, quest_id: &str) -> DBResult<i64>;
 
-    async fn add_event(&self, event: &AddEvent, quest_instance_id: &str) -> DBResult<()>;
+    /// Returns whether the event was stored, `false` if the quest instance already has it
+    async fn add_event(&self, event: &AddEvent, quest_instance_id: &str) -> DBResult<bool>;
     async fn get_events(&self, quest_instance_id: &str) -> DBResult<Vec<Event>>;
+    async fn get_events_after(
+        &self,
+        quest_instance_id: &str,
+        event_id: &str,
+    ) -> DBResult<Vec<Event>>;
+    async fn get_events_before(
+        &self,
+        quest_instance_id: &str,
+        event_id: &str,
+    ) -> DBResult<Vec<Event>>;
+    /// Returns the events of the quest instance in the order they were added, filtered and paginated
+    async fn get_events_page(
+        &self,
+        quest_instance_id: &str,
+        filter: &EventsFilter,
+    ) -> DBResult<Vec<Event>>;
     async fn remove_events_from_quest_instance(&self, quest_instance_id: &str) -> DBResult<()>;
-    async fn remove_event(&self, event_id: &str) -> DBResult<()>;
+    /// Removes the event only if it belongs to the given quest instance
+    async fn remove_event(&self, quest_instance_id: &str, event_id: &str) -> DBResult<()>;
+
+    async fn get_quest_instance_state(
+        &self,
+        quest_instance_id: &str,
+    ) -> DBResult<QuestInstanceState>;
+    /// Stores the snapshot only if the instance has no events after `previous_event_id` other than the applied ones,
+    /// and those events are still stored, so a snapshot never skips an event nor keeps a removed one. Returns whether
+    /// the snapshot was stored.
+    async fn save_quest_instance_state(
+        &self,
+        quest_instance_id: &str,
+        state: &SaveQuestInstanceState,
+    ) -> DBResult<bool>;
 
     async fn add_reward_hook_to_quest(
         &self,
@@ -81,6 +123,22 @@ pub trait QuestsDatabase: Send + Sync + CloneDatabase {
     ) -> DBResult<()>;
     async fn get_quest_reward_items(&self, quest_id: &str) -> DBResult<Vec<QuestRewardItem>>;
 
+    async fn get_quest_prerequisites(&self, quest_id: &str) -> DBResult<Vec<String>>;
+    /// Returns the prerequisites of the quest that the user hasn't completed yet. Completing any version of a
+    /// prerequisite quest is enough
+    async fn get_missing_quest_prerequisites(
+        &self,
+        quest_id: &str,
+        user_address: &str,
+    ) -> DBResult<Vec<String>>;
+    /// Whether any version of the quest is the prerequisite quest or is required by it, directly or through the
+    /// prerequisites of any of their versions
+    async fn is_quest_required_by(
+        &self,
+        quest_id: &str,
+        prerequisite_quest_id: &str,
+    ) -> DBResult<bool>;
+
     async fn can_activate_quest(&self, quest_id: &str) -> DBResult<bool>;
     async fn activate_quest(&self, quest_id: &str) -> DBResult<bool>;
 
@@ -96,6 +154,16 @@ pub struct AddEvent<'a> {
     pub event: Vec<u8>,
 }
 
+/// Filters for a page of events. Timestamps are unix seconds and both ends of the range are included, so `to` includes
+/// the events of its whole second
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub struct EventsFilter {
+    pub offset: i64,
+    pub limit: i64,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
 #[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub struct Event {
     pub id: String,
@@ -105,12 +173,94 @@ pub struct Event {
     pub event: Vec<u8>,
 }
 
+impl TryFrom<PgRow> for Event {
+    type Error = DBError;
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(Event {
+            id: parse_uuid_to_str(
+                value
+                    .try_get("id")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            ),
+            user_address: value
+                .try_get("user_address")
+                .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            event: value
+                .try_get("event")
+                .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            quest_instance_id: parse_uuid_to_str(
+                value
+                    .try_get("quest_instance_id")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            ),
+            timestamp: date_time_to_unix(
+                value
+                    .try_get("timestamp")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            ),
+        })
+    }
+}
+
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub struct SaveQuestInstanceState<'a> {
+    pub state: Vec<u8>,
+    /// Last event of the snapshot the new state was calculated from, `None` if it was calculated from scratch
+    pub previous_event_id: Option<&'a str>,
+    /// Events applied on top of the previous snapshot, in order
+    pub applied_event_ids: Vec<&'a str>,
+}
+
+/// Snapshot of a Quest Instance's state after applying every event up to `last_event_id`
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub struct QuestInstanceState {
+    pub quest_instance_id: String,
+    pub state: Vec<u8>,
+    pub last_event_id: String,
+    pub updated_at: i64,
+}
+
+impl TryFrom<PgRow> for QuestInstanceState {
+    type Error = DBError;
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(QuestInstanceState {
+            quest_instance_id: parse_uuid_to_str(
+                value
+                    .try_get("quest_instance_id")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            ),
+            state: value
+                .try_get("state")
+                .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            last_event_id: parse_uuid_to_str(
+                value
+                    .try_get("last_event_id")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            ),
+            updated_at: date_time_to_unix(
+                value
+                    .try_get("updated_at")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            ),
+        })
+    }
+}
+
 #[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub struct QuestInstance {
     pub id: String,
     pub quest_id: String,
     pub user_address: String,
     pub start_timestamp: i64,
+    /// Unix timestamp until the quest can be completed, `None` if the quest has no time limit
+    pub deadline: Option<i64>,
+}
+
+impl QuestInstance {
+    /// Whether the instance can't progress anymore because its deadline is before the given unix timestamp
+    pub fn is_expired_at(&self, timestamp: i64) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= timestamp)
+    }
 }
 
 impl TryFrom<PgRow> for QuestInstance {
@@ -135,6 +285,7 @@ impl TryFrom<PgRow> for QuestInstance {
                     .try_get("start_timestamp")
                     .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
             ),
+            deadline: optional_date_time_to_unix(&value, "deadline")?,
         })
     }
 }
@@ -146,6 +297,15 @@ pub struct CreateQuest<'a> {
     pub image_url: &'a str,
     pub definition: Vec<u8>,
     pub reward: Option<QuestReward>,
+    /// Unix timestamp since the quest can be started
+    pub starts_at: Option<i64>,
+    /// Unix timestamp until the quest can be started
+    pub ends_at: Option<i64>,
+    /// Seconds the user has to complete the quest after starting it
+    pub instance_duration: Option<i64>,
+    /// Quests the user has to complete before starting this one
+    pub prerequisites: Vec<String>,
+    pub repeat_policy: RepeatPolicy,
 }
 
 #[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug)]
@@ -158,6 +318,65 @@ pub struct StoredQuest {
     pub image_url: String,
     pub active: bool,
     pub created_at: i64,
+    pub starts_at: Option<i64>,
+    pub ends_at: Option<i64>,
+    pub instance_duration: Option<i64>,
+    pub repeat_policy: RepeatPolicy,
+}
+
+impl StoredQuest {
+    /// Whether the quest can be started at the given unix timestamp
+    pub fn is_available_at(&self, timestamp: i64) -> bool {
+        self.starts_at
+            .map_or(true, |starts_at| starts_at <= timestamp)
+            && self.ends_at.map_or(true, |ends_at| timestamp < ends_at)
+    }
+}
+
+/// Whether a user can start a quest again after completing it
+#[derive(Default, PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Debug, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RepeatPolicy {
+    /// The quest can be completed only once
+    #[default]
+    Never,
+    Unlimited,
+    /// The quest can be completed up to `count` times
+    Times {
+        count: i64,
+    },
+    /// The quest can be started again once `seconds` passed since the last completion
+    Cooldown {
+        seconds: i64,
+    },
+}
+
+/// Completions of a quest by a user
+#[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub struct QuestCompletions {
+    pub count: i64,
+    /// Unix timestamp of the last completion
+    pub last_completed_at: Option<i64>,
+}
+
+impl RepeatPolicy {
+    /// Returns whether the user can start the quest at the given unix timestamp according to the previous
+    /// completions. `Err` holds the unix timestamp when the quest can be started again, if it can
+    pub fn check(&self, completions: &QuestCompletions, timestamp: i64) -> Result<(), Option<i64>> {
+        let Some(last_completed_at) = completions.last_completed_at else {
+            return Ok(());
+        };
+        match self {
+            Self::Never => Err(None),
+            Self::Unlimited => Ok(()),
+            Self::Times { count } if completions.count >= *count => Err(None),
+            Self::Times { .. } => Ok(()),
+            Self::Cooldown { seconds } if timestamp < last_completed_at + seconds => {
+                Err(Some(last_completed_at + seconds))
+            }
+            Self::Cooldown { .. } => Ok(()),
+        }
+    }
 }
 
 #[derive(Default, PartialEq, Serialize, Deserialize, Clone, Debug, ToSchema)]
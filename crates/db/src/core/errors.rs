@@ -37,6 +37,9 @@ pub enum DBError {
     #[error("Unable to create a quest instance: {0}")]
     StartQuestFailed(BoxDynError),
 
+    #[error("Unable to get the completions of a quest: {0}")]
+    GetQuestCompletionsFailed(BoxDynError),
+
     #[error("Unable to get a quest instance: {0}")]
     GetQuestInstanceFailed(BoxDynError),
 
@@ -46,12 +49,27 @@ pub enum DBError {
     #[error("Unable to store an event for a quest: {0}")]
     CreateQuestEventFailed(BoxDynError),
 
+    #[error("Unable to get a quest instance state: {0}")]
+    GetQuestInstanceStateFailed(BoxDynError),
+
+    #[error("Unable to store a quest instance state: {0}")]
+    SaveQuestInstanceStateFailed(BoxDynError),
+
+    #[error("Unable to remove a quest instance state: {0}")]
+    RemoveQuestInstanceStateFailed(BoxDynError),
+
     #[error("Unable to add a reward to a quest: {0}")]
     CreateQuestRewardFailed(BoxDynError),
 
     #[error("Unable to get quest reward: {0}")]
     GetQuestRewardFailed(BoxDynError),
 
+    #[error("Unable to add prerequisites to a quest: {0}")]
+    CreateQuestPrerequisitesFailed(BoxDynError),
+
+    #[error("Unable to get quest prerequisites: {0}")]
+    GetQuestPrerequisitesFailed(BoxDynError),
+
     #[error("Unable to check if a quest is still active: {0}")]
     GetActiveQuestFailed(BoxDynError),
 
@@ -72,6 +90,9 @@ pub enum DBError {
     #[error("Unable to check if there is an active quest instance for a user {0}: {1}")]
     GetActiveQuestInstancesFailed(String, BoxDynError),
 
+    #[error("Unable to retrieve the completed quest instances of a user {0}: {1}")]
+    GetCompletedQuestInstancesFailed(String, BoxDynError),
+
     #[error("Unable to check if there is older versions: {0}")]
     GetOldQuestVersionsFailed(BoxDynError),
 
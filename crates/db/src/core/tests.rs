@@ -1,4 +1,6 @@
-use super::definitions::{AddEvent, CreateQuest, QuestsDatabase};
+use super::definitions::{
+    AddEvent, CreateQuest, EventsFilter, QuestsDatabase, RepeatPolicy, SaveQuestInstanceState,
+};
 use crate::core::{
     definitions::{QuestReward, QuestRewardHook, QuestRewardItem},
     errors::DBError,
@@ -79,6 +81,11 @@ pub async fn quest_database_works<DB: QuestsDatabase>(db: &DB, quest: CreateQues
         definition: quest.definition.clone(),
         image_url: quest.image_url,
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     // updatable checks
@@ -110,6 +117,11 @@ pub async fn quest_database_works<DB: QuestsDatabase>(db: &DB, quest: CreateQues
         definition: quest.definition.clone(),
         image_url: quest.image_url,
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let deactivated_quest = db
@@ -180,7 +192,9 @@ pub async fn quest_database_works<DB: QuestsDatabase>(db: &DB, quest: CreateQues
         event: vec![0],
     };
 
-    db.add_event(&event, &quest_instance_id).await.unwrap();
+    assert!(db.add_event(&event, &quest_instance_id).await.unwrap());
+    // adding an already stored event again is ignored
+    assert!(!db.add_event(&event, &quest_instance_id).await.unwrap());
 
     let quest_instance_events = db.get_events(&quest_instance_id).await.unwrap();
 
@@ -211,6 +225,9 @@ pub async fn quest_database_works<DB: QuestsDatabase>(db: &DB, quest: CreateQues
     assert!(!is_active_instance);
 
     let new_instance = db.start_quest(&quest_id, "0xB").await.unwrap();
+    // the same event can be stored for every instance it's applied to
+    assert!(db.add_event(&event, &new_instance).await.unwrap());
+    assert_eq!(db.get_events(&new_instance).await.unwrap().len(), 1);
     db.complete_quest_instance(&new_instance).await.unwrap();
 
     let result = db.is_completed_instance(&new_instance).await.unwrap();
@@ -323,11 +340,255 @@ pub async fn quest_database_works<DB: QuestsDatabase>(db: &DB, quest: CreateQues
     let events = db.get_events(&new_quest_instance_id).await.unwrap();
     assert_eq!(events.len(), 1);
 
-    db.remove_event(&event_id).await.unwrap();
+    // events can only be removed from the instance they belong to
+    let other_quest_instance_id = db.start_quest(&quest_id, "0xE").await.unwrap();
+    let result = db.remove_event(&other_quest_instance_id, &event_id).await;
+    assert!(matches!(result, Err(DBError::RowNotFound)));
+
+    db.remove_event(&new_quest_instance_id, &event_id)
+        .await
+        .unwrap();
 
     let events = db.get_events(&new_quest_instance_id).await.unwrap();
     assert_eq!(events.len(), 0);
 
+    // test events pages
+    let paginated_quest_instance_id = db.start_quest(&quest_id, "0xF").await.unwrap();
+    let mut event_ids = vec![];
+    for _ in 0..3 {
+        let event_id = uuid::Uuid::new_v4().to_string();
+        db.add_event(
+            &AddEvent {
+                id: event_id.clone(),
+                user_address: "0xF",
+                event: vec![0],
+            },
+            &paginated_quest_instance_id,
+        )
+        .await
+        .unwrap();
+        event_ids.push(event_id);
+    }
+    let all_events = db.get_events(&paginated_quest_instance_id).await.unwrap();
+
+    let page = db
+        .get_events_page(
+            &paginated_quest_instance_id,
+            &EventsFilter {
+                offset: 1,
+                limit: 1,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].id, all_events[1].id);
+
+    let page = db
+        .get_events_page(
+            &paginated_quest_instance_id,
+            &EventsFilter {
+                offset: 0,
+                limit: 10,
+                from: Some(all_events[0].timestamp),
+                to: Some(all_events[2].timestamp),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(page.len(), 3);
+
+    let page = db
+        .get_events_page(
+            &paginated_quest_instance_id,
+            &EventsFilter {
+                offset: 0,
+                limit: 10,
+                from: Some(all_events[2].timestamp + 1),
+                to: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(page.is_empty());
+
+    // test quest instance state snapshots
+    let first_event_id = uuid::Uuid::new_v4().to_string();
+    db.add_event(
+        &AddEvent {
+            id: first_event_id.clone(),
+            user_address: "0xD",
+            event: vec![0],
+        },
+        &new_quest_instance_id,
+    )
+    .await
+    .unwrap();
+
+    let saved = db
+        .save_quest_instance_state(
+            &new_quest_instance_id,
+            &SaveQuestInstanceState {
+                state: vec![1],
+                previous_event_id: None,
+                applied_event_ids: vec![&first_event_id],
+            },
+        )
+        .await
+        .unwrap();
+    assert!(saved);
+
+    let snapshot = db
+        .get_quest_instance_state(&new_quest_instance_id)
+        .await
+        .unwrap();
+    assert_eq!(snapshot.state, vec![1]);
+    assert_eq!(snapshot.last_event_id, first_event_id);
+
+    let second_event_id = uuid::Uuid::new_v4().to_string();
+    db.add_event(
+        &AddEvent {
+            id: second_event_id.clone(),
+            user_address: "0xD",
+            event: vec![0],
+        },
+        &new_quest_instance_id,
+    )
+    .await
+    .unwrap();
+
+    let events = db
+        .get_events_after(&new_quest_instance_id, &first_event_id)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, second_event_id);
+
+    let events = db
+        .get_events_before(&new_quest_instance_id, &second_event_id)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, first_event_id);
+
+    // a snapshot skipping the first event is not stored
+    let saved = db
+        .save_quest_instance_state(
+            &new_quest_instance_id,
+            &SaveQuestInstanceState {
+                state: vec![2],
+                previous_event_id: None,
+                applied_event_ids: vec![&second_event_id],
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!saved);
+
+    let saved = db
+        .save_quest_instance_state(
+            &new_quest_instance_id,
+            &SaveQuestInstanceState {
+                state: vec![2],
+                previous_event_id: Some(&first_event_id),
+                applied_event_ids: vec![&second_event_id],
+            },
+        )
+        .await
+        .unwrap();
+    assert!(saved);
+
+    let snapshot = db
+        .get_quest_instance_state(&new_quest_instance_id)
+        .await
+        .unwrap();
+    assert_eq!(snapshot.state, vec![2]);
+    assert_eq!(snapshot.last_event_id, second_event_id);
+
+    // a snapshot calculated from an outdated one is not stored
+    let saved = db
+        .save_quest_instance_state(
+            &new_quest_instance_id,
+            &SaveQuestInstanceState {
+                state: vec![3],
+                previous_event_id: None,
+                applied_event_ids: vec![&first_event_id],
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!saved);
+
+    // removing an event invalidates the snapshot
+    db.remove_event(&new_quest_instance_id, &second_event_id)
+        .await
+        .unwrap();
+    let snapshot = db.get_quest_instance_state(&new_quest_instance_id).await;
+    assert!(matches!(snapshot, Err(DBError::RowNotFound)));
+
+    // a snapshot calculated before the removal is not stored
+    let saved = db
+        .save_quest_instance_state(
+            &new_quest_instance_id,
+            &SaveQuestInstanceState {
+                state: vec![2],
+                previous_event_id: Some(&first_event_id),
+                applied_event_ids: vec![&second_event_id],
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!saved);
+
+    let saved = db
+        .save_quest_instance_state(
+            &new_quest_instance_id,
+            &SaveQuestInstanceState {
+                state: vec![1],
+                previous_event_id: None,
+                applied_event_ids: vec![&first_event_id],
+            },
+        )
+        .await
+        .unwrap();
+    assert!(saved);
+
+    // nor one calculated from a snapshot whose last event was removed
+    let third_event_id = uuid::Uuid::new_v4().to_string();
+    db.add_event(
+        &AddEvent {
+            id: third_event_id.clone(),
+            user_address: "0xD",
+            event: vec![0],
+        },
+        &new_quest_instance_id,
+    )
+    .await
+    .unwrap();
+    db.remove_event(&new_quest_instance_id, &first_event_id)
+        .await
+        .unwrap();
+    let saved = db
+        .save_quest_instance_state(
+            &new_quest_instance_id,
+            &SaveQuestInstanceState {
+                state: vec![3],
+                previous_event_id: Some(&first_event_id),
+                applied_event_ids: vec![&third_event_id],
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!saved);
+
+    // resetting the instance invalidates the snapshot
+    db.remove_events_from_quest_instance(&new_quest_instance_id)
+        .await
+        .unwrap();
+    let snapshot = db.get_quest_instance_state(&new_quest_instance_id).await;
+    assert!(matches!(snapshot, Err(DBError::RowNotFound)));
+
     // test remove from completed quest instances
     db.remove_instance_from_completed_instances(&new_quest_instance_id)
         .await
@@ -337,4 +598,237 @@ pub async fn quest_database_works<DB: QuestsDatabase>(db: &DB, quest: CreateQues
         .await
         .unwrap();
     assert!(!is_not_completed);
+
+    // time limited quests
+    let timed_quest_id = db
+        .create_quest(
+            &CreateQuest {
+                name: quest.name,
+                description: quest.description,
+                definition: vec![0, 1],
+                image_url: quest.image_url,
+                reward: None,
+                starts_at: Some(1_000),
+                ends_at: Some(2_000),
+                instance_duration: Some(3_600),
+                prerequisites: vec![],
+                repeat_policy: RepeatPolicy::Never,
+            },
+            "0xA",
+        )
+        .await
+        .unwrap();
+    let timed_quest = db.get_quest(&timed_quest_id).await.unwrap();
+    assert_eq!(timed_quest.starts_at, Some(1_000));
+    assert_eq!(timed_quest.ends_at, Some(2_000));
+    assert_eq!(timed_quest.instance_duration, Some(3_600));
+    assert!(!timed_quest.is_available_at(999));
+    assert!(timed_quest.is_available_at(1_000));
+    assert!(!timed_quest.is_available_at(2_000));
+
+    let timed_instance_id = db.start_quest(&timed_quest_id, "0xG").await.unwrap();
+    let timed_instance = db.get_quest_instance(&timed_instance_id).await.unwrap();
+    assert_eq!(
+        timed_instance.deadline,
+        Some(timed_instance.start_timestamp + 3_600)
+    );
+    assert!(!timed_instance.is_expired_at(timed_instance.start_timestamp));
+    assert!(timed_instance.is_expired_at(timed_instance.start_timestamp + 3_600));
+    assert!(db
+        .has_active_quest_instance("0xG", &timed_quest_id)
+        .await
+        .unwrap());
+
+    // an instance past its deadline is not active anymore
+    let expired_quest_id = db
+        .create_quest(
+            &CreateQuest {
+                name: quest.name,
+                description: quest.description,
+                definition: vec![0, 1],
+                image_url: quest.image_url,
+                reward: None,
+                starts_at: None,
+                ends_at: None,
+                instance_duration: Some(0),
+                prerequisites: vec![],
+                repeat_policy: RepeatPolicy::Never,
+            },
+            "0xA",
+        )
+        .await
+        .unwrap();
+    db.start_quest(&expired_quest_id, "0xH").await.unwrap();
+    assert!(!db
+        .has_active_quest_instance("0xH", &expired_quest_id)
+        .await
+        .unwrap());
+    assert!(db
+        .get_active_user_quest_instances("0xH")
+        .await
+        .unwrap()
+        .is_empty());
+
+    // prerequisites
+    let prerequisite_quest = CreateQuest {
+        name: quest.name,
+        description: quest.description,
+        definition: vec![0, 1],
+        image_url: quest.image_url,
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let prerequisite_quest_id = db.create_quest(&prerequisite_quest, "0xA").await.unwrap();
+    let chained_quest_id = db
+        .create_quest(
+            &CreateQuest {
+                prerequisites: vec![prerequisite_quest_id.clone()],
+                ..prerequisite_quest.clone()
+            },
+            "0xA",
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        db.get_quest_prerequisites(&chained_quest_id).await.unwrap(),
+        vec![prerequisite_quest_id.clone()]
+    );
+    assert_eq!(
+        db.get_missing_quest_prerequisites(&chained_quest_id, "0xI")
+            .await
+            .unwrap(),
+        vec![prerequisite_quest_id.clone()]
+    );
+
+    // completing a newer version of the prerequisite is enough
+    let new_prerequisite_quest_id = db
+        .update_quest(&prerequisite_quest_id, &prerequisite_quest, "0xA")
+        .await
+        .unwrap();
+    let prerequisite_instance_id = db
+        .start_quest(&new_prerequisite_quest_id, "0xI")
+        .await
+        .unwrap();
+    assert_eq!(
+        db.get_missing_quest_prerequisites(&chained_quest_id, "0xI")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    db.complete_quest_instance(&prerequisite_instance_id)
+        .await
+        .unwrap();
+    assert!(db
+        .get_missing_quest_prerequisites(&chained_quest_id, "0xI")
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(db
+        .get_missing_quest_prerequisites(&prerequisite_quest_id, "0xI")
+        .await
+        .unwrap()
+        .is_empty());
+
+    // the chained quest requires every version of the prerequisite
+    assert!(db
+        .is_quest_required_by(&new_prerequisite_quest_id, &chained_quest_id)
+        .await
+        .unwrap());
+    assert!(db
+        .is_quest_required_by(&chained_quest_id, &chained_quest_id)
+        .await
+        .unwrap());
+    assert!(!db
+        .is_quest_required_by(&chained_quest_id, &new_prerequisite_quest_id)
+        .await
+        .unwrap());
+
+    // repeatable quests
+    let repeatable_quest_id = db
+        .create_quest(
+            &CreateQuest {
+                repeat_policy: RepeatPolicy::Cooldown { seconds: 60 },
+                ..prerequisite_quest.clone()
+            },
+            "0xA",
+        )
+        .await
+        .unwrap();
+    let repeatable_quest = db.get_quest(&repeatable_quest_id).await.unwrap();
+    assert_eq!(
+        repeatable_quest.repeat_policy,
+        RepeatPolicy::Cooldown { seconds: 60 }
+    );
+
+    let completions = db
+        .get_user_quest_completions("0xJ", &repeatable_quest_id)
+        .await
+        .unwrap();
+    assert_eq!(completions.count, 0);
+    assert_eq!(completions.last_completed_at, None);
+    assert!(repeatable_quest
+        .repeat_policy
+        .check(&completions, 0)
+        .is_ok());
+
+    let repeatable_instance_id = db.start_quest(&repeatable_quest_id, "0xJ").await.unwrap();
+    db.complete_quest_instance(&repeatable_instance_id)
+        .await
+        .unwrap();
+    // completed instances are not active anymore
+    assert!(!db
+        .has_active_quest_instance("0xJ", &repeatable_quest_id)
+        .await
+        .unwrap());
+    assert!(db
+        .get_active_user_quest_instances("0xJ")
+        .await
+        .unwrap()
+        .is_empty());
+    let completed_instances = db.get_completed_user_quest_instances("0xJ").await.unwrap();
+    assert_eq!(completed_instances.len(), 1);
+    assert_eq!(completed_instances[0].id, repeatable_instance_id);
+
+    let completions = db
+        .get_user_quest_completions("0xJ", &repeatable_quest_id)
+        .await
+        .unwrap();
+    assert_eq!(completions.count, 1);
+    let last_completed_at = completions.last_completed_at.unwrap();
+    assert_eq!(
+        repeatable_quest
+            .repeat_policy
+            .check(&completions, last_completed_at + 59),
+        Err(Some(last_completed_at + 60))
+    );
+    assert!(repeatable_quest
+        .repeat_policy
+        .check(&completions, last_completed_at + 60)
+        .is_ok());
+    assert_eq!(RepeatPolicy::Never.check(&completions, 0), Err(None));
+    assert!(RepeatPolicy::Unlimited.check(&completions, 0).is_ok());
+    assert!(RepeatPolicy::Times { count: 2 }
+        .check(&completions, 0)
+        .is_ok());
+    assert_eq!(
+        RepeatPolicy::Times { count: 1 }.check(&completions, 0),
+        Err(None)
+    );
+
+    // the completions of previous versions count for the new one
+    let new_repeatable_quest_id = db
+        .update_quest(&repeatable_quest_id, &prerequisite_quest, "0xA")
+        .await
+        .unwrap();
+    let completions = db
+        .get_user_quest_completions("0xJ", &new_repeatable_quest_id)
+        .await
+        .unwrap();
+    assert_eq!(completions.count, 1);
+    assert_eq!(completions.last_completed_at, Some(last_completed_at));
 }
@@ -2,8 +2,9 @@ pub mod core;
 
 use crate::core::{
     definitions::{
-        AddEvent, CreateQuest, Event, QuestInstance, QuestRewardHook, QuestRewardItem,
-        QuestsDatabase, StoredQuest,
+        AddEvent, CreateQuest, Event, EventsFilter, QuestCompletions, QuestInstance,
+        QuestInstanceState, QuestRewardHook, QuestRewardItem, QuestsDatabase, RepeatPolicy,
+        SaveQuestInstanceState, StoredQuest,
     },
     errors::{DBError, DBResult},
     ops::{Connect, GetConnection, Migrate},
@@ -12,7 +13,7 @@ use futures_util::StreamExt;
 pub use sqlx::Executor;
 use sqlx::{
     pool::PoolConnection,
-    postgres::{PgConnectOptions, PgPoolOptions},
+    postgres::{PgConnectOptions, PgPoolOptions, PgRow},
     types::{chrono::NaiveDateTime, Json},
     ConnectOptions, Error, PgPool, Postgres, QueryBuilder, Row, Transaction,
 };
@@ -128,6 +129,15 @@ impl QuestsDatabase for Database {
                     .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
                 active: true,
                 created_at: created_at.timestamp(),
+                starts_at: optional_date_time_to_unix(&row, "starts_at")?,
+                ends_at: optional_date_time_to_unix(&row, "ends_at")?,
+                instance_duration: row
+                    .try_get("instance_duration")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+                repeat_policy: row
+                    .try_get::<Json<RepeatPolicy>, _>("repeat_policy")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?
+                    .0,
             })
         }
 
@@ -205,6 +215,15 @@ impl QuestsDatabase for Database {
                     .try_get("active")
                     .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
                 created_at: created_at.timestamp(),
+                starts_at: optional_date_time_to_unix(&row, "starts_at")?,
+                ends_at: optional_date_time_to_unix(&row, "ends_at")?,
+                instance_duration: row
+                    .try_get("instance_duration")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+                repeat_policy: row
+                    .try_get::<Json<RepeatPolicy>, _>("repeat_policy")
+                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?
+                    .0,
             })
         }
 
@@ -228,7 +247,7 @@ impl QuestsDatabase for Database {
     }
 
     async fn create_quest(&self, quest: &CreateQuest, creator_address: &str) -> DBResult<String> {
-        let quest_id = if let Some(reward) = &quest.reward {
+        let quest_id = if quest.reward.is_some() || !quest.prerequisites.is_empty() {
             let mut tx = self
                 .pool
                 .begin()
@@ -239,10 +258,15 @@ impl QuestsDatabase for Database {
                 .do_create_quest(quest, creator_address, Some(&mut tx))
                 .await?;
 
-            self.do_add_quest_reward_hook(&quest_id, &reward.hook, Some(&mut tx))
-                .await?;
+            if let Some(reward) = &quest.reward {
+                self.do_add_quest_reward_hook(&quest_id, &reward.hook, Some(&mut tx))
+                    .await?;
+
+                self.do_add_quest_reward_items(&quest_id, &reward.items, Some(&mut tx))
+                    .await?;
+            }
 
-            self.do_add_quest_reward_items(&quest_id, &reward.items, Some(&mut tx))
+            self.do_add_quest_prerequisites(&quest_id, &quest.prerequisites, Some(&mut tx))
                 .await?;
 
             tx.commit()
@@ -281,6 +305,8 @@ impl QuestsDatabase for Database {
             self.do_add_quest_reward_items(&quest_id, &reward.items, Some(&mut transaction))
                 .await?;
         }
+        self.do_add_quest_prerequisites(&quest_id, &quest.prerequisites, Some(&mut transaction))
+            .await?;
 
         let id = Uuid::new_v4().to_string();
         sqlx::query(
@@ -342,6 +368,15 @@ impl QuestsDatabase for Database {
                 .try_get("active")
                 .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
             created_at: created_at.timestamp(),
+            starts_at: optional_date_time_to_unix(&query_result, "starts_at")?,
+            ends_at: optional_date_time_to_unix(&query_result, "ends_at")?,
+            instance_duration: query_result
+                .try_get("instance_duration")
+                .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            repeat_policy: query_result
+                .try_get::<Json<RepeatPolicy>, _>("repeat_policy")
+                .map_err(|err| DBError::RowCorrupted(Box::new(err)))?
+                .0,
         })
     }
 
@@ -435,15 +470,23 @@ impl QuestsDatabase for Database {
     ) -> DBResult<bool> {
         let quest_instance_exists: bool = sqlx::query_scalar(
             "
-                SELECT EXISTS (SELECT 1 FROM quest_instances
-                WHERE user_address = $1 AND quest_id = $2 AND id NOT IN (SELECT quest_instance_id as id FROM abandoned_quest_instances))
+                SELECT EXISTS (SELECT 1 FROM quest_instances qi
+                WHERE qi.user_address = $1 AND qi.quest_id = $2 AND (qi.deadline IS NULL OR qi.deadline > now())
+                AND NOT EXISTS (SELECT 1 FROM abandoned_quest_instances aqi WHERE aqi.quest_instance_id = qi.id)
+                AND NOT EXISTS (SELECT 1 FROM completed_quest_instances cqi WHERE cqi.quest_instance_id = qi.id))
             ",
         )
         .bind(user_address)
         .bind(parse_str_to_uuid(quest_id)?)
         .fetch_one(&self.pool)
         .await
-        .map_err(|err| DBError::HasActiveQuestInstanceFailed(quest_id.to_string(), user_address.to_string(), Box::new(err)))?;
+        .map_err(|err| {
+            DBError::HasActiveQuestInstanceFailed(
+                quest_id.to_string(),
+                user_address.to_string(),
+                Box::new(err),
+            )
+        })?;
 
         Ok(quest_instance_exists)
     }
@@ -455,17 +498,58 @@ impl QuestsDatabase for Database {
     async fn start_quest(&self, quest_id: &str, user_address: &str) -> DBResult<String> {
         let id = Uuid::new_v4().to_string();
 
-        sqlx::query("INSERT INTO quest_instances (id, quest_id, user_address) VALUES ($1, $2, $3)")
-            .bind(parse_str_to_uuid(&id)?)
-            .bind(parse_str_to_uuid(quest_id)?)
-            .bind(user_address)
-            .execute(&self.pool)
-            .await
-            .map_err(|err| DBError::StartQuestFailed(Box::new(err)))?;
+        // the deadline is null if the quest has no time limit to be completed
+        sqlx::query(
+            "INSERT INTO quest_instances (id, quest_id, user_address, deadline) 
+            VALUES ($1, $2, $3, (SELECT now() + make_interval(secs => instance_duration) FROM quests WHERE id = $2))",
+        )
+        .bind(parse_str_to_uuid(&id)?)
+        .bind(parse_str_to_uuid(quest_id)?)
+        .bind(user_address)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| DBError::StartQuestFailed(Box::new(err)))?;
 
         Ok(id)
     }
 
+    async fn get_user_quest_completions(
+        &self,
+        user_address: &str,
+        quest_id: &str,
+    ) -> DBResult<QuestCompletions> {
+        // every version of the quest is found following the updates in both directions
+        let row = sqlx::query(
+            "
+            WITH RECURSIVE versions (quest_id) AS (
+                SELECT $2::uuid
+
+                UNION
+
+                SELECT (CASE WHEN qu.previous_quest_id = v.quest_id THEN qu.quest_id ELSE qu.previous_quest_id END)
+                FROM versions v
+                JOIN quest_updates qu ON qu.previous_quest_id = v.quest_id OR qu.quest_id = v.quest_id
+            )
+            SELECT count(cqi.id) as count, max(cqi.created_at) as last_completed_at
+            FROM completed_quest_instances cqi
+            JOIN quest_instances qi ON qi.id = cqi.quest_instance_id
+            WHERE qi.user_address = $1 AND qi.quest_id IN (SELECT quest_id FROM versions)
+            ",
+        )
+        .bind(user_address)
+        .bind(parse_str_to_uuid(quest_id)?)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| DBError::GetQuestCompletionsFailed(Box::new(err)))?;
+
+        Ok(QuestCompletions {
+            count: row
+                .try_get("count")
+                .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
+            last_completed_at: optional_date_time_to_unix(&row, "last_completed_at")?,
+        })
+    }
+
     async fn get_quest_instance(&self, id: &str) -> DBResult<QuestInstance> {
         let query_result = sqlx::query("SELECT * FROM quest_instances WHERE id = $1")
             .bind(parse_str_to_uuid(id)?)
@@ -483,10 +567,14 @@ impl QuestsDatabase for Database {
         &self,
         user_address: &str,
     ) -> DBResult<Vec<QuestInstance>> {
+        // NOT EXISTS lets the planner use the unique indexes on quest_instance_id as anti-joins, NOT IN can't because
+        // the column is nullable
         let query_result = sqlx::query(
-            "SELECT * FROM quest_instances 
-            WHERE user_address = $1 
-            AND id NOT IN (SELECT quest_instance_id as id FROM abandoned_quest_instances)",
+            "SELECT qi.* FROM quest_instances qi
+            WHERE qi.user_address = $1 
+            AND (qi.deadline IS NULL OR qi.deadline > now())
+            AND NOT EXISTS (SELECT 1 FROM abandoned_quest_instances aqi WHERE aqi.quest_instance_id = qi.id)
+            AND NOT EXISTS (SELECT 1 FROM completed_quest_instances cqi WHERE cqi.quest_instance_id = qi.id)",
         )
         .bind(user_address)
         .fetch_all(&self.pool) // it could be replaced by fetch_many that returns a stream
@@ -505,9 +593,35 @@ impl QuestsDatabase for Database {
         Ok(quests)
     }
 
-    async fn add_event(&self, event: &AddEvent, quest_instance_id: &str) -> DBResult<()> {
-        sqlx::query(
-            "INSERT INTO events (id, user_address, event, quest_instance_id) VALUES ($1, $2, $3, $4)",
+    async fn get_completed_user_quest_instances(
+        &self,
+        user_address: &str,
+    ) -> DBResult<Vec<QuestInstance>> {
+        let query_result = sqlx::query(
+            "SELECT qi.* FROM quest_instances qi
+            JOIN completed_quest_instances cqi ON cqi.quest_instance_id = qi.id
+            WHERE qi.user_address = $1
+            AND NOT EXISTS (SELECT 1 FROM abandoned_quest_instances aqi WHERE aqi.quest_instance_id = qi.id)",
+        )
+        .bind(user_address)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| {
+            DBError::GetCompletedQuestInstancesFailed(user_address.to_string(), Box::new(err))
+        })?;
+
+        let mut quests = vec![];
+
+        for row in query_result {
+            quests.push(QuestInstance::try_from(row)?)
+        }
+
+        Ok(quests)
+    }
+
+    async fn add_event(&self, event: &AddEvent, quest_instance_id: &str) -> DBResult<bool> {
+        let query_result = sqlx::query(
+            "INSERT INTO events (id, user_address, event, quest_instance_id) VALUES ($1, $2, $3, $4) ON CONFLICT (id, quest_instance_id) DO NOTHING",
         )
         .bind(parse_str_to_uuid(&event.id)?)
         .bind(event.user_address)
@@ -517,70 +631,233 @@ impl QuestsDatabase for Database {
         .await
         .map_err(|err| DBError::CreateQuestEventFailed(Box::new(err)))?;
 
-        Ok(())
+        Ok(query_result.rows_affected() > 0)
     }
 
     async fn get_events(&self, quest_instance_id: &str) -> DBResult<Vec<Event>> {
-        let query_result =
-            sqlx::query("SELECT * FROM events WHERE quest_instance_id = $1 ORDER BY timestamp ASC")
-                .bind(parse_str_to_uuid(quest_instance_id)?)
-                .fetch_all(&self.pool) // it could be replaced by fetch_many that returns a stream
-                .await
-                .map_err(|err| DBError::GetQuestEventsFailed(Box::new(err)))?;
+        let query_result = sqlx::query(
+            "SELECT * FROM events WHERE quest_instance_id = $1 ORDER BY timestamp ASC, id ASC",
+        )
+        .bind(parse_str_to_uuid(quest_instance_id)?)
+        .fetch_all(&self.pool) // it could be replaced by fetch_many that returns a stream
+        .await
+        .map_err(|err| DBError::GetQuestEventsFailed(Box::new(err)))?;
 
         let mut events = vec![];
 
         for row in query_result {
             // not using functional methods due to "question mark"
-            events.push(Event {
-                id: parse_uuid_to_str(
-                    row.try_get("id")
-                        .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
-                ),
-                user_address: row
-                    .try_get("user_address")
-                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
-                event: row
-                    .try_get("event")
-                    .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
-                quest_instance_id: parse_uuid_to_str(
-                    row.try_get("quest_instance_id")
-                        .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
-                ),
-                timestamp: date_time_to_unix(
-                    row.try_get("timestamp")
-                        .map_err(|err| DBError::RowCorrupted(Box::new(err)))?,
-                ),
-            })
+            events.push(Event::try_from(row)?)
+        }
+
+        Ok(events)
+    }
+
+    async fn get_events_after(
+        &self,
+        quest_instance_id: &str,
+        event_id: &str,
+    ) -> DBResult<Vec<Event>> {
+        let query_result = sqlx::query(
+            "SELECT * FROM events
+            WHERE quest_instance_id = $1
+            AND (timestamp, id) > (SELECT timestamp, id FROM events WHERE id = $2 AND quest_instance_id = $1)
+            ORDER BY timestamp ASC, id ASC",
+        )
+        .bind(parse_str_to_uuid(quest_instance_id)?)
+        .bind(parse_str_to_uuid(event_id)?)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| DBError::GetQuestEventsFailed(Box::new(err)))?;
+
+        let mut events = vec![];
+
+        for row in query_result {
+            events.push(Event::try_from(row)?)
+        }
+
+        Ok(events)
+    }
+
+    async fn get_events_before(
+        &self,
+        quest_instance_id: &str,
+        event_id: &str,
+    ) -> DBResult<Vec<Event>> {
+        let query_result = sqlx::query(
+            "SELECT * FROM events
+            WHERE quest_instance_id = $1
+            AND (timestamp, id) < (SELECT timestamp, id FROM events WHERE id = $2 AND quest_instance_id = $1)
+            ORDER BY timestamp ASC, id ASC",
+        )
+        .bind(parse_str_to_uuid(quest_instance_id)?)
+        .bind(parse_str_to_uuid(event_id)?)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| DBError::GetQuestEventsFailed(Box::new(err)))?;
+
+        let mut events = vec![];
+
+        for row in query_result {
+            events.push(Event::try_from(row)?)
+        }
+
+        Ok(events)
+    }
+
+    async fn get_events_page(
+        &self,
+        quest_instance_id: &str,
+        filter: &EventsFilter,
+    ) -> DBResult<Vec<Event>> {
+        let query_result = sqlx::query(
+            "SELECT * FROM events
+            WHERE quest_instance_id = $1
+            AND ($2::TIMESTAMP IS NULL OR timestamp >= $2)
+            AND ($3::TIMESTAMP IS NULL OR timestamp < $3 + INTERVAL '1 second')
+            ORDER BY timestamp ASC, id ASC
+            OFFSET $4 LIMIT $5",
+        )
+        .bind(parse_str_to_uuid(quest_instance_id)?)
+        .bind(filter.from.and_then(unix_to_date_time))
+        .bind(filter.to.and_then(unix_to_date_time))
+        .bind(filter.offset)
+        .bind(filter.limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| DBError::GetQuestEventsFailed(Box::new(err)))?;
+
+        let mut events = vec![];
+
+        for row in query_result {
+            events.push(Event::try_from(row)?)
         }
 
         Ok(events)
     }
 
     async fn remove_events_from_quest_instance(&self, quest_instance_id: &str) -> DBResult<()> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| DBError::TransactionBeginFailed(Box::new(err)))?;
+
+        self.do_remove_quest_instance_state(quest_instance_id, Some(&mut transaction))
+            .await?;
+
         sqlx::query("DELETE FROM events WHERE quest_instance_id = $1")
             .bind(parse_str_to_uuid(quest_instance_id)?)
-            .execute(&self.pool)
+            .execute(&mut transaction)
             .await
             .map_err(|err| DBError::GetQuestEventsFailed(Box::new(err)))?;
 
+        transaction
+            .commit()
+            .await
+            .map_err(|err| DBError::TransactionFailed(Box::new(err)))?;
+
         Ok(())
     }
 
-    async fn remove_event(&self, event_id: &str) -> DBResult<()> {
-        let query_result = sqlx::query("DELETE FROM events WHERE id = $1")
-            .bind(parse_str_to_uuid(event_id)?)
-            .execute(&self.pool)
+    async fn remove_event(&self, quest_instance_id: &str, event_id: &str) -> DBResult<()> {
+        let mut transaction = self
+            .pool
+            .begin()
             .await
-            .map_err(|err| DBError::GetQuestEventsFailed(Box::new(err)))?;
+            .map_err(|err| DBError::TransactionBeginFailed(Box::new(err)))?;
+
+        let query_result =
+            sqlx::query("DELETE FROM events WHERE id = $1 AND quest_instance_id = $2")
+                .bind(parse_str_to_uuid(event_id)?)
+                .bind(parse_str_to_uuid(quest_instance_id)?)
+                .execute(&mut transaction)
+                .await
+                .map_err(|err| DBError::GetQuestEventsFailed(Box::new(err)))?;
 
         if query_result.rows_affected() == 0 {
             return Err(DBError::RowNotFound);
         }
 
+        // the snapshot may include the removed event, so the state has to be calculated from scratch
+        self.do_remove_quest_instance_state(quest_instance_id, Some(&mut transaction))
+            .await?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(|err| DBError::TransactionFailed(Box::new(err)))?;
+
         Ok(())
     }
 
+    async fn get_quest_instance_state(
+        &self,
+        quest_instance_id: &str,
+    ) -> DBResult<QuestInstanceState> {
+        let query_result =
+            sqlx::query("SELECT * FROM quest_instance_states WHERE quest_instance_id = $1")
+                .bind(parse_str_to_uuid(quest_instance_id)?)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|err| match err {
+                    Error::RowNotFound => DBError::RowNotFound,
+                    _ => DBError::GetQuestInstanceStateFailed(Box::new(err)),
+                })?;
+
+        QuestInstanceState::try_from(query_result)
+    }
+
+    async fn save_quest_instance_state(
+        &self,
+        quest_instance_id: &str,
+        state: &SaveQuestInstanceState,
+    ) -> DBResult<bool> {
+        let Some(last_event_id) = state.applied_event_ids.last() else {
+            return Ok(false);
+        };
+        let previous_event_id = state.previous_event_id.map(parse_str_to_uuid).transpose()?;
+        let applied_event_ids = state
+            .applied_event_ids
+            .iter()
+            .map(|id| parse_str_to_uuid(id))
+            .collect::<DBResult<Vec<_>>>()?;
+
+        // the events the snapshot was calculated from are locked, so they can't be removed while it's stored, and
+        // if they were removed in the meantime, it's not stored
+        let query_result = sqlx::query(
+            "INSERT INTO quest_instance_states (quest_instance_id, state, last_event_id)
+            SELECT $1, $2, $3
+            WHERE ($4::uuid IS NULL OR EXISTS (
+                SELECT 1 FROM events WHERE id = $4 AND quest_instance_id = $1 FOR SHARE
+            ))
+            AND cardinality($5::uuid[]) = (
+                SELECT count(*) FROM (
+                    SELECT 1 FROM events WHERE quest_instance_id = $1 AND id = ANY($5) FOR SHARE
+                ) AS applied_events
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM events
+                WHERE quest_instance_id = $1
+                AND id <> ALL($5)
+                AND ($4::uuid IS NULL OR (timestamp, id) > (SELECT timestamp, id FROM events WHERE id = $4 AND quest_instance_id = $1))
+            )
+            ON CONFLICT (quest_instance_id) DO UPDATE
+            SET state = EXCLUDED.state, last_event_id = EXCLUDED.last_event_id, updated_at = now()
+            WHERE quest_instance_states.last_event_id IS NOT DISTINCT FROM $4",
+        )
+        .bind(parse_str_to_uuid(quest_instance_id)?)
+        .bind(&state.state)
+        .bind(parse_str_to_uuid(last_event_id)?)
+        .bind(previous_event_id)
+        .bind(applied_event_ids)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| DBError::SaveQuestInstanceStateFailed(Box::new(err)))?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
     async fn add_reward_hook_to_quest(
         &self,
         quest_id: &str,
@@ -605,6 +882,90 @@ impl QuestsDatabase for Database {
         self.do_get_quest_reward_items(quest_id, None).await
     }
 
+    async fn get_quest_prerequisites(&self, quest_id: &str) -> DBResult<Vec<String>> {
+        let prerequisites: Vec<sqlx::types::Uuid> = sqlx::query_scalar(
+            "SELECT prerequisite_quest_id FROM quest_prerequisites WHERE quest_id = $1",
+        )
+        .bind(parse_str_to_uuid(quest_id)?)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| DBError::GetQuestPrerequisitesFailed(Box::new(err)))?;
+
+        Ok(prerequisites.into_iter().map(parse_uuid_to_str).collect())
+    }
+
+    async fn get_missing_quest_prerequisites(
+        &self,
+        quest_id: &str,
+        user_address: &str,
+    ) -> DBResult<Vec<String>> {
+        // every version of each prerequisite is found following the updates in both directions
+        let missing: Vec<sqlx::types::Uuid> = sqlx::query_scalar(
+            "
+            WITH RECURSIVE versions (prerequisite_quest_id, quest_id) AS (
+                SELECT prerequisite_quest_id, prerequisite_quest_id FROM quest_prerequisites WHERE quest_id = $1
+
+                UNION
+
+                SELECT v.prerequisite_quest_id, 
+                    (CASE WHEN qu.previous_quest_id = v.quest_id THEN qu.quest_id ELSE qu.previous_quest_id END)
+                FROM versions v
+                JOIN quest_updates qu ON qu.previous_quest_id = v.quest_id OR qu.quest_id = v.quest_id
+            )
+            SELECT v.prerequisite_quest_id FROM versions v
+            GROUP BY v.prerequisite_quest_id
+            HAVING NOT bool_or(EXISTS (
+                SELECT 1 FROM quest_instances qi
+                JOIN completed_quest_instances cqi ON cqi.quest_instance_id = qi.id
+                WHERE qi.quest_id = v.quest_id AND qi.user_address = $2
+            ))
+            ",
+        )
+        .bind(parse_str_to_uuid(quest_id)?)
+        .bind(user_address)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| DBError::GetQuestPrerequisitesFailed(Box::new(err)))?;
+
+        Ok(missing.into_iter().map(parse_uuid_to_str).collect())
+    }
+
+    async fn is_quest_required_by(
+        &self,
+        quest_id: &str,
+        prerequisite_quest_id: &str,
+    ) -> DBResult<bool> {
+        // the required quests are found following the updates in both directions and the prerequisites, so they
+        // include every version of each one
+        let is_required: bool = sqlx::query_scalar(
+            "
+            WITH RECURSIVE links (from_quest_id, to_quest_id) AS (
+                SELECT previous_quest_id, quest_id FROM quest_updates
+                UNION ALL
+                SELECT quest_id, previous_quest_id FROM quest_updates
+                UNION ALL
+                SELECT quest_id, prerequisite_quest_id FROM quest_prerequisites
+            ),
+            required (quest_id) AS (
+                SELECT $2::uuid
+
+                UNION
+
+                SELECT l.to_quest_id FROM required r
+                JOIN links l ON l.from_quest_id = r.quest_id
+            )
+            SELECT EXISTS (SELECT 1 FROM required WHERE quest_id = $1)
+            ",
+        )
+        .bind(parse_str_to_uuid(quest_id)?)
+        .bind(parse_str_to_uuid(prerequisite_quest_id)?)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| DBError::GetQuestPrerequisitesFailed(Box::new(err)))?;
+
+        Ok(is_required)
+    }
+
     async fn get_all_quest_instances_by_quest_id(
         &self,
         quest_id: &str,
@@ -777,6 +1138,23 @@ impl QuestsDatabase for Database {
 }
 
 impl Database {
+    async fn do_remove_quest_instance_state(
+        &self,
+        quest_instance_id: &str,
+        tx: Option<&mut Transaction<'_, Postgres>>,
+    ) -> DBResult<()> {
+        let query = sqlx::query("DELETE FROM quest_instance_states WHERE quest_instance_id = $1")
+            .bind(parse_str_to_uuid(quest_instance_id)?);
+        let result = if let Some(tx) = tx {
+            query.execute(tx).await
+        } else {
+            query.execute(&self.pool).await
+        };
+        result
+            .map_err(|err| DBError::RemoveQuestInstanceStateFailed(Box::new(err)))
+            .map(|_| ())
+    }
+
     async fn do_create_quest(
         &self,
         quest: &CreateQuest<'_>,
@@ -785,14 +1163,18 @@ impl Database {
     ) -> DBResult<String> {
         let quest_id = Uuid::new_v4().to_string();
         let query = sqlx::query(
-            "INSERT INTO quests (id, name, description, definition, creator_address, image_url) VALUES ($1, $2, $3, $4, $5, $6)",
+            "INSERT INTO quests (id, name, description, definition, creator_address, image_url, starts_at, ends_at, instance_duration, repeat_policy) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
         )
         .bind(parse_str_to_uuid(&quest_id)?)
         .bind(quest.name)
         .bind(quest.description)
         .bind(&quest.definition)
         .bind(creator_address)
-        .bind(quest.image_url);
+        .bind(quest.image_url)
+        .bind(quest.starts_at.and_then(unix_to_date_time))
+        .bind(quest.ends_at.and_then(unix_to_date_time))
+        .bind(quest.instance_duration)
+        .bind(Json(&quest.repeat_policy));
 
         let result = if let Some(tx) = tx {
             query.execute(tx).await
@@ -949,6 +1331,42 @@ impl Database {
 
         Ok(())
     }
+
+    async fn do_add_quest_prerequisites(
+        &self,
+        quest_id: &str,
+        prerequisites: &[String],
+        tx: Option<&mut Transaction<'_, Postgres>>,
+    ) -> DBResult<()> {
+        if prerequisites.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder =
+            QueryBuilder::new("INSERT INTO quest_prerequisites (quest_id, prerequisite_quest_id)");
+
+        let quest_id = parse_str_to_uuid(quest_id)?;
+        let prerequisites = prerequisites
+            .iter()
+            .map(|prerequisite| parse_str_to_uuid(prerequisite))
+            .collect::<DBResult<Vec<_>>>()?;
+
+        builder.push_values(prerequisites, |mut b, prerequisite| {
+            b.push_bind(quest_id).push_bind(prerequisite);
+        });
+
+        let query = builder.build();
+
+        let result = if let Some(tx) = tx {
+            query.execute(tx).await
+        } else {
+            query.execute(&self.pool).await
+        };
+
+        result.map_err(|err| DBError::CreateQuestPrerequisitesFailed(Box::new(err)))?;
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -998,6 +1416,17 @@ fn date_time_to_unix(time: sqlx::types::chrono::NaiveDateTime) -> i64 {
     time.timestamp()
 }
 
+fn optional_date_time_to_unix(row: &PgRow, column: &str) -> DBResult<Option<i64>> {
+    let time: Option<NaiveDateTime> = row
+        .try_get(column)
+        .map_err(|err| DBError::RowCorrupted(Box::new(err)))?;
+    Ok(time.map(date_time_to_unix))
+}
+
+fn unix_to_date_time(timestamp: i64) -> Option<sqlx::types::chrono::NaiveDateTime> {
+    sqlx::types::chrono::NaiveDateTime::from_timestamp_opt(timestamp, 0)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::parse_str_to_uuid;
@@ -1,7 +1,7 @@
 use std::env;
 
 use quests_db::{
-    core::definitions::CreateQuest,
+    core::definitions::{CreateQuest, RepeatPolicy},
     core::ops::{Connect, Migrate},
     core::tests::quest_database_works,
     DatabaseOptions,
@@ -26,6 +26,11 @@ async fn quests_database_works() {
             definition: vec![0, 1, 4],
             image_url: "",
             reward: None,
+            starts_at: None,
+            ends_at: None,
+            instance_duration: None,
+            prerequisites: vec![],
+            repeat_policy: RepeatPolicy::Never,
         },
     )
     .await;
@@ -1,9 +1,40 @@
-use crate::redis::Redis;
+use crate::{
+    redis::Redis,
+    replay_log::{RedisReplayLog, Sequenced},
+};
 use deadpool_redis::redis::{aio::PubSub, AsyncCommands};
 use futures_util::{Future, StreamExt as _};
 use log::{debug, error};
 use quests_protocol::definitions::*;
-use std::sync::Arc;
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+use tokio::{sync::Mutex, task::JoinHandle};
+
+/// Messages published to a sharded channel go to the shard of their key
+pub trait ShardKey {
+    fn shard_key(&self) -> &str;
+}
+
+impl ShardKey for UserUpdate {
+    fn shard_key(&self) -> &str {
+        &self.user_address
+    }
+}
+
+/// Name of the channel for the shard of the given key. With a single shard, it's the channel itself
+pub fn shard_channel_name(channel_name: &str, shards: u32, key: &str) -> String {
+    if shards <= 1 {
+        return channel_name.to_string();
+    }
+
+    // FNV-1a, as the shard of a key has to be the same in every process
+    let hash = key
+        .to_ascii_lowercase()
+        .bytes()
+        .fold(0x811c9dc5_u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x01000193)
+        });
+    format!("{channel_name}:{}", hash % shards)
+}
 
 #[derive(Debug)]
 pub enum RedisChannelSubscriberError {
@@ -69,20 +100,121 @@ impl RedisChannelSubscriber {
     }
 }
 
+type OnNewMessageFn<T> =
+    Arc<dyn Fn(T) -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> + Send + Sync>;
+
+/// Subscribes to the shards of a channel on demand, so only the messages for the keys being listened are received.
+///
+/// Each shard has its own connection, as a connection can't subscribe to more channels while receiving messages.
+pub struct RedisShardedChannelSubscriber<T> {
+    redis: Arc<Redis>,
+    channel_name: String,
+    shards: u32,
+    on_new_message_fn: OnNewMessageFn<T>,
+    /// Keys being listened and the task receiving the messages of each subscribed shard
+    subscriptions: Mutex<HashMap<String, (usize, JoinHandle<()>)>>,
+}
+
+impl<T: ProtocolMessage + Default + 'static> RedisShardedChannelSubscriber<T> {
+    pub fn new<U: Future<Output = ()> + Send + Sync + 'static>(
+        redis: Arc<Redis>,
+        channel_name: &str,
+        shards: u32,
+        on_new_message_fn: impl Fn(T) -> U + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            redis,
+            channel_name: channel_name.to_string(),
+            shards,
+            on_new_message_fn: Arc::new(move |message| Box::pin(on_new_message_fn(message))),
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Subscribes to the shard of the key, if it isn't subscribed yet
+    pub async fn listen(&self, key: &str) -> Result<(), RedisChannelSubscriberError> {
+        let shard = shard_channel_name(&self.channel_name, self.shards, key);
+        let mut subscriptions = self.subscriptions.lock().await;
+        if let Some((listeners, _)) = subscriptions.get_mut(&shard) {
+            *listeners += 1;
+            return Ok(());
+        }
+
+        let mut subscriber = RedisChannelSubscriber::new(self.redis.clone(), &shard).await?;
+        let on_new_message_fn = self.on_new_message_fn.clone();
+        let task = tokio::spawn(async move {
+            subscriber
+                .on_new_message(move |message: T| on_new_message_fn(message))
+                .await
+        });
+        subscriptions.insert(shard, (1, task));
+
+        Ok(())
+    }
+
+    /// Unsubscribes from the shard of the key once no other key of the shard is listened
+    pub async fn stop_listening(&self, key: &str) {
+        let shard = shard_channel_name(&self.channel_name, self.shards, key);
+        let mut subscriptions = self.subscriptions.lock().await;
+        let Some((listeners, _)) = subscriptions.get_mut(&shard) else {
+            return;
+        };
+
+        *listeners -= 1;
+        if *listeners == 0 {
+            if let Some((_, task)) = subscriptions.remove(&shard) {
+                // the connection is closed when the subscriber is dropped
+                task.abort();
+            }
+        }
+    }
+}
+
 pub struct RedisChannelPublisher {
     redis: Arc<Redis>,
     channel_name: String,
+    shards: u32,
+    replay_log: Option<RedisReplayLog>,
 }
 
 impl RedisChannelPublisher {
     pub fn new(redis: Arc<Redis>, channel_name: &str) -> Self {
+        Self::new_sharded(redis, channel_name, 1)
+    }
+
+    /// Each message is published to the shard of its key, see [`shard_channel_name`]
+    pub fn new_sharded(redis: Arc<Redis>, channel_name: &str, shards: u32) -> Self {
         Self {
             redis,
             channel_name: channel_name.to_string(),
+            shards,
+            replay_log: None,
         }
     }
 
-    pub async fn publish<P: ProtocolMessage + 'static>(&self, publishment: P) {
+    /// Numbers the messages of each key and keeps the last `max_size` of them, so they can be replayed
+    pub fn with_replay_log(mut self, max_size: u64) -> Self {
+        self.replay_log = Some(RedisReplayLog::new(
+            self.redis.clone(),
+            &self.channel_name,
+            max_size,
+        ));
+        self
+    }
+
+    pub fn replay_log(&self) -> Option<&RedisReplayLog> {
+        self.replay_log.as_ref()
+    }
+
+    pub async fn publish<P: ProtocolMessage + Sequenced + 'static>(&self, mut publishment: P) {
+        if let Some(replay_log) = &self.replay_log {
+            debug!("Publish > Appending to the replay log...");
+            // it's still published, the message is only missing for the ones reconnecting
+            if let Err(err) = replay_log.append(&mut publishment).await {
+                error!("Couldn't append message to the replay log with error: {err}");
+            }
+        }
+
         debug!("Publish > Getting connection...");
         let mut publish = self
             .redis
@@ -94,7 +226,9 @@ impl RedisChannelPublisher {
         let publishment_bin = publishment.encode_to_vec();
 
         debug!("Publish > Publishing...");
-        let result: Result<usize, _> = publish.publish(&self.channel_name, publishment_bin).await;
+        let channel_name =
+            shard_channel_name(&self.channel_name, self.shards, publishment.shard_key());
+        let result: Result<usize, _> = publish.publish(channel_name, publishment_bin).await;
         match result {
             Ok(result) => debug!("Publish > Done with response {result}"),
             Err(e) => error!("Couldn't publish message with error: {e:?}"),
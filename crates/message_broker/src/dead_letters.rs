@@ -0,0 +1,143 @@
+use crate::redis::Redis;
+use async_trait::async_trait;
+use deadpool_redis::redis::AsyncCommands;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::Arc;
+
+pub type DeadLettersResult<T> = Result<T, String>;
+
+/// Retry counters are dropped after this time, in case they aren't cleared because the process stopped
+const RETRIES_TTL_SECONDS: usize = 60 * 60 * 24;
+
+/// A message that couldn't be processed after all its attempts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter<T> {
+    pub id: String,
+    pub message: T,
+    pub attempts: u32,
+    pub reason: String,
+    pub failed_at: i64,
+}
+
+#[async_trait]
+pub trait DeadLetterQueue<T>: Send + Sync {
+    async fn push(&self, dead_letter: &DeadLetter<T>) -> DeadLettersResult<usize>;
+    async fn list(&self) -> DeadLettersResult<Vec<DeadLetter<T>>>;
+    /// Removes the dead letter from the queue and returns it, `None` if there is none with the given ID
+    async fn take(&self, id: &str) -> DeadLettersResult<Option<DeadLetter<T>>>;
+}
+
+pub struct RedisDeadLetterQueue {
+    redis: Arc<Redis>,
+    queue_name: String,
+}
+
+impl RedisDeadLetterQueue {
+    pub fn new(redis: Arc<Redis>, queue_name: &str) -> Self {
+        Self {
+            redis,
+            queue_name: queue_name.to_string(),
+        }
+    }
+
+    /// Records a failed attempt to process the message and returns how many times it has failed so far
+    pub async fn record_failure(&self, id: &str) -> DeadLettersResult<u32> {
+        let mut connection = self.get_connection().await?;
+        let key = self.retries_key(id);
+        let attempts: u32 = connection
+            .incr(&key, 1)
+            .await
+            .map_err(|err| format!("Failed to send incr command: {err}"))?;
+        connection
+            .expire::<_, ()>(&key, RETRIES_TTL_SECONDS)
+            .await
+            .map_err(|err| format!("Failed to send expire command: {err}"))?;
+
+        Ok(attempts)
+    }
+
+    /// Clears the failed attempts of a message that was finally processed, so it starts from scratch if it fails again
+    pub async fn clear_failures(&self, id: &str) -> DeadLettersResult<()> {
+        let mut connection = self.get_connection().await?;
+        connection
+            .del::<_, ()>(self.retries_key(id))
+            .await
+            .map_err(|err| format!("Failed to send del command: {err}"))
+    }
+
+    fn retries_key(&self, id: &str) -> String {
+        format!("{}:retries:{id}", self.queue_name)
+    }
+
+    async fn get_connection(&self) -> DeadLettersResult<deadpool_redis::Connection> {
+        self.redis
+            .get_async_connection()
+            .await
+            .ok_or("Failed to get a connection".to_string())
+    }
+}
+
+#[async_trait]
+impl<T: Serialize + DeserializeOwned + Send + Sync> DeadLetterQueue<T> for RedisDeadLetterQueue {
+    async fn push(&self, dead_letter: &DeadLetter<T>) -> DeadLettersResult<usize> {
+        let mut connection = self.get_connection().await?;
+        let serialized = serde_json::to_string(dead_letter)
+            .map_err(|err| format!("Couldn't serialize the dead letter: {err}"))?;
+        let queue_size: usize = connection
+            .rpush(&self.queue_name, serialized)
+            .await
+            .map_err(|err| format!("Failed to send push command: {err}"))?;
+        // the message gets a fresh set of attempts if it's replayed
+        connection
+            .del::<_, ()>(self.retries_key(&dead_letter.id))
+            .await
+            .map_err(|err| format!("Failed to send del command: {err}"))?;
+
+        Ok(queue_size)
+    }
+
+    async fn list(&self) -> DeadLettersResult<Vec<DeadLetter<T>>> {
+        let mut connection = self.get_connection().await?;
+        let entries: Vec<String> = connection
+            .lrange(&self.queue_name, 0, -1)
+            .await
+            .map_err(|err| format!("Failed to send lrange command: {err}"))?;
+
+        entries
+            .iter()
+            .map(|entry| {
+                serde_json::from_str(entry)
+                    .map_err(|err| format!("Couldn't deserialize the dead letter: {err}"))
+            })
+            .collect()
+    }
+
+    async fn take(&self, id: &str) -> DeadLettersResult<Option<DeadLetter<T>>> {
+        let mut connection = self.get_connection().await?;
+        let entries: Vec<String> = connection
+            .lrange(&self.queue_name, 0, -1)
+            .await
+            .map_err(|err| format!("Failed to send lrange command: {err}"))?;
+
+        for entry in entries {
+            let Ok(dead_letter) = serde_json::from_str::<DeadLetter<T>>(&entry) else {
+                continue;
+            };
+            if dead_letter.id != id {
+                continue;
+            }
+
+            let removed: usize = connection
+                .lrem(&self.queue_name, 1, &entry)
+                .await
+                .map_err(|err| format!("Failed to send lrem command: {err}"))?;
+            // someone else took it in the meantime
+            if removed == 0 {
+                return Ok(None);
+            }
+            return Ok(Some(dead_letter));
+        }
+
+        Ok(None)
+    }
+}
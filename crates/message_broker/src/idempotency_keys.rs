@@ -0,0 +1,154 @@
+use crate::redis::Redis;
+use deadpool_redis::redis::cmd;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub type IdempotencyKeysResult<T> = Result<T, String>;
+
+const PENDING_PREFIX: &str = "pending:";
+
+/// How long a message may take to be sent once its key is reserved. A reservation still pending after it is taken
+/// as left by a process that stopped before sending the message, so a retry sends it again
+const DEFAULT_PENDING_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reserves the key with a pending marker, unless it has the ID of a sent message or a marker that's not timed out.
+/// It returns what the key had then
+const RESERVE_SCRIPT: &str = r"
+local reserved = redis.call('GET', KEYS[1])
+if reserved then
+    local since = string.match(reserved, '^pending:.-:(%d+)$')
+    if not since or tonumber(ARGV[2]) - tonumber(since) < tonumber(ARGV[3]) then
+        return reserved
+    end
+end
+redis.call('SET', KEYS[1], 'pending:' .. ARGV[1] .. ':' .. ARGV[2], 'PX', ARGV[4])
+return false
+";
+
+/// Runs the command on the key only if it still has the pending marker of the given ID, as it may have been taken
+/// over meanwhile
+const IF_PENDING_SCRIPT: &str = r"
+local reserved = redis.call('GET', KEYS[1])
+if not reserved or string.match(reserved, '^pending:(.-):%d+$') ~= ARGV[1] then
+    return 0
+end
+if ARGV[2] == 'confirm' then
+    redis.call('SET', KEYS[1], ARGV[1], 'KEEPTTL')
+else
+    redis.call('DEL', KEYS[1])
+end
+return 1
+";
+
+/// Keys sent by clients along with a message, so the retries of a message are only accepted once.
+///
+/// Each key is reserved for the ID given to the message the first time, during the configured window. The
+/// reservation is pending until the message is sent and it's confirmed, so a message is never taken as sent when the
+/// process stopped in between
+pub struct RedisIdempotencyKeys {
+    redis: Arc<Redis>,
+    name: String,
+    window: Duration,
+    pending_timeout: Duration,
+}
+
+impl RedisIdempotencyKeys {
+    pub fn new(redis: Arc<Redis>, name: &str, window: Duration) -> Self {
+        Self {
+            redis,
+            name: name.to_string(),
+            window,
+            pending_timeout: DEFAULT_PENDING_TIMEOUT,
+        }
+    }
+
+    /// Sets how long a reservation may be pending before a retry takes it over
+    pub fn with_pending_timeout(mut self, pending_timeout: Duration) -> Self {
+        self.pending_timeout = pending_timeout;
+        self
+    }
+
+    /// Reserves the key of the scope for the given ID, until it's confirmed or released. If it was already
+    /// reserved, it returns the ID it has, even if its message is still being sent
+    pub async fn reserve(
+        &self,
+        scope: &str,
+        key: &str,
+        id: &str,
+    ) -> IdempotencyKeysResult<Option<String>> {
+        let mut connection = self.get_connection().await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| format!("Failed to get the current time: {err}"))?;
+
+        let reserved: Option<String> = cmd("EVAL")
+            .arg(RESERVE_SCRIPT)
+            .arg(1)
+            .arg(self.redis_key(scope, key))
+            .arg(id)
+            .arg(now.as_millis() as u64)
+            .arg(self.pending_timeout.as_millis() as u64)
+            .arg(self.window.as_millis().max(1) as u64)
+            .query_async(&mut connection)
+            .await
+            .map_err(|err| format!("Failed to reserve the idempotency key {key}: {err}"))?;
+
+        Ok(
+            reserved.map(|reserved| match reserved.strip_prefix(PENDING_PREFIX) {
+                Some(pending) => pending
+                    .rsplit_once(':')
+                    .map_or(pending, |(id, _)| id)
+                    .to_string(),
+                None => reserved,
+            }),
+        )
+    }
+
+    /// Marks the message of the reservation as sent, so its key is no longer taken over
+    pub async fn confirm(&self, scope: &str, key: &str, id: &str) -> IdempotencyKeysResult<()> {
+        if !self.run_if_pending(scope, key, id, "confirm").await? {
+            return Err(format!(
+                "The idempotency key {key} was taken over before confirming it"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Drops the reservation while it's pending, so the message can be sent again with the same key
+    pub async fn release(&self, scope: &str, key: &str, id: &str) -> IdempotencyKeysResult<()> {
+        self.run_if_pending(scope, key, id, "release").await?;
+        Ok(())
+    }
+
+    async fn run_if_pending(
+        &self,
+        scope: &str,
+        key: &str,
+        id: &str,
+        command: &str,
+    ) -> IdempotencyKeysResult<bool> {
+        let mut connection = self.get_connection().await?;
+        cmd("EVAL")
+            .arg(IF_PENDING_SCRIPT)
+            .arg(1)
+            .arg(self.redis_key(scope, key))
+            .arg(id)
+            .arg(command)
+            .query_async(&mut connection)
+            .await
+            .map_err(|err| format!("Failed to {command} the idempotency key {key}: {err}"))
+    }
+
+    fn redis_key(&self, scope: &str, key: &str) -> String {
+        format!("{}:{}:{key}", self.name, scope.to_ascii_lowercase())
+    }
+
+    async fn get_connection(&self) -> IdempotencyKeysResult<deadpool_redis::Connection> {
+        self.redis
+            .get_async_connection()
+            .await
+            .ok_or("Failed to get a connection".to_string())
+    }
+}
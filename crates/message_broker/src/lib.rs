@@ -1,3 +1,7 @@
 pub mod channel;
+pub mod dead_letters;
+pub mod idempotency_keys;
 pub mod messages_queue;
 pub mod redis;
+pub mod replay_log;
+pub mod streams_messages_queue;
@@ -1,20 +1,61 @@
 use crate::redis::Redis;
 use async_trait::async_trait;
-use deadpool_redis::redis::AsyncCommands;
+use deadpool_redis::redis::{pipe, AsyncCommands, Direction};
 use quests_protocol::definitions::ProtocolMessage;
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 pub type MessagesQueueResult<T> = Result<T, String>;
 
+/// A message popped with [`MessagesQueue::pop_delivery`], which has to be acked or nacked once processed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery<T> {
+    pub message: T,
+    /// What the queue needs to find the message when it's acked or nacked
+    pub(crate) receipt: Vec<u8>,
+}
+
 #[async_trait]
 pub trait MessagesQueue<T>: Send + Sync {
     async fn push(&self, item: &T) -> MessagesQueueResult<usize>;
     async fn pop(&self) -> MessagesQueueResult<T>;
+    /// Pops a message that is kept in a processing list until it's acked or nacked, if the queue is reliable
+    async fn pop_delivery(&self) -> MessagesQueueResult<Delivery<T>>;
+    /// Like [`MessagesQueue::pop_delivery`], but gives up if there is no message after the timeout
+    async fn try_pop_delivery(&self, timeout: Duration)
+        -> MessagesQueueResult<Option<Delivery<T>>>;
+    /// Removes the message from the processing list after processing it
+    async fn ack(&self, delivery: &Delivery<T>) -> MessagesQueueResult<()>;
+    /// Pushes the message back to the queue because it couldn't be processed
+    async fn nack(&self, delivery: &Delivery<T>) -> MessagesQueueResult<()>;
+    /// Takes back the messages a consumer that stopped left without acking, so they're popped again.
+    /// It should be called on startup, before popping any message, as the ones left by this consumer are only reclaimed
+    /// on the first call, and then periodically, as other consumers may stop at any time
+    async fn reclaim(&self) -> MessagesQueueResult<usize>;
 }
 
 pub struct RedisMessagesQueue {
     redis: Arc<Redis>,
     queue_name: String,
+    /// Only set in reliable mode
+    processing: Option<Processing>,
+}
+
+/// Where a reliable queue keeps the messages popped by this consumer until they're acked
+struct Processing {
+    list_name: String,
+    /// Sorted set with the last time each consumer popped a message, so the lists of the ones that stopped are found
+    consumers_name: String,
+    consumer_name: String,
+    /// Time without popping after which a consumer is considered stopped and its messages are reclaimed
+    claim_min_idle: Duration,
+    /// The messages of this consumer are only reclaimed once, as afterwards they're the ones it's processing
+    own_list_reclaimed: AtomicBool,
 }
 
 impl RedisMessagesQueue {
@@ -22,8 +63,147 @@ impl RedisMessagesQueue {
         Self {
             redis,
             queue_name: channel_name.to_string(),
+            processing: None,
         }
     }
+
+    /// Messages popped with `pop_delivery` are kept in a processing list of the consumer until they're acked, so they
+    /// aren't lost if the consumer stops while processing them. It gives at-least-once delivery.
+    ///
+    /// Each process sharing the queue should have its own consumer name, as the messages of a consumer are reclaimed
+    /// when it starts, or by another consumer once it's been idle for `claim_min_idle`
+    pub fn new_reliable(
+        redis: Arc<Redis>,
+        channel_name: &str,
+        consumer_name: &str,
+        claim_min_idle: Duration,
+    ) -> Self {
+        Self {
+            redis,
+            queue_name: channel_name.to_string(),
+            processing: Some(Processing {
+                list_name: format!("{channel_name}:processing:{consumer_name}"),
+                consumers_name: format!("{channel_name}:consumers"),
+                consumer_name: consumer_name.to_string(),
+                claim_min_idle,
+                own_list_reclaimed: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    async fn get_connection(&self) -> MessagesQueueResult<deadpool_redis::Connection> {
+        self.redis
+            .get_async_connection()
+            .await
+            .ok_or("Failed to get a connection".to_string())
+    }
+
+    /// Pops a message waiting up to the timeout for one, or until there is one if it's not set
+    async fn pop_within<T: ProtocolMessage + Default>(
+        &self,
+        timeout: Option<Duration>,
+    ) -> MessagesQueueResult<Option<T>> {
+        let mut connection = self.get_connection().await?;
+
+        // it returns an array of [key = {channel_name}, value = message]
+        let result: Option<Vec<Vec<u8>>> = connection
+            .blpop(&self.queue_name, block_seconds(timeout))
+            .await
+            .map_err(|err| format!("Couldn't get an element from the messages queue: {err}"))?;
+        let Some(result) = result else {
+            return Ok(None);
+        };
+
+        T::decode(&*result[1])
+            .map(Some)
+            .map_err(|_| "Couldn't deserialize response as an return type".to_string())
+    }
+
+    async fn pop_delivery_within<T: ProtocolMessage + Default>(
+        &self,
+        timeout: Option<Duration>,
+    ) -> MessagesQueueResult<Option<Delivery<T>>> {
+        let Some(processing) = &self.processing else {
+            let message: Option<T> = self.pop_within(timeout).await?;
+            return Ok(message.map(|message| {
+                let receipt = message.encode_to_vec();
+                Delivery { message, receipt }
+            }));
+        };
+
+        let mut connection = self.get_connection().await?;
+        // the consumer is recorded as alive along with the pop, so its messages aren't reclaimed by another one
+        let (receipt,): (Option<Vec<u8>>,) = pipe()
+            .zadd(
+                &processing.consumers_name,
+                &processing.consumer_name,
+                unix_millis(),
+            )
+            .ignore()
+            .blmove(
+                &self.queue_name,
+                &processing.list_name,
+                Direction::Left,
+                Direction::Right,
+                block_seconds(timeout),
+            )
+            .query_async(&mut connection)
+            .await
+            .map_err(|err| format!("Couldn't get an element from the messages queue: {err}"))?;
+        let Some(receipt) = receipt else {
+            return Ok(None);
+        };
+
+        match T::decode(&*receipt) {
+            Ok(message) => Ok(Some(Delivery { message, receipt })),
+            Err(_) => {
+                // it would be reclaimed forever otherwise
+                let _ = connection
+                    .lrem::<_, _, ()>(&processing.list_name, 1, &receipt)
+                    .await;
+                Err("Couldn't deserialize response as an return type".to_string())
+            }
+        }
+    }
+
+    /// Moves the messages of a processing list back to the front of the queue, keeping the order in which they were
+    /// popped
+    async fn reclaim_list(
+        &self,
+        connection: &mut deadpool_redis::Connection,
+        list_name: &str,
+    ) -> MessagesQueueResult<usize> {
+        let mut reclaimed = 0;
+        loop {
+            let message: Option<Vec<u8>> = connection
+                .lmove(
+                    list_name,
+                    &self.queue_name,
+                    Direction::Right,
+                    Direction::Left,
+                )
+                .await
+                .map_err(|err| format!("Failed to send lmove command: {err}"))?;
+            if message.is_none() {
+                break;
+            }
+            reclaimed += 1;
+        }
+
+        Ok(reclaimed)
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Blocking commands take whole seconds, and 0 blocks until there is a message
+fn block_seconds(timeout: Option<Duration>) -> usize {
+    timeout.map_or(0, |timeout| timeout.as_secs_f64().ceil().max(1.0) as usize)
 }
 
 #[async_trait]
@@ -44,19 +224,80 @@ impl<T: ProtocolMessage + Default> MessagesQueue<T> for RedisMessagesQueue {
     }
 
     async fn pop(&self) -> MessagesQueueResult<T> {
-        let mut connection = self
-            .redis
-            .get_async_connection()
+        self.pop_within(None)
+            .await?
+            .ok_or("Couldn't get an element from the messages queue".to_string())
+    }
+
+    async fn pop_delivery(&self) -> MessagesQueueResult<Delivery<T>> {
+        self.pop_delivery_within(None)
+            .await?
+            .ok_or("Couldn't get an element from the messages queue".to_string())
+    }
+
+    async fn try_pop_delivery(
+        &self,
+        timeout: Duration,
+    ) -> MessagesQueueResult<Option<Delivery<T>>> {
+        self.pop_delivery_within(Some(timeout)).await
+    }
+
+    async fn ack(&self, delivery: &Delivery<T>) -> MessagesQueueResult<()> {
+        let Some(processing) = &self.processing else {
+            return Ok(());
+        };
+
+        let mut connection = self.get_connection().await?;
+        connection
+            .lrem(&processing.list_name, 1, &delivery.receipt)
             .await
-            .ok_or("Failed to get a connection")?;
+            .map_err(|err| format!("Failed to send lrem command: {err}"))
+    }
 
-        // it returns an array of [key = {channel_name}, value = message]
-        let result: Vec<Vec<u8>> = connection
-            .blpop(&self.queue_name, 0)
+    async fn nack(&self, delivery: &Delivery<T>) -> MessagesQueueResult<()> {
+        let mut connection = self.get_connection().await?;
+        // pushed before removing it from the processing list, so it isn't lost if the connection fails in between
+        connection
+            .rpush::<_, _, ()>(&self.queue_name, &delivery.receipt)
             .await
-            .map_err(|err| format!("Couldn't get an element from the messages queue: {err}"))?;
+            .map_err(|err| format!("Failed to send push command: {err}"))?;
 
-        T::decode(&*result[1])
-            .map_err(|_| "Couldn't deserialize response as an return type".to_string())
+        self.ack(delivery).await
+    }
+
+    async fn reclaim(&self) -> MessagesQueueResult<usize> {
+        let Some(processing) = &self.processing else {
+            return Ok(0);
+        };
+
+        let mut connection = self.get_connection().await?;
+        let mut reclaimed = 0;
+        if !processing.own_list_reclaimed.swap(true, Ordering::SeqCst) {
+            reclaimed += self
+                .reclaim_list(&mut connection, &processing.list_name)
+                .await?;
+        }
+
+        let now = unix_millis();
+        connection
+            .zadd::<_, _, _, ()>(&processing.consumers_name, &processing.consumer_name, now)
+            .await
+            .map_err(|err| format!("Failed to send zadd command: {err}"))?;
+
+        let idle_since = now.saturating_sub(processing.claim_min_idle.as_millis() as u64);
+        let idle_consumers: Vec<String> = connection
+            .zrangebyscore(&processing.consumers_name, "-inf", idle_since)
+            .await
+            .map_err(|err| format!("Failed to send zrangebyscore command: {err}"))?;
+        for consumer_name in idle_consumers {
+            let list_name = format!("{}:processing:{consumer_name}", self.queue_name);
+            reclaimed += self.reclaim_list(&mut connection, &list_name).await?;
+            connection
+                .zrem::<_, _, ()>(&processing.consumers_name, &consumer_name)
+                .await
+                .map_err(|err| format!("Failed to send zrem command: {err}"))?;
+        }
+
+        Ok(reclaimed)
     }
 }
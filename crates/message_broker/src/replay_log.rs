@@ -0,0 +1,129 @@
+use crate::{channel::ShardKey, redis::Redis};
+use deadpool_redis::redis::AsyncCommands;
+use quests_protocol::definitions::*;
+use std::sync::Arc;
+
+pub type ReplayLogResult<T> = Result<T, String>;
+
+/// Logs and sequences of keys without new messages are dropped after this time
+const LOG_TTL_SECONDS: usize = 60 * 60 * 24;
+
+/// Messages numbered in the order they were appended to the log of their key
+pub trait Sequenced: ShardKey {
+    fn sequence(&self) -> u64;
+    fn set_sequence(&mut self, sequence: u64);
+}
+
+impl Sequenced for UserUpdate {
+    fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+}
+
+/// Keeps the last messages of each key, so the ones missed while disconnected can be sent again
+pub struct RedisReplayLog {
+    redis: Arc<Redis>,
+    log_name: String,
+    max_size: u64,
+}
+
+impl RedisReplayLog {
+    pub fn new(redis: Arc<Redis>, log_name: &str, max_size: u64) -> Self {
+        Self {
+            redis,
+            log_name: log_name.to_string(),
+            max_size,
+        }
+    }
+
+    /// Assigns the next sequence of its key to the message and keeps it in the log, dropping the oldest ones
+    pub async fn append<T: ProtocolMessage + Sequenced>(
+        &self,
+        message: &mut T,
+    ) -> ReplayLogResult<u64> {
+        let mut connection = self.get_connection().await?;
+        let sequence_key = self.sequence_key(message.shard_key());
+        let sequence: u64 = connection
+            .incr(&sequence_key, 1)
+            .await
+            .map_err(|err| format!("Failed to send incr command: {err}"))?;
+        connection
+            .expire::<_, ()>(&sequence_key, LOG_TTL_SECONDS)
+            .await
+            .map_err(|err| format!("Failed to send expire command: {err}"))?;
+        message.set_sequence(sequence);
+
+        if self.max_size == 0 {
+            return Ok(sequence);
+        }
+
+        let log_key = self.log_key(message.shard_key());
+        connection
+            .zadd::<_, _, _, ()>(&log_key, message.encode_to_vec(), sequence)
+            .await
+            .map_err(|err| format!("Failed to send zadd command: {err}"))?;
+        if sequence > self.max_size {
+            connection
+                .zrembyscore::<_, _, _, ()>(&log_key, "-inf", sequence - self.max_size)
+                .await
+                .map_err(|err| format!("Failed to send zremrangebyscore command: {err}"))?;
+        }
+        connection
+            .expire::<_, ()>(&log_key, LOG_TTL_SECONDS)
+            .await
+            .map_err(|err| format!("Failed to send expire command: {err}"))?;
+
+        Ok(sequence)
+    }
+
+    /// Sequence of the last message appended for the key, 0 if there is none
+    pub async fn last_sequence(&self, key: &str) -> ReplayLogResult<u64> {
+        let mut connection = self.get_connection().await?;
+        let sequence: Option<u64> = connection
+            .get(self.sequence_key(key))
+            .await
+            .map_err(|err| format!("Failed to send get command: {err}"))?;
+
+        Ok(sequence.unwrap_or_default())
+    }
+
+    /// Messages still kept for the key that were appended after the given sequence, oldest first
+    pub async fn since<T: ProtocolMessage + Default>(
+        &self,
+        key: &str,
+        sequence: u64,
+    ) -> ReplayLogResult<Vec<T>> {
+        let mut connection = self.get_connection().await?;
+        let entries: Vec<Vec<u8>> = connection
+            .zrangebyscore(self.log_key(key), format!("({sequence}"), "+inf")
+            .await
+            .map_err(|err| format!("Failed to send zrangebyscore command: {err}"))?;
+
+        entries
+            .iter()
+            .map(|entry| {
+                T::decode(entry.as_slice())
+                    .map_err(|err| format!("Couldn't decode the logged message: {err}"))
+            })
+            .collect()
+    }
+
+    fn sequence_key(&self, key: &str) -> String {
+        format!("{}:sequence:{}", self.log_name, key.to_ascii_lowercase())
+    }
+
+    fn log_key(&self, key: &str) -> String {
+        format!("{}:replay:{}", self.log_name, key.to_ascii_lowercase())
+    }
+
+    async fn get_connection(&self) -> ReplayLogResult<deadpool_redis::Connection> {
+        self.redis
+            .get_async_connection()
+            .await
+            .ok_or("Failed to get a connection".to_string())
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{
+    messages_queue::{Delivery, MessagesQueue, MessagesQueueResult},
+    redis::Redis,
+};
+use async_trait::async_trait;
+use deadpool_redis::redis::{cmd, from_redis_value, pipe, FromRedisValue, RedisResult, Value};
+use quests_protocol::definitions::ProtocolMessage;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// Field of the stream entries holding the encoded message
+const MESSAGE_FIELD: &str = "message";
+
+/// How many pending entries are read at once when reclaiming them
+const RECLAIM_BATCH_SIZE: usize = 100;
+
+/// Entry as returned by `XREADGROUP` and `XCLAIM`: `[id, [field, value, ...]]`
+type StreamEntry = (String, Vec<Vec<u8>>);
+
+/// Arrays of arrays are parsed one by one, as a `Vec` of tuples is expected to be a flat array
+fn parse_array<T: FromRedisValue>(values: Vec<Value>) -> RedisResult<Vec<T>> {
+    values.iter().map(from_redis_value).collect()
+}
+
+/// Parses the `XREADGROUP` response: an array of `[stream, entries]`
+fn parse_read_entries(streams: Vec<Value>) -> RedisResult<Vec<StreamEntry>> {
+    let entries = parse_array::<(String, Vec<Value>)>(streams)?
+        .into_iter()
+        .map(|(_, entries)| parse_array::<StreamEntry>(entries))
+        .collect::<RedisResult<Vec<_>>>()?;
+    Ok(entries.into_iter().flatten().collect())
+}
+
+/// Events queue backed by a Redis Stream and a consumer group, so several processes can share the load.
+///
+/// Each consumer has its own pending list with the entries it read but didn't ack yet. `reclaim` takes the ones of
+/// this consumer on its first call, as it has just started, and claims the ones that have been idle for too long in
+/// other consumers. Reclaimed entries keep their place in the stream and are popped before the new ones, so they're
+/// processed before the events that came after them.
+pub struct RedisStreamsMessagesQueue {
+    redis: Arc<Redis>,
+    stream_name: String,
+    group_name: String,
+    consumer_name: String,
+    claim_min_idle: Duration,
+    /// The entries of this consumer are only reclaimed once, as afterwards they're the ones it's processing
+    own_entries_reclaimed: AtomicBool,
+    /// Entries reclaimed by this consumer, waiting to be popped
+    reclaimed: Mutex<VecDeque<StreamEntry>>,
+}
+
+impl RedisStreamsMessagesQueue {
+    /// Creates the consumer group if it doesn't exist yet
+    pub async fn new(
+        redis: Arc<Redis>,
+        stream_name: &str,
+        group_name: &str,
+        consumer_name: &str,
+        claim_min_idle: Duration,
+    ) -> MessagesQueueResult<Self> {
+        let queue = Self {
+            redis,
+            stream_name: stream_name.to_string(),
+            group_name: group_name.to_string(),
+            consumer_name: consumer_name.to_string(),
+            claim_min_idle,
+            own_entries_reclaimed: AtomicBool::new(false),
+            reclaimed: Mutex::new(VecDeque::new()),
+        };
+
+        let mut connection = queue.get_connection().await?;
+        // starting from the first entry, as the processed ones are deleted from the stream
+        let result: Result<(), _> = cmd("XGROUP")
+            .arg("CREATE")
+            .arg(&queue.stream_name)
+            .arg(&queue.group_name)
+            .arg("0")
+            .arg("MKSTREAM")
+            .query_async(&mut connection)
+            .await;
+        if let Err(err) = result {
+            if err.code() != Some("BUSYGROUP") {
+                return Err(format!("Couldn't create the consumer group: {err}"));
+            }
+        }
+
+        Ok(queue)
+    }
+
+    async fn get_connection(&self) -> MessagesQueueResult<deadpool_redis::Connection> {
+        self.redis
+            .get_async_connection()
+            .await
+            .ok_or("Failed to get a connection".to_string())
+    }
+
+    fn decode_entry<T: ProtocolMessage + Default>(
+        (id, fields): StreamEntry,
+    ) -> MessagesQueueResult<Delivery<T>> {
+        let message = fields
+            .chunks(2)
+            .find(|field| field[0] == MESSAGE_FIELD.as_bytes())
+            .and_then(|field| field.get(1))
+            .ok_or(format!("Stream entry {id} has no message"))?;
+
+        T::decode(message.as_slice())
+            .map(|message| Delivery {
+                message,
+                receipt: id.into_bytes(),
+            })
+            .map_err(|_| "Couldn't deserialize response as an return type".to_string())
+    }
+
+    /// Pops the first reclaimed entry, or reads a new one waiting up to the timeout for it, or until there is one if
+    /// it's not set
+    async fn pop_delivery_within<T: ProtocolMessage + Default>(
+        &self,
+        timeout: Option<Duration>,
+    ) -> MessagesQueueResult<Option<Delivery<T>>> {
+        let mut connection = self.get_connection().await?;
+        let reclaimed = self.reclaimed_entries().pop_front();
+        if let Some(entry) = reclaimed {
+            return self
+                .decode_or_remove(&mut connection, entry)
+                .await
+                .map(Some);
+        }
+
+        // it returns an array of [stream = {stream_name}, entries], or nil if there was no entry in time
+        let result: Option<Vec<Value>> = cmd("XREADGROUP")
+            .arg("GROUP")
+            .arg(&self.group_name)
+            .arg(&self.consumer_name)
+            .arg("COUNT")
+            .arg(1)
+            .arg("BLOCK")
+            .arg(timeout.map_or(0, |timeout| timeout.as_millis()) as u64)
+            .arg("STREAMS")
+            .arg(&self.stream_name)
+            .arg(">")
+            .query_async(&mut connection)
+            .await
+            .map_err(|err| format!("Couldn't get an element from the messages queue: {err}"))?;
+        let Some(result) = result else {
+            return Ok(None);
+        };
+
+        let entry = parse_read_entries(result)
+            .map_err(|err| format!("Couldn't get an element from the messages queue: {err}"))?
+            .into_iter()
+            .next()
+            .ok_or("Couldn't get an element from the messages queue".to_string())?;
+
+        self.decode_or_remove(&mut connection, entry)
+            .await
+            .map(Some)
+    }
+
+    async fn decode_or_remove<T: ProtocolMessage + Default>(
+        &self,
+        connection: &mut deadpool_redis::Connection,
+        entry: StreamEntry,
+    ) -> MessagesQueueResult<Delivery<T>> {
+        let id = entry.0.clone();
+        match Self::decode_entry(entry) {
+            Ok(delivery) => Ok(delivery),
+            Err(err) => {
+                // it would be reclaimed forever otherwise
+                let _ = self.remove_entry(connection, id.as_bytes()).await;
+                Err(err)
+            }
+        }
+    }
+
+    fn reclaimed_entries(&self) -> std::sync::MutexGuard<'_, VecDeque<StreamEntry>> {
+        self.reclaimed
+            .lock()
+            .expect("the reclaimed entries lock is never poisoned")
+    }
+
+    /// Reads the entries this consumer left pending before it stopped, in the order they were added
+    async fn read_own_pending_entries(
+        &self,
+        connection: &mut deadpool_redis::Connection,
+    ) -> MessagesQueueResult<Vec<StreamEntry>> {
+        let mut entries = vec![];
+        // the history of the consumer is read instead of the new entries when the ID isn't ">"
+        let mut last_id = "0".to_string();
+        loop {
+            let result: Option<Vec<Value>> = cmd("XREADGROUP")
+                .arg("GROUP")
+                .arg(&self.group_name)
+                .arg(&self.consumer_name)
+                .arg("COUNT")
+                .arg(RECLAIM_BATCH_SIZE)
+                .arg("STREAMS")
+                .arg(&self.stream_name)
+                .arg(&last_id)
+                .query_async(connection)
+                .await
+                .map_err(|err| format!("Failed to send xreadgroup command: {err}"))?;
+            let page = parse_read_entries(result.unwrap_or_default())
+                .map_err(|err| format!("Unexpected xreadgroup response: {err}"))?;
+            let Some((id, _)) = page.last() else {
+                break;
+            };
+            last_id = id.clone();
+            let page_len = page.len();
+            entries.extend(page);
+            if page_len < RECLAIM_BATCH_SIZE {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Claims the entries that have been idle for too long in other consumers, in the order they were added
+    async fn claim_idle_entries(
+        &self,
+        connection: &mut deadpool_redis::Connection,
+    ) -> MessagesQueueResult<Vec<StreamEntry>> {
+        let claim_min_idle = self.claim_min_idle.as_millis() as u64;
+        let mut entries = vec![];
+        let mut start = "-".to_string();
+        loop {
+            // [id, consumer, idle time in ms, times delivered]
+            let pending: Vec<Value> = cmd("XPENDING")
+                .arg(&self.stream_name)
+                .arg(&self.group_name)
+                .arg("IDLE")
+                .arg(claim_min_idle)
+                .arg(&start)
+                .arg("+")
+                .arg(RECLAIM_BATCH_SIZE)
+                .query_async(connection)
+                .await
+                .map_err(|err| format!("Failed to send xpending command: {err}"))?;
+            let pending = parse_array::<(String, String, u64, u64)>(pending)
+                .map_err(|err| format!("Unexpected xpending response: {err}"))?;
+            let Some((last_id, ..)) = pending.last() else {
+                break;
+            };
+            // the next page starts after the last entry of this one
+            start = format!("({last_id}");
+            let page_len = pending.len();
+
+            let ids = pending
+                .into_iter()
+                .filter(|(_, consumer, ..)| consumer != &self.consumer_name)
+                .map(|(id, ..)| id)
+                .collect::<Vec<_>>();
+            if !ids.is_empty() {
+                // the idle time is checked again, in case they were just delivered again, and if another consumer
+                // acked or claimed them in the meantime, they aren't returned
+                let claimed: Vec<Value> = cmd("XCLAIM")
+                    .arg(&self.stream_name)
+                    .arg(&self.group_name)
+                    .arg(&self.consumer_name)
+                    .arg(claim_min_idle)
+                    .arg(&ids)
+                    .query_async(connection)
+                    .await
+                    .map_err(|err| format!("Failed to send xclaim command: {err}"))?;
+                entries.extend(
+                    parse_array::<StreamEntry>(claimed)
+                        .map_err(|err| format!("Unexpected xclaim response: {err}"))?,
+                );
+            }
+
+            if page_len < RECLAIM_BATCH_SIZE {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Deletes the consumers that have been idle as long as it takes to claim their entries and have none pending, so
+    /// stopped ones don't pile up in the group. The idle time counts the reads that got nothing since Redis 7.2, so a
+    /// consumer that is still running isn't idle for that long, and if it were, it's added again on its next read
+    async fn delete_stopped_consumers(
+        &self,
+        connection: &mut deadpool_redis::Connection,
+    ) -> MessagesQueueResult<()> {
+        let consumers: Vec<Value> = cmd("XINFO")
+            .arg("CONSUMERS")
+            .arg(&self.stream_name)
+            .arg(&self.group_name)
+            .query_async(connection)
+            .await
+            .map_err(|err| format!("Failed to send xinfo command: {err}"))?;
+        let consumers = parse_array::<HashMap<String, Value>>(consumers)
+            .map_err(|err| format!("Unexpected xinfo response: {err}"))?;
+
+        let claim_min_idle = self.claim_min_idle.as_millis() as u64;
+        for consumer in consumers {
+            let field = |name: &str| consumer.get(name).cloned().unwrap_or(Value::Nil);
+            let (Ok(name), Ok(pending), Ok(idle)) = (
+                from_redis_value::<String>(&field("name")),
+                from_redis_value::<u64>(&field("pending")),
+                from_redis_value::<u64>(&field("idle")),
+            ) else {
+                continue;
+            };
+            if name == self.consumer_name || pending > 0 || idle < claim_min_idle {
+                continue;
+            }
+
+            cmd("XGROUP")
+                .arg("DELCONSUMER")
+                .arg(&self.stream_name)
+                .arg(&self.group_name)
+                .arg(&name)
+                .query_async::<_, ()>(connection)
+                .await
+                .map_err(|err| format!("Failed to send xgroup delconsumer command: {err}"))?;
+            log::info!("Deleted the consumer {name} of the stream, as it stopped");
+        }
+        Ok(())
+    }
+
+    /// Acks the entry and deletes it from the stream, so the stream only keeps the entries not processed yet
+    async fn remove_entry(
+        &self,
+        connection: &mut deadpool_redis::Connection,
+        id: &[u8],
+    ) -> MessagesQueueResult<()> {
+        pipe()
+            .cmd("XACK")
+            .arg(&self.stream_name)
+            .arg(&self.group_name)
+            .arg(id)
+            .ignore()
+            .cmd("XDEL")
+            .arg(&self.stream_name)
+            .arg(id)
+            .ignore()
+            .query_async(connection)
+            .await
+            .map_err(|err| format!("Failed to send xack command: {err}"))
+    }
+}
+
+#[async_trait]
+impl<T: ProtocolMessage + Default> MessagesQueue<T> for RedisStreamsMessagesQueue {
+    async fn push(&self, message: &T) -> MessagesQueueResult<usize> {
+        let mut connection = self.get_connection().await?;
+        let (_, queue_size): (String, usize) = pipe()
+            .cmd("XADD")
+            .arg(&self.stream_name)
+            .arg("*")
+            .arg(MESSAGE_FIELD)
+            .arg(message.encode_to_vec())
+            .cmd("XLEN")
+            .arg(&self.stream_name)
+            .query_async(&mut connection)
+            .await
+            .map_err(|err| format!("Failed to send xadd command: {err}"))?;
+
+        Ok(queue_size)
+    }
+
+    async fn pop(&self) -> MessagesQueueResult<T> {
+        let delivery = self.pop_delivery().await?;
+        self.ack(&delivery).await?;
+        Ok(delivery.message)
+    }
+
+    async fn pop_delivery(&self) -> MessagesQueueResult<Delivery<T>> {
+        self.pop_delivery_within(None)
+            .await?
+            .ok_or("Couldn't get an element from the messages queue".to_string())
+    }
+
+    async fn try_pop_delivery(
+        &self,
+        timeout: Duration,
+    ) -> MessagesQueueResult<Option<Delivery<T>>> {
+        // 0 would block until there is an entry
+        self.pop_delivery_within(Some(timeout.max(Duration::from_millis(1))))
+            .await
+    }
+
+    async fn ack(&self, delivery: &Delivery<T>) -> MessagesQueueResult<()> {
+        let mut connection = self.get_connection().await?;
+        self.remove_entry(&mut connection, &delivery.receipt).await
+    }
+
+    async fn nack(&self, delivery: &Delivery<T>) -> MessagesQueueResult<()> {
+        // added again before removing it, so it isn't lost if the connection fails in between
+        self.push(&delivery.message).await?;
+        self.ack(delivery).await
+    }
+
+    async fn reclaim(&self) -> MessagesQueueResult<usize> {
+        let mut connection = self.get_connection().await?;
+        let mut entries = vec![];
+        if !self.own_entries_reclaimed.swap(true, Ordering::SeqCst) {
+            entries.extend(self.read_own_pending_entries(&mut connection).await?);
+        }
+        // more entries are only claimed once the previous ones were popped, so they don't go idle waiting here
+        if entries.is_empty() && self.reclaimed_entries().is_empty() {
+            entries.extend(self.claim_idle_entries(&mut connection).await?);
+        }
+        self.delete_stopped_consumers(&mut connection).await?;
+
+        let reclaimed = entries.len();
+        self.reclaimed_entries().extend(entries);
+        Ok(reclaimed)
+    }
+}
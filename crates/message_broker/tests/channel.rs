@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use quests_message_broker::channel::{
+    shard_channel_name, RedisChannelPublisher, RedisShardedChannelSubscriber,
+};
+use quests_protocol::definitions::*;
+use tokio::sync::mpsc;
+
+mod common;
+use common::redis::build_redis;
+
+const SHARDS: u32 = 4;
+
+// a new channel on each run, so tests running at the same time don't receive each other's updates
+fn channel_name() -> String {
+    format!("quests:updates:{}", uuid::Uuid::new_v4())
+}
+
+fn build_update(user_address: &str) -> UserUpdate {
+    UserUpdate {
+        message: Some(user_update::Message::EventIgnored("event".to_string())),
+        user_address: user_address.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Returns an address that falls in a different shard than the given one
+fn address_in_another_shard(channel: &str, user_address: &str) -> String {
+    let shard = shard_channel_name(channel, SHARDS, user_address);
+    (0..)
+        .map(|i| format!("0x{i}"))
+        .find(|address| shard_channel_name(channel, SHARDS, address) != shard)
+        .unwrap()
+}
+
+#[test]
+fn shard_of_a_key_ignores_case() {
+    assert_eq!(shard_channel_name("updates", 1, "0xA"), "updates");
+    assert_eq!(
+        shard_channel_name("updates", SHARDS, "0xAbC"),
+        shard_channel_name("updates", SHARDS, "0xabc")
+    );
+}
+
+#[tokio::test]
+async fn only_receives_updates_for_the_listened_shards() {
+    let redis = build_redis(1).await;
+    let channel = channel_name();
+    let listened_address = "0xA";
+    let other_address = address_in_another_shard(&channel, listened_address);
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let subscriber =
+        RedisShardedChannelSubscriber::new(redis.clone(), &channel, SHARDS, move |update| {
+            let sender = sender.clone();
+            async move {
+                sender.send(update).unwrap();
+            }
+        });
+    let publisher = RedisChannelPublisher::new_sharded(redis, &channel, SHARDS);
+
+    subscriber.listen(listened_address).await.unwrap();
+
+    publisher.publish(build_update(&other_address)).await;
+    publisher.publish(build_update(listened_address)).await;
+
+    let received: UserUpdate = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received, build_update(listened_address));
+
+    subscriber.stop_listening(listened_address).await;
+    publisher.publish(build_update(listened_address)).await;
+
+    let received = tokio::time::timeout(Duration::from_millis(500), receiver.recv()).await;
+    assert!(received.is_err());
+}
+
+#[tokio::test]
+async fn keeps_listening_a_shard_while_a_key_needs_it() {
+    let redis = build_redis(1).await;
+    let channel = channel_name();
+    let user_address = "0xB";
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let subscriber =
+        RedisShardedChannelSubscriber::new(redis.clone(), &channel, SHARDS, move |update| {
+            let sender = sender.clone();
+            async move {
+                sender.send(update).unwrap();
+            }
+        });
+    let publisher = RedisChannelPublisher::new_sharded(redis, &channel, SHARDS);
+
+    // the user is connected twice and one of the connections is closed
+    subscriber.listen(user_address).await.unwrap();
+    subscriber.listen(user_address).await.unwrap();
+    subscriber.stop_listening(user_address).await;
+
+    publisher.publish(build_update(user_address)).await;
+
+    let received: UserUpdate = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received, build_update(user_address));
+}
@@ -0,0 +1,67 @@
+use quests_message_broker::dead_letters::{DeadLetter, DeadLetterQueue, RedisDeadLetterQueue};
+use quests_protocol::definitions::*;
+use quests_protocol::quests::*;
+
+mod common;
+use common::redis::build_redis;
+
+fn build_dead_letter(coordinates: Coordinates) -> DeadLetter<Event> {
+    let event = Event {
+        id: uuid::Uuid::new_v4().to_string(),
+        address: "0xA".to_string(),
+        action: Some(Action::location(coordinates)),
+        instance_id: None,
+    };
+    DeadLetter {
+        id: event.id.clone(),
+        message: event,
+        attempts: 3,
+        reason: "Failed".to_string(),
+        failed_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn can_record_failures() {
+    let redis = build_redis(14).await;
+    let dead_letters = RedisDeadLetterQueue::new(redis, "events:dead_letters");
+    let id = uuid::Uuid::new_v4().to_string();
+
+    assert_eq!(dead_letters.record_failure(&id).await, Ok(1));
+    assert_eq!(dead_letters.record_failure(&id).await, Ok(2));
+
+    // processing it resets the counter
+    dead_letters.clear_failures(&id).await.unwrap();
+    assert_eq!(dead_letters.record_failure(&id).await, Ok(1));
+    assert_eq!(dead_letters.record_failure(&id).await, Ok(2));
+
+    // moving it to the dead letters resets the counter
+    let mut dead_letter = build_dead_letter(Coordinates::new(0, 0));
+    dead_letter.id = id.clone();
+    dead_letters.push(&dead_letter).await.unwrap();
+    assert_eq!(dead_letters.record_failure(&id).await, Ok(1));
+}
+
+#[tokio::test]
+async fn can_list_and_take_dead_letters() {
+    let redis = build_redis(15).await;
+    // a new list on each run, as entries from previous runs are kept
+    let queue_name = format!("events:dead_letters:{}", uuid::Uuid::new_v4());
+    let dead_letters = RedisDeadLetterQueue::new(redis, &queue_name);
+
+    let first = build_dead_letter(Coordinates::new(0, 0));
+    let second = build_dead_letter(Coordinates::new(0, 1));
+    dead_letters.push(&first).await.unwrap();
+    dead_letters.push(&second).await.unwrap();
+
+    let listed: Vec<DeadLetter<Event>> = dead_letters.list().await.unwrap();
+    assert_eq!(listed, vec![first.clone(), second.clone()]);
+
+    let taken: Option<DeadLetter<Event>> = dead_letters.take(&second.id).await.unwrap();
+    assert_eq!(taken, Some(second.clone()));
+    let taken: Option<DeadLetter<Event>> = dead_letters.take(&second.id).await.unwrap();
+    assert_eq!(taken, None);
+
+    let listed: Vec<DeadLetter<Event>> = dead_letters.list().await.unwrap();
+    assert_eq!(listed, vec![first]);
+}
@@ -1,4 +1,6 @@
-use quests_message_broker::messages_queue::{MessagesQueue, RedisMessagesQueue};
+use std::time::Duration;
+
+use quests_message_broker::messages_queue::{Delivery, MessagesQueue, RedisMessagesQueue};
 use quests_protocol::definitions::*;
 use quests_protocol::quests::*;
 
@@ -12,6 +14,7 @@ fn build_location_event(coordinates: Coordinates) -> Event {
         id: uuid::Uuid::new_v4().to_string(),
         address: ADDRESS.to_string(),
         action: Some(Action::location(coordinates)),
+        instance_id: None,
     }
 }
 #[tokio::test]
@@ -83,3 +86,132 @@ async fn can_receive_multiple_events_from_the_queue() {
     let received_event = second_pop.unwrap();
     assert_eq!(second_event, received_event);
 }
+
+const CLAIM_MIN_IDLE: Duration = Duration::from_secs(60);
+
+#[tokio::test]
+async fn reliable_queue_keeps_events_until_acked() {
+    let redis = build_redis(1).await;
+    let events_queue = RedisMessagesQueue::new_reliable(
+        redis.clone(),
+        "events:acked_queue",
+        "consumer",
+        CLAIM_MIN_IDLE,
+    );
+
+    let event = build_location_event(Coordinates::new(0, 0));
+    let result = events_queue.push(&event).await;
+    assert!(result.is_ok(), "should be able to push events");
+
+    let delivery: Delivery<Event> = events_queue.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, event);
+
+    // a consumer that stopped before acking it
+    let events_queue = RedisMessagesQueue::new_reliable(
+        redis.clone(),
+        "events:acked_queue",
+        "consumer",
+        CLAIM_MIN_IDLE,
+    );
+    assert_eq!(MessagesQueue::<Event>::reclaim(&events_queue).await, Ok(1));
+
+    let delivery: Delivery<Event> = events_queue.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, event);
+    assert!(events_queue.ack(&delivery).await.is_ok());
+
+    assert_eq!(MessagesQueue::<Event>::reclaim(&events_queue).await, Ok(0));
+}
+
+#[tokio::test]
+async fn reliable_queue_pushes_back_nacked_events() {
+    let redis = build_redis(1).await;
+    let events_queue =
+        RedisMessagesQueue::new_reliable(redis, "events:nacked_queue", "consumer", CLAIM_MIN_IDLE);
+
+    let first_event = build_location_event(Coordinates::new(0, 0));
+    let second_event = build_location_event(Coordinates::new(0, 1));
+    events_queue.push(&first_event).await.unwrap();
+    events_queue.push(&second_event).await.unwrap();
+
+    let delivery: Delivery<Event> = events_queue.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, first_event);
+    assert!(events_queue.nack(&delivery).await.is_ok());
+
+    let delivery: Delivery<Event> = events_queue.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, second_event);
+    events_queue.ack(&delivery).await.unwrap();
+
+    let delivery: Delivery<Event> = events_queue.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, first_event);
+    events_queue.ack(&delivery).await.unwrap();
+
+    assert_eq!(MessagesQueue::<Event>::reclaim(&events_queue).await, Ok(0));
+}
+
+#[tokio::test]
+async fn try_pop_gives_up_when_the_queue_is_empty() {
+    let redis = build_redis(1).await;
+    let events_queue =
+        RedisMessagesQueue::new_reliable(redis, "events:empty_queue", "consumer", CLAIM_MIN_IDLE);
+
+    let delivery: Option<Delivery<Event>> = events_queue
+        .try_pop_delivery(Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert!(delivery.is_none());
+
+    let event = build_location_event(Coordinates::new(0, 0));
+    events_queue.push(&event).await.unwrap();
+
+    let delivery: Option<Delivery<Event>> = events_queue
+        .try_pop_delivery(Duration::from_secs(1))
+        .await
+        .unwrap();
+    let delivery = delivery.unwrap();
+    assert_eq!(delivery.message, event);
+    events_queue.ack(&delivery).await.unwrap();
+}
+
+#[tokio::test]
+async fn consumers_only_reclaim_their_own_or_idle_events() {
+    let redis = build_redis(1).await;
+    let queue_name = format!("events:shared_queue:{}", uuid::Uuid::new_v4());
+    let first_consumer =
+        RedisMessagesQueue::new_reliable(redis.clone(), &queue_name, "first", CLAIM_MIN_IDLE);
+    let second_consumer = RedisMessagesQueue::new_reliable(
+        redis.clone(),
+        &queue_name,
+        "second",
+        Duration::from_millis(500),
+    );
+    assert_eq!(
+        MessagesQueue::<Event>::reclaim(&first_consumer).await,
+        Ok(0)
+    );
+
+    let event = build_location_event(Coordinates::new(0, 0));
+    first_consumer.push(&event).await.unwrap();
+    let delivery: Delivery<Event> = first_consumer.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, event);
+
+    // the first consumer is alive, so its event is left alone
+    assert_eq!(
+        MessagesQueue::<Event>::reclaim(&second_consumer).await,
+        Ok(0)
+    );
+    // and it doesn't reclaim the events it's processing again
+    assert_eq!(
+        MessagesQueue::<Event>::reclaim(&first_consumer).await,
+        Ok(0)
+    );
+
+    // once it's idle for too long, its event is reclaimed by the second one
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        MessagesQueue::<Event>::reclaim(&second_consumer).await,
+        Ok(1)
+    );
+    let delivery: Delivery<Event> = second_consumer.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, event);
+    second_consumer.ack(&delivery).await.unwrap();
+}
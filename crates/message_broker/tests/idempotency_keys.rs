@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use quests_message_broker::idempotency_keys::RedisIdempotencyKeys;
+
+mod common;
+use common::redis::build_redis;
+
+// new keys on each run, as the reservations from previous runs are kept during the window
+fn keys_name() -> String {
+    format!("events:idempotency_keys:{}", uuid::Uuid::new_v4())
+}
+
+#[tokio::test]
+async fn a_key_is_only_reserved_once_per_scope() {
+    let redis = build_redis(1).await;
+    let idempotency_keys = RedisIdempotencyKeys::new(redis, &keys_name(), Duration::from_secs(60));
+
+    assert_eq!(idempotency_keys.reserve("0xA", "key", "1").await, Ok(None));
+    assert_eq!(
+        idempotency_keys.reserve("0xa", "key", "2").await,
+        Ok(Some("1".to_string()))
+    );
+    assert_eq!(idempotency_keys.reserve("0xB", "key", "3").await, Ok(None));
+}
+
+#[tokio::test]
+async fn a_released_key_can_be_reserved_again() {
+    let redis = build_redis(1).await;
+    let idempotency_keys = RedisIdempotencyKeys::new(redis, &keys_name(), Duration::from_secs(60));
+
+    assert_eq!(idempotency_keys.reserve("0xA", "key", "1").await, Ok(None));
+    idempotency_keys.release("0xA", "key", "1").await.unwrap();
+    assert_eq!(idempotency_keys.reserve("0xA", "key", "2").await, Ok(None));
+}
+
+#[tokio::test]
+async fn a_key_is_reserved_only_during_the_window() {
+    let redis = build_redis(1).await;
+    let idempotency_keys =
+        RedisIdempotencyKeys::new(redis, &keys_name(), Duration::from_millis(100));
+
+    assert_eq!(idempotency_keys.reserve("0xA", "key", "1").await, Ok(None));
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(idempotency_keys.reserve("0xA", "key", "2").await, Ok(None));
+}
+
+#[tokio::test]
+async fn a_pending_key_is_taken_over_once_timed_out() {
+    let redis = build_redis(1).await;
+    let idempotency_keys = RedisIdempotencyKeys::new(redis, &keys_name(), Duration::from_secs(60))
+        .with_pending_timeout(Duration::from_millis(100));
+
+    assert_eq!(idempotency_keys.reserve("0xA", "key", "1").await, Ok(None));
+    assert_eq!(
+        idempotency_keys.reserve("0xA", "key", "2").await,
+        Ok(Some("1".to_string()))
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(idempotency_keys.reserve("0xA", "key", "3").await, Ok(None));
+
+    // the process that reserved it first can't confirm nor release it anymore
+    assert!(idempotency_keys.confirm("0xA", "key", "1").await.is_err());
+    idempotency_keys.release("0xA", "key", "1").await.unwrap();
+    assert_eq!(
+        idempotency_keys.reserve("0xA", "key", "4").await,
+        Ok(Some("3".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn a_confirmed_key_is_not_taken_over() {
+    let redis = build_redis(1).await;
+    let idempotency_keys = RedisIdempotencyKeys::new(redis, &keys_name(), Duration::from_secs(60))
+        .with_pending_timeout(Duration::from_millis(100));
+
+    assert_eq!(idempotency_keys.reserve("0xA", "key", "1").await, Ok(None));
+    idempotency_keys.confirm("0xA", "key", "1").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(
+        idempotency_keys.reserve("0xA", "key", "2").await,
+        Ok(Some("1".to_string()))
+    );
+
+    // nor released, as its message was already sent
+    idempotency_keys.release("0xA", "key", "1").await.unwrap();
+    assert_eq!(
+        idempotency_keys.reserve("0xA", "key", "3").await,
+        Ok(Some("1".to_string()))
+    );
+}
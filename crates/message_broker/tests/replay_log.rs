@@ -0,0 +1,72 @@
+use quests_message_broker::channel::RedisChannelPublisher;
+use quests_message_broker::replay_log::RedisReplayLog;
+use quests_protocol::definitions::*;
+
+mod common;
+use common::redis::build_redis;
+
+// a new log on each run, as the sequences from previous runs are kept
+fn log_name() -> String {
+    format!("quests:updates:{}", uuid::Uuid::new_v4())
+}
+
+fn build_update(user_address: &str, event_id: &str) -> UserUpdate {
+    UserUpdate {
+        message: Some(user_update::Message::EventIgnored(event_id.to_string())),
+        user_address: user_address.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn sequences_are_assigned_per_user() {
+    let redis = build_redis(1).await;
+    let replay_log = RedisReplayLog::new(redis, &log_name(), 10);
+
+    let mut first_update = build_update("0xA", "1");
+    let mut second_update = build_update("0xa", "2");
+    let mut other_user_update = build_update("0xB", "3");
+    assert_eq!(replay_log.append(&mut first_update).await, Ok(1));
+    assert_eq!(replay_log.append(&mut second_update).await, Ok(2));
+    assert_eq!(replay_log.append(&mut other_user_update).await, Ok(1));
+    assert_eq!(second_update.sequence, 2);
+
+    assert_eq!(replay_log.last_sequence("0xA").await, Ok(2));
+    assert_eq!(replay_log.last_sequence("0xC").await, Ok(0));
+
+    let missed_updates: Vec<UserUpdate> = replay_log.since("0xA", 1).await.unwrap();
+    assert_eq!(missed_updates, vec![second_update]);
+}
+
+#[tokio::test]
+async fn only_keeps_the_last_updates() {
+    let redis = build_redis(1).await;
+    let replay_log = RedisReplayLog::new(redis, &log_name(), 2);
+
+    for event_id in ["1", "2", "3"] {
+        replay_log
+            .append(&mut build_update("0xA", event_id))
+            .await
+            .unwrap();
+    }
+
+    let missed_updates: Vec<UserUpdate> = replay_log.since("0xA", 0).await.unwrap();
+    let sequences: Vec<u64> = missed_updates
+        .iter()
+        .map(|update| update.sequence)
+        .collect();
+    assert_eq!(sequences, vec![2, 3]);
+}
+
+#[tokio::test]
+async fn published_updates_are_logged() {
+    let redis = build_redis(1).await;
+    let publisher = RedisChannelPublisher::new(redis, &log_name()).with_replay_log(10);
+
+    publisher.publish(build_update("0xA", "1")).await;
+
+    let replay_log = publisher.replay_log().unwrap();
+    let missed_updates: Vec<UserUpdate> = replay_log.since("0xA", 0).await.unwrap();
+    assert_eq!(missed_updates.len(), 1);
+    assert_eq!(missed_updates[0].sequence, 1);
+}
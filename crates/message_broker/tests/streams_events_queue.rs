@@ -0,0 +1,156 @@
+use std::{collections::HashMap, time::Duration};
+
+use deadpool_redis::redis::{cmd, from_redis_value, Value};
+use quests_message_broker::messages_queue::{Delivery, MessagesQueue};
+use quests_message_broker::streams_messages_queue::RedisStreamsMessagesQueue;
+use quests_protocol::definitions::*;
+use quests_protocol::quests::*;
+
+mod common;
+use common::redis::build_redis;
+
+const GROUP: &str = "events:processors";
+
+fn build_location_event(coordinates: Coordinates) -> Event {
+    Event {
+        id: uuid::Uuid::new_v4().to_string(),
+        address: "0xA".to_string(),
+        action: Some(Action::location(coordinates)),
+        instance_id: None,
+    }
+}
+
+// a new stream on each run, as entries from previous runs are kept
+fn stream_name() -> String {
+    format!("events:stream:{}", uuid::Uuid::new_v4())
+}
+
+#[tokio::test]
+async fn can_send_and_receive_events_from_the_stream() {
+    let redis = build_redis(1).await;
+    let stream = stream_name();
+    let events_queue = RedisStreamsMessagesQueue::new(redis, &stream, GROUP, "A", Duration::ZERO)
+        .await
+        .unwrap();
+
+    let first_event = build_location_event(Coordinates::new(0, 0));
+    let second_event = build_location_event(Coordinates::new(0, 1));
+    assert_eq!(events_queue.push(&first_event).await, Ok(1));
+    assert_eq!(events_queue.push(&second_event).await, Ok(2));
+
+    let received_event: Event = events_queue.pop().await.unwrap();
+    assert_eq!(received_event, first_event);
+
+    let delivery: Delivery<Event> = events_queue.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, second_event);
+    assert!(events_queue.ack(&delivery).await.is_ok());
+
+    let delivery: Option<Delivery<Event>> = events_queue
+        .try_pop_delivery(Duration::from_millis(100))
+        .await
+        .unwrap();
+    assert!(delivery.is_none());
+
+    assert_eq!(MessagesQueue::<Event>::reclaim(&events_queue).await, Ok(0));
+}
+
+#[tokio::test]
+async fn consumers_only_reclaim_their_own_or_idle_events() {
+    let redis = build_redis(1).await;
+    let stream = stream_name();
+    let idle = Duration::from_secs(60);
+    let consumer_a = RedisStreamsMessagesQueue::new(redis.clone(), &stream, GROUP, "A", idle)
+        .await
+        .unwrap();
+    let consumer_b = RedisStreamsMessagesQueue::new(redis.clone(), &stream, GROUP, "B", idle)
+        .await
+        .unwrap();
+
+    let event = build_location_event(Coordinates::new(0, 0));
+    consumer_a.push(&event).await.unwrap();
+    let delivery: Delivery<Event> = consumer_a.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, event);
+
+    // it's still being processed by A
+    assert_eq!(MessagesQueue::<Event>::reclaim(&consumer_b).await, Ok(0));
+
+    // A stopped before acking it and started again, so it processes it in place instead of adding it again
+    let consumer_a = RedisStreamsMessagesQueue::new(redis.clone(), &stream, GROUP, "A", idle)
+        .await
+        .unwrap();
+    assert_eq!(MessagesQueue::<Event>::reclaim(&consumer_a).await, Ok(1));
+    let delivery: Option<Delivery<Event>> = consumer_b
+        .try_pop_delivery(Duration::from_millis(100))
+        .await
+        .unwrap();
+    assert!(delivery.is_none());
+
+    let delivery: Delivery<Event> = consumer_a.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, event);
+
+    // A only reclaims its own entries on its first call, as afterwards it's processing them
+    assert_eq!(MessagesQueue::<Event>::reclaim(&consumer_a).await, Ok(0));
+
+    // A stopped again before acking it and C takes over once it's idle
+    let consumer_c =
+        RedisStreamsMessagesQueue::new(redis.clone(), &stream, GROUP, "C", Duration::ZERO)
+            .await
+            .unwrap();
+    assert_eq!(MessagesQueue::<Event>::reclaim(&consumer_c).await, Ok(1));
+
+    // the consumers without pending entries are deleted, as they're considered stopped
+    let mut connection = redis.get_async_connection().await.unwrap();
+    let consumers: Vec<HashMap<String, Value>> = cmd("XINFO")
+        .arg("CONSUMERS")
+        .arg(&stream)
+        .arg(GROUP)
+        .query_async(&mut connection)
+        .await
+        .unwrap();
+    let consumers = consumers
+        .iter()
+        .map(|consumer| from_redis_value::<String>(&consumer["name"]).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(consumers, vec!["C"]);
+
+    let delivery: Delivery<Event> = consumer_c.pop_delivery().await.unwrap();
+    assert_eq!(delivery.message, event);
+    consumer_c.ack(&delivery).await.unwrap();
+    assert_eq!(MessagesQueue::<Event>::reclaim(&consumer_c).await, Ok(0));
+}
+
+#[tokio::test]
+async fn reclaimed_events_are_popped_before_newer_ones() {
+    let redis = build_redis(1).await;
+    let stream = stream_name();
+    let consumer_a =
+        RedisStreamsMessagesQueue::new(redis.clone(), &stream, GROUP, "A", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+    // more than the entries read at once when reclaiming them
+    let events = (0..250)
+        .map(|x| build_location_event(Coordinates::new(x, 0)))
+        .collect::<Vec<_>>();
+    for event in &events {
+        consumer_a.push(event).await.unwrap();
+        let _: Delivery<Event> = consumer_a.pop_delivery().await.unwrap();
+    }
+    let newer_event = build_location_event(Coordinates::new(0, 1));
+    consumer_a.push(&newer_event).await.unwrap();
+
+    // A stopped before acking them
+    let consumer_b = RedisStreamsMessagesQueue::new(redis, &stream, GROUP, "B", Duration::ZERO)
+        .await
+        .unwrap();
+    assert_eq!(
+        MessagesQueue::<Event>::reclaim(&consumer_b).await,
+        Ok(events.len())
+    );
+
+    for event in events.iter().chain([&newer_event]) {
+        let delivery: Delivery<Event> = consumer_b.pop_delivery().await.unwrap();
+        assert_eq!(&delivery.message, event);
+        consumer_b.ack(&delivery).await.unwrap();
+    }
+}
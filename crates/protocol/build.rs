@@ -1,26 +1,44 @@
+use prost::Message;
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    ServiceDescriptorProto,
+};
 use reqwest::{header::USER_AGENT, Url};
 use std::{
+    collections::HashMap,
     env,
-    io::{Cursor, Result},
-    time::Duration,
+    io::{Cursor, Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    process::Command,
 };
 
 const DCL_PROTOCOL_REPO_URL: &str =
     "https://api.github.com/repos/decentraland/protocol/contents/proto/decentraland";
 const QUESTS_PROTO_PATH: &str = "/quests/definitions.proto";
-/// Modify this value to update the proto version, it is the commit sha from protocol repo used for downloading the proto file
+/// Modify this value to update the proto version, it is the commit sha from protocol repo used for downloading the proto file.
+///
+/// The proto of that version is kept in `upstream/`, and the build fails if `quests.proto` doesn't have every definition
+/// of it, so a new version can't be picked without bringing its changes to `quests.proto`
 const QUESTS_PROTOCOL_VERSION: &str = "03626d76db879afcdfd4fbcdc0342a04e5b4f663";
+const QUESTS_PACKAGE: &str = "decentraland.quests";
+/// Requests that may be replaced by a message whose fields are optional, as both are encoded the same when they're not set
+const EMPTY_REQUEST: &str = ".google.protobuf.Empty";
 
 fn main() -> Result<()> {
-    if should_download_proto() {
-        download_proto_from_github()?;
+    let upstream_proto_path = PathBuf::from(format!("upstream/{QUESTS_PROTOCOL_VERSION}.proto"));
+    if should_download_proto(&upstream_proto_path) {
+        download_proto_from_github(&upstream_proto_path)?;
     }
 
     // Tell Cargo that if the given file changes, to rerun this build script.
     println!("cargo:rerun-if-changed=quests.proto");
+    println!("cargo:rerun-if-changed={}", upstream_proto_path.display());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    let descriptor_set_path = out_dir.join("quests_descriptor_set.bin");
     let mut prost_build_config = prost_build::Config::new();
     prost_build_config.service_generator(Box::new(dcl_rpc::codegen::RPCServiceGenerator::new()));
     prost_build_config
+        .file_descriptor_set_path(&descriptor_set_path)
         .type_attribute(
             ".",
             "#[derive(serde::Serialize,serde::Deserialize,utoipa::ToSchema)]",
@@ -30,33 +48,214 @@ fn main() -> Result<()> {
             "definition",
             "#[serde(skip_serializing_if = \"Option::is_none\")]",
         )
+        // fields added after the first version of the definitions, so older payloads are still accepted
+        .field_attribute("Task.ordered", "#[serde(default)]")
+        .field_attribute("StepContent.actions_progress", "#[serde(default)]")
+        .field_attribute("Step.optional", "#[serde(default)]")
+        .field_attribute("Step.branch_group", "#[serde(default)]")
+        .field_attribute("QuestState.required_branch_groups", "#[serde(default)]")
+        .field_attribute("UserUpdate.sequence", "#[serde(default)]")
         .compile_protos(&["quests.proto"], &["./"])?;
 
+    let local = read_descriptor_set(&descriptor_set_path)?;
+    let upstream_descriptor_set_path = out_dir.join("upstream_descriptor_set.bin");
+    compile_descriptor_set(&upstream_proto_path, &upstream_descriptor_set_path)?;
+    let upstream = read_descriptor_set(&upstream_descriptor_set_path)?;
+
+    let missing = missing_upstream_definitions(&upstream, &local);
+    if !missing.is_empty() {
+        panic!(
+            "quests.proto doesn't match the protocol version {QUESTS_PROTOCOL_VERSION}:\n{}",
+            missing.join("\n")
+        );
+    }
+
     Ok(())
 }
 
-/// Avoid the GitHub Request if the file exists and has been modified in the last hour.
-/// It will return `true` if the file has not been modified in the last hour or doesn't exist.
-/// If the file has been modified within the last hour, the function will return `false`.
-fn should_download_proto() -> bool {
-    if let Ok(cwd) = env::current_dir() {
-        let path = cwd.join("./quests.proto");
-        if let Ok(metadata) = std::fs::metadata(path) {
-            if let Ok(modified) = metadata.modified() {
-                if modified
-                    .elapsed()
-                    .unwrap_or_else(|_| Duration::from_secs(0))
-                    < Duration::from_secs(3600)
+fn compile_descriptor_set(proto_path: &Path, descriptor_set_path: &Path) -> Result<()> {
+    let proto_dir = proto_path.parent().unwrap_or(Path::new("./"));
+    let mut protoc = Command::new(prost_build::protoc_from_env());
+    protoc
+        .arg("-I")
+        .arg(proto_dir)
+        .arg("--descriptor_set_out")
+        .arg(descriptor_set_path)
+        .arg(proto_path);
+    if let Some(include) = prost_build::protoc_include_from_env() {
+        protoc.arg("-I").arg(include);
+    }
+
+    let output = protoc.output()?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "protoc failed to compile {}: {}",
+                proto_path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn read_descriptor_set(path: &Path) -> Result<FileDescriptorSet> {
+    let bytes = std::fs::read(path)?;
+    FileDescriptorSet::decode(bytes.as_slice())
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err))
+}
+
+/// Definitions of the upstream proto that `quests.proto` doesn't have, or has with another number or type. Fields,
+/// messages and methods may only be added locally, and a request may only replace `google.protobuf.Empty`
+fn missing_upstream_definitions(
+    upstream: &FileDescriptorSet,
+    local: &FileDescriptorSet,
+) -> Vec<String> {
+    let quests_files = |set: &FileDescriptorSet| {
+        set.file
+            .iter()
+            .filter(|file| file.package() == QUESTS_PACKAGE)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let local_files = quests_files(local);
+
+    let mut local_messages = HashMap::new();
+    let mut local_enums = HashMap::new();
+    for file in &local_files {
+        collect_definitions(
+            QUESTS_PACKAGE,
+            &file.message_type,
+            &file.enum_type,
+            &mut local_messages,
+            &mut local_enums,
+        );
+    }
+    let local_services = local_files
+        .iter()
+        .flat_map(|file: &FileDescriptorProto| &file.service)
+        .map(|service| (service.name(), service))
+        .collect::<HashMap<_, _>>();
+
+    let mut missing = vec![];
+    for file in quests_files(upstream) {
+        let mut messages = HashMap::new();
+        let mut enums = HashMap::new();
+        collect_definitions(
+            QUESTS_PACKAGE,
+            &file.message_type,
+            &file.enum_type,
+            &mut messages,
+            &mut enums,
+        );
+
+        for (name, message) in messages {
+            let Some(local_message) = local_messages.get(&name) else {
+                missing.push(format!("message {name}"));
+                continue;
+            };
+            for field in &message.field {
+                let matches = local_message.field.iter().any(|local_field| {
+                    local_field.name() == field.name()
+                        && local_field.number() == field.number()
+                        && local_field.label() == field.label()
+                        && local_field.r#type() == field.r#type()
+                        && local_field.type_name() == field.type_name()
+                });
+                if !matches {
+                    missing.push(format!(
+                        "field {name}.{} = {}",
+                        field.name(),
+                        field.number()
+                    ));
+                }
+            }
+        }
+
+        for (name, r#enum) in enums {
+            let Some(local_enum) = local_enums.get(&name) else {
+                missing.push(format!("enum {name}"));
+                continue;
+            };
+            for value in &r#enum.value {
+                if !local_enum
+                    .value
+                    .iter()
+                    .any(|local_value| local_value == value)
                 {
-                    return false;
+                    missing.push(format!(
+                        "value {name}.{} = {}",
+                        value.name(),
+                        value.number()
+                    ));
                 }
             }
         }
+
+        for service in &file.service {
+            let Some(local_service) = local_services.get(service.name()) else {
+                missing.push(format!("service {}", service.name()));
+                continue;
+            };
+            missing.extend(missing_methods(service, local_service));
+        }
+    }
+    missing
+}
+
+fn missing_methods(
+    service: &ServiceDescriptorProto,
+    local_service: &ServiceDescriptorProto,
+) -> Vec<String> {
+    service
+        .method
+        .iter()
+        .filter(|method| {
+            !local_service.method.iter().any(|local_method| {
+                local_method.name() == method.name()
+                    && (local_method.input_type() == method.input_type()
+                        || method.input_type() == EMPTY_REQUEST)
+                    && local_method.output_type() == method.output_type()
+                    && local_method.client_streaming() == method.client_streaming()
+                    && local_method.server_streaming() == method.server_streaming()
+            })
+        })
+        .map(|method| format!("rpc {}.{}", service.name(), method.name()))
+        .collect()
+}
+
+/// Indexes the messages and enums by their full name, including the nested ones
+fn collect_definitions(
+    scope: &str,
+    messages: &[DescriptorProto],
+    enums: &[EnumDescriptorProto],
+    messages_by_name: &mut HashMap<String, DescriptorProto>,
+    enums_by_name: &mut HashMap<String, EnumDescriptorProto>,
+) {
+    for r#enum in enums {
+        enums_by_name.insert(format!("{scope}.{}", r#enum.name()), r#enum.clone());
+    }
+    for message in messages {
+        let name = format!("{scope}.{}", message.name());
+        collect_definitions(
+            &name,
+            &message.nested_type,
+            &message.enum_type,
+            messages_by_name,
+            enums_by_name,
+        );
+        messages_by_name.insert(name, message.clone());
     }
-    true
 }
 
-fn download_proto_from_github() -> Result<()> {
+/// Avoid the GitHub Request if the proto of the pinned version was already downloaded.
+/// It will return `true` only if the file doesn't exist.
+fn should_download_proto(proto_path: &Path) -> bool {
+    !proto_path.exists()
+}
+
+fn download_proto_from_github(proto_path: &Path) -> Result<()> {
     let client = reqwest::blocking::Client::new();
 
     let file_url = build_github_url_to_download();
@@ -65,13 +264,13 @@ fn download_proto_from_github() -> Result<()> {
     let content_url = extract_file_url(file_metadata);
     let content = download_file(client, content_url);
 
-    save_content_to_file(content)
+    save_content_to_file(content, proto_path)
 }
 
-fn save_content_to_file(content: reqwest::blocking::Response) -> Result<()> {
-    let cwd = env::current_dir()?;
-
-    let file_path: String = String::from(cwd.to_string_lossy()) + "/" + "quests.proto";
+fn save_content_to_file(content: reqwest::blocking::Response, file_path: &Path) -> Result<()> {
+    if let Some(dir) = file_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
     // Create destination file
     let mut file = std::fs::File::create(file_path)?;
     let inner = match content.bytes() {
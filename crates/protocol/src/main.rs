@@ -25,7 +25,10 @@ fn main() {
                             Action::location(Coordinates::new(10, 10)),
                             Action::jump(Coordinates::new(10, 11)),
                         ],
+                        ordered: false,
                     }],
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "A2".to_string(),
@@ -34,7 +37,10 @@ fn main() {
                         id: "A2_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::npc_interaction("NPC_IDEN")],
+                        ordered: false,
                     }],
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -43,7 +49,10 @@ fn main() {
                         id: "B_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::jump(Coordinates::new(20, 10))],
+                        ordered: false,
                     }],
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -52,7 +61,10 @@ fn main() {
                         id: "C_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::jump(Coordinates::new(20, 20))],
+                        ordered: false,
                     }],
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -61,7 +73,10 @@ fn main() {
                         id: "D_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::npc_interaction("OTHER_NPC")],
+                        ordered: false,
                     }],
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         }),
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use crate::quests::QuestValidationError;
+
+pub const LOCATION: &str = "LOCATION";
+pub const JUMP: &str = "JUMP";
+pub const EMOTE: &str = "EMOTE";
+pub const CUSTOM: &str = "CUSTOM";
+pub const NPC_INTERACTION: &str = "NPC_INTERACTION";
+
+pub const X_PARAMETER: &str = "x";
+pub const Y_PARAMETER: &str = "y";
+pub const ID_PARAMETER: &str = "id";
+pub const NPC_ID_PARAMETER: &str = "npc_id";
+
+/// Genesis City parcel bounds, both ends included
+pub const MIN_PARCEL_X: isize = -150;
+pub const MAX_PARCEL_X: isize = 163;
+pub const MIN_PARCEL_Y: isize = -150;
+pub const MAX_PARCEL_Y: isize = 158;
+
+/// Kind of value expected for an action parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterKind {
+    Text,
+    /// Coordinate of a parcel, it has to be an integer between `min` and `max`
+    Coordinate {
+        min: isize,
+        max: isize,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ActionParameter {
+    pub name: &'static str,
+    pub kind: ParameterKind,
+}
+
+/// Schema of the parameters an action type needs
+#[derive(Debug, PartialEq, Eq)]
+pub struct ActionType {
+    pub name: &'static str,
+    /// Parameters that have to be present for the action to be valid
+    pub parameters: &'static [ActionParameter],
+    /// Whether the action accepts parameters not defined in the schema. These actions need at least one parameter
+    pub free_parameters: bool,
+}
+
+const X: ActionParameter = ActionParameter {
+    name: X_PARAMETER,
+    kind: ParameterKind::Coordinate {
+        min: MIN_PARCEL_X,
+        max: MAX_PARCEL_X,
+    },
+};
+const Y: ActionParameter = ActionParameter {
+    name: Y_PARAMETER,
+    kind: ParameterKind::Coordinate {
+        min: MIN_PARCEL_Y,
+        max: MAX_PARCEL_Y,
+    },
+};
+
+/// All the action types supported in quest definitions
+pub const ACTION_TYPES: &[ActionType] = &[
+    ActionType {
+        name: LOCATION,
+        parameters: &[X, Y],
+        free_parameters: false,
+    },
+    ActionType {
+        name: JUMP,
+        parameters: &[X, Y],
+        free_parameters: false,
+    },
+    ActionType {
+        name: EMOTE,
+        parameters: &[
+            X,
+            Y,
+            ActionParameter {
+                name: ID_PARAMETER,
+                kind: ParameterKind::Text,
+            },
+        ],
+        free_parameters: false,
+    },
+    ActionType {
+        name: CUSTOM,
+        parameters: &[],
+        free_parameters: true,
+    },
+    ActionType {
+        name: NPC_INTERACTION,
+        parameters: &[ActionParameter {
+            name: NPC_ID_PARAMETER,
+            kind: ParameterKind::Text,
+        }],
+        free_parameters: false,
+    },
+];
+
+/// Returns the schema of the action type with the given name, if it's supported
+pub fn get_action_type(name: &str) -> Option<&'static ActionType> {
+    ACTION_TYPES
+        .iter()
+        .find(|action_type| action_type.name == name)
+}
+
+impl ActionType {
+    /// Checks the given parameters against the schema of the action type. Parameters not defined in the schema are
+    /// ignored, as existing quests may carry them
+    pub fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), QuestValidationError> {
+        let error = || QuestValidationError::ActionItemParametersNotValid(self.name.to_string());
+
+        if self.free_parameters && parameters.is_empty() {
+            return Err(error());
+        }
+
+        for parameter in self.parameters {
+            let Some(value) = parameters.get(parameter.name) else {
+                return Err(error());
+            };
+            match parameter.kind {
+                ParameterKind::Text if value.is_empty() => return Err(error()),
+                ParameterKind::Text => {}
+                ParameterKind::Coordinate { min, max } => {
+                    let Ok(coordinate) = value.trim().parse::<isize>() else {
+                        return Err(error());
+                    };
+                    if !(min..=max).contains(&coordinate) {
+                        return Err(QuestValidationError::ActionItemCoordinateOutOfBounds(
+                            self.name.to_string(),
+                            parameter.name.to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the kind of the parameter with the given name, if it's defined in the schema
+    pub fn parameter_kind(&self, name: &str) -> Option<ParameterKind> {
+        self.parameters
+            .iter()
+            .find(|parameter| parameter.name == name)
+            .map(|parameter| parameter.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{definitions::Action, quests::Coordinates};
+
+    #[test]
+    fn builders_match_action_types() {
+        let actions = [
+            Action::location(Coordinates::new(10, -20)),
+            Action::jump(Coordinates::new(10, 20)),
+            Action::emote(Coordinates::new(10, 20), "wave"),
+            Action::custom("my_id"),
+            Action::npc_interaction("npc"),
+        ];
+        for action in actions {
+            let action_type = get_action_type(&action.r#type).unwrap();
+            assert!(action_type.validate_parameters(&action.parameters).is_ok());
+        }
+        assert_eq!(ACTION_TYPES.len(), 5);
+    }
+
+    #[test]
+    fn validate_parameters_works() {
+        let location = get_action_type(LOCATION).unwrap();
+        let mut parameters = HashMap::from([
+            (X_PARAMETER.to_string(), "10".to_string()),
+            (Y_PARAMETER.to_string(), "ten".to_string()),
+        ]);
+        assert_eq!(
+            location.validate_parameters(&parameters).unwrap_err(),
+            QuestValidationError::ActionItemParametersNotValid(LOCATION.to_string())
+        );
+        parameters.insert(Y_PARAMETER.to_string(), "-10".to_string());
+        assert!(location.validate_parameters(&parameters).is_ok());
+        parameters.insert(X_PARAMETER.to_string(), "164".to_string());
+        assert_eq!(
+            location.validate_parameters(&parameters).unwrap_err(),
+            QuestValidationError::ActionItemCoordinateOutOfBounds(
+                LOCATION.to_string(),
+                X_PARAMETER.to_string()
+            )
+        );
+        parameters.insert(X_PARAMETER.to_string(), "-150".to_string());
+        assert!(location.validate_parameters(&parameters).is_ok());
+
+        let npc_interaction = get_action_type(NPC_INTERACTION).unwrap();
+        assert_eq!(
+            npc_interaction
+                .validate_parameters(&HashMap::new())
+                .unwrap_err(),
+            QuestValidationError::ActionItemParametersNotValid(NPC_INTERACTION.to_string())
+        );
+        let mut parameters = HashMap::from([(NPC_ID_PARAMETER.to_string(), "npc".to_string())]);
+        assert!(npc_interaction.validate_parameters(&parameters).is_ok());
+        parameters.insert("extra".to_string(), "1".to_string());
+        assert!(npc_interaction.validate_parameters(&parameters).is_ok());
+
+        let custom = get_action_type(CUSTOM).unwrap();
+        assert!(custom.validate_parameters(&HashMap::new()).is_err());
+        assert!(custom
+            .validate_parameters(&HashMap::from([("any".to_string(), "1".to_string())]))
+            .is_ok());
+
+        assert!(get_action_type("UNKNOWN").is_none());
+    }
+}
@@ -2,13 +2,10 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{definitions::*, quests::Quest};
-
-const LOCATION: &str = "LOCATION";
-const JUMP: &str = "JUMP";
-const NPC_INTERACTION: &str = "NPC_INTERACTION";
-const CUSTOM: &str = "CUSTOM";
-pub(crate) const EMOTE: &str = "EMOTE";
+use crate::{
+    definitions::*,
+    quests::{action_types::*, Quest},
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Coordinates {
@@ -23,57 +20,70 @@ impl Coordinates {
 }
 
 impl Action {
+    /// Sets how many times the action has to be done to complete it as an action item of a task
+    pub fn times(self, count: u32) -> Self {
+        Self {
+            count: Some(count),
+            ..self
+        }
+    }
+
     pub fn location(coords: Coordinates) -> Self {
         let parameters = HashMap::from_iter([
-            ("x".to_string(), coords.x.to_string()),
-            ("y".to_string(), coords.y.to_string()),
+            (X_PARAMETER.to_string(), coords.x.to_string()),
+            (Y_PARAMETER.to_string(), coords.y.to_string()),
         ]);
 
         Self {
             r#type: LOCATION.to_string(),
             parameters,
+            count: None,
         }
     }
 
     pub fn jump(coords: Coordinates) -> Self {
         let parameters = HashMap::from_iter([
-            ("x".to_string(), coords.x.to_string()),
-            ("y".to_string(), coords.y.to_string()),
+            (X_PARAMETER.to_string(), coords.x.to_string()),
+            (Y_PARAMETER.to_string(), coords.y.to_string()),
         ]);
 
         Self {
             r#type: JUMP.to_string(),
             parameters,
+            count: None,
         }
     }
 
     pub fn emote(coords: Coordinates, emote_id: &str) -> Self {
         let parameters = HashMap::from_iter([
-            ("x".to_string(), coords.x.to_string()),
-            ("y".to_string(), coords.y.to_string()),
-            ("id".to_string(), emote_id.to_string()),
+            (X_PARAMETER.to_string(), coords.x.to_string()),
+            (Y_PARAMETER.to_string(), coords.y.to_string()),
+            (ID_PARAMETER.to_string(), emote_id.to_string()),
         ]);
 
         Self {
             r#type: EMOTE.to_string(),
             parameters,
+            count: None,
         }
     }
 
     pub fn custom(id: &str) -> Self {
-        let parameters = HashMap::from_iter([("id".to_string(), id.to_string())]);
+        let parameters = HashMap::from_iter([(ID_PARAMETER.to_string(), id.to_string())]);
         Self {
             r#type: CUSTOM.to_string(),
             parameters,
+            count: None,
         }
     }
 
     pub fn npc_interaction(npc_id: &str) -> Self {
-        let parameters = HashMap::from_iter([("npc_id".to_string(), npc_id.to_string())]);
+        let parameters = HashMap::from_iter([(NPC_ID_PARAMETER.to_string(), npc_id.to_string())]);
 
         Self {
             r#type: NPC_INTERACTION.to_string(),
             parameters,
+            count: None,
         }
     }
 }
@@ -106,6 +116,18 @@ impl StartQuestResponse {
         ))
     }
 
+    pub fn quest_not_repeatable(available_at: Option<u32>) -> Self {
+        Self::response(start_quest_response::Response::QuestNotRepeatable(
+            QuestNotRepeatable { available_at },
+        ))
+    }
+
+    pub fn prerequisites_not_completed(quest_ids: Vec<String>) -> Self {
+        Self::response(start_quest_response::Response::PrerequisitesNotCompleted(
+            PrerequisitesNotCompleted { quest_ids },
+        ))
+    }
+
     pub fn internal_server_error() -> Self {
         Self::response(start_quest_response::Response::InternalServerError(
             InternalServerError {},
@@ -1,6 +1,10 @@
 use crate::definitions::*;
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::Hash,
+};
 
+use super::action_types::{get_action_type, ParameterKind};
 use super::*;
 use daggy::{
     self,
@@ -14,6 +18,8 @@ use daggy::{
 pub struct QuestGraph {
     graph: Dag<String, u32>,
     pub tasks_by_step: HashMap<StepID, Vec<Task>>,
+    optional_steps: HashSet<StepID>,
+    branch_group_by_step: HashMap<StepID, String>,
 }
 
 impl QuestGraph {
@@ -52,8 +58,43 @@ impl QuestGraph {
     }
 
     /// Returns steps required for the end of the quests. It returns the steps that directly point to the END, not all the path
+    ///
+    /// Optional steps and steps that are part of a branch group are not included
     pub fn required_for_end(&self) -> Option<Vec<StepID>> {
-        self.prev(END_STEP_ID)
+        let mut steps = self.prev(END_STEP_ID)?;
+        steps.retain(|step| {
+            !self.optional_steps.contains(step) && !self.branch_group_by_step.contains_key(step)
+        });
+        steps.sort();
+        steps.dedup();
+        Some(steps)
+    }
+
+    /// Returns the branch groups pointing to the END. At least one step of each group is required for the end of the quest
+    pub fn required_branch_groups_for_end(&self) -> Option<Vec<BranchGroup>> {
+        let steps = self.prev(END_STEP_ID)?;
+        let mut groups: BTreeMap<&String, Vec<StepID>> = BTreeMap::new();
+        for step in steps {
+            if self.optional_steps.contains(&step) {
+                continue;
+            }
+            if let Some(group) = self.branch_group_by_step.get(&step) {
+                let group_steps = groups.entry(group).or_default();
+                if !group_steps.contains(&step) {
+                    group_steps.push(step);
+                }
+            }
+        }
+
+        Some(
+            groups
+                .into_iter()
+                .map(|(id, steps)| BranchGroup {
+                    id: id.clone(),
+                    steps,
+                })
+                .collect(),
+        )
     }
 
     pub fn total_steps(&self) -> usize {
@@ -79,9 +120,24 @@ impl QuestGraph {
 
 impl From<&Quest> for QuestGraph {
     fn from(value: &Quest) -> Self {
+        let steps = value
+            .definition
+            .as_ref()
+            .map(|definition| definition.steps.as_slice())
+            .unwrap_or_default();
         Self {
             graph: build_graph_from_quest_definition(value),
             tasks_by_step: build_tasks_by_step_from_quest_definition(value),
+            optional_steps: steps
+                .iter()
+                .filter(|step| step.optional)
+                .map(|step| step.id.clone())
+                .collect(),
+            branch_group_by_step: steps
+                .iter()
+                .filter(|step| !step.branch_group.is_empty())
+                .map(|step| (step.id.clone(), step.branch_group.clone()))
+                .collect(),
         }
     }
 }
@@ -92,11 +148,15 @@ fn build_graph_from_quest_definition(quest: &Quest) -> Dag<String, u32> {
         id: START_STEP_ID.to_string(),
         description: "COMMON START NODE".to_string(),
         tasks: vec![],
+        optional: false,
+        branch_group: String::new(),
     };
     let ending_step = Step {
         id: END_STEP_ID.to_string(),
         description: "COMMON END NODE".to_string(),
         tasks: vec![],
+        optional: false,
+        branch_group: String::new(),
     };
 
     let start_node = dag.add_node(starting_step.id);
@@ -178,13 +238,22 @@ pub fn matches_action(action: &Action, other_action: &Action) -> bool {
         return false;
     };
 
+    let action_type = get_action_type(&action.r#type.to_ascii_uppercase());
     for (key, value) in &action.parameters {
         let other_value = if let Some(other_value) = other_action.parameters.get(key) {
             other_value
         } else {
             return false;
         };
-        if !value.eq_ignore_ascii_case(other_value) {
+        let is_coordinate = matches!(
+            action_type.and_then(|action_type| action_type.parameter_kind(key)),
+            Some(ParameterKind::Coordinate { .. })
+        );
+        if is_coordinate {
+            if !coordinates_match(value, other_value) {
+                return false;
+            }
+        } else if !value.eq_ignore_ascii_case(other_value) {
             return false;
         }
     }
@@ -192,9 +261,21 @@ pub fn matches_action(action: &Action, other_action: &Action) -> bool {
     true
 }
 
+/// Compares coordinates as numbers so "010" and "10" are the same, falling back to the string comparison if they are not numbers
+fn coordinates_match(coordinate: &str, other_coordinate: &str) -> bool {
+    match (
+        coordinate.trim().parse::<isize>(),
+        other_coordinate.trim().parse::<isize>(),
+    ) {
+        (Ok(coordinate), Ok(other_coordinate)) => coordinate == other_coordinate,
+        _ => coordinate.eq_ignore_ascii_case(other_coordinate),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::builders::{Coordinates, EMOTE};
+    use super::action_types::EMOTE;
+    use super::builders::Coordinates;
     use super::*;
 
     #[test]
@@ -218,7 +299,10 @@ mod tests {
                             id: "A_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B".to_string(),
@@ -227,7 +311,10 @@ mod tests {
                             id: "B_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "C".to_string(),
@@ -236,7 +323,10 @@ mod tests {
                             id: "C_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "D".to_string(),
@@ -245,7 +335,10 @@ mod tests {
                             id: "D_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                 ],
             }),
@@ -289,7 +382,10 @@ mod tests {
                             id: "A1_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "A2".to_string(),
@@ -298,7 +394,10 @@ mod tests {
                             id: "A2_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B".to_string(),
@@ -307,7 +406,10 @@ mod tests {
                             id: "B_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "C".to_string(),
@@ -316,7 +418,10 @@ mod tests {
                             id: "C_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "D".to_string(),
@@ -325,7 +430,10 @@ mod tests {
                             id: "D_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                 ],
             }),
@@ -381,7 +489,10 @@ mod tests {
                             id: "A_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B1".to_string(),
@@ -390,7 +501,10 @@ mod tests {
                             id: "B_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B2".to_string(),
@@ -399,7 +513,10 @@ mod tests {
                             id: "B_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B3".to_string(),
@@ -408,7 +525,10 @@ mod tests {
                             id: "B_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "C".to_string(),
@@ -417,7 +537,10 @@ mod tests {
                             id: "C_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "D".to_string(),
@@ -426,7 +549,10 @@ mod tests {
                             id: "D_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                 ],
             }),
@@ -478,7 +604,10 @@ mod tests {
                             id: "A1_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "A2".to_string(),
@@ -487,7 +616,10 @@ mod tests {
                             id: "A2_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B".to_string(),
@@ -496,7 +628,10 @@ mod tests {
                             id: "B_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "C".to_string(),
@@ -505,7 +640,10 @@ mod tests {
                             id: "C_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "D".to_string(),
@@ -514,7 +652,10 @@ mod tests {
                             id: "D_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                 ],
             }),
@@ -552,7 +693,10 @@ mod tests {
                             id: "A1_1".to_string(),
                             description: "A1_1".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "A2".to_string(),
@@ -561,7 +705,10 @@ mod tests {
                             id: "A2_1".to_string(),
                             description: "A_2 desc".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B".to_string(),
@@ -570,7 +717,10 @@ mod tests {
                             id: "B_1".to_string(),
                             description: "B_1 desc".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "C".to_string(),
@@ -579,7 +729,10 @@ mod tests {
                             id: "C_1".to_string(),
                             description: "C_1 desc".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "D".to_string(),
@@ -588,7 +741,10 @@ mod tests {
                             id: "D_1".to_string(),
                             description: "D_1 desc".to_string(),
                             action_items: vec![],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                 ],
             }),
@@ -602,6 +758,52 @@ mod tests {
         assert!(steps_required_for_end.contains(&"C".to_string()));
     }
 
+    #[test]
+    fn quest_graph_steps_required_for_end_with_optional_steps_and_branch_groups() {
+        let step = |id: &str, optional: bool, branch_group: &str| Step {
+            id: id.to_string(),
+            description: format!("{id} desc"),
+            tasks: vec![Task {
+                id: format!("{id}_1"),
+                description: format!("{id}_1 desc"),
+                action_items: vec![],
+                ordered: false,
+            }],
+            optional,
+            branch_group: branch_group.to_string(),
+        };
+        let quest = Quest {
+            id: "1e9a8bbf-2223-4f51-b7e5-660d35cedef4".to_string(),
+            name: "CUSTOM_QUEST".to_string(),
+            description: "".to_string(),
+            creator_address: "0xB".to_string(),
+            definition: Some(QuestDefinition {
+                connections: vec![
+                    Connection::new("A", "B1"),
+                    Connection::new("A", "B2"),
+                    Connection::new("A", "C"),
+                ],
+                steps: vec![
+                    step("A", false, ""),
+                    step("B1", false, "B"),
+                    step("B2", false, "B"),
+                    step("C", true, ""),
+                ],
+            }),
+            ..Default::default()
+        };
+
+        assert!(quest.is_valid().is_ok());
+        let graph = QuestGraph::from(&quest);
+        assert!(graph.required_for_end().unwrap().is_empty());
+        let groups = graph.required_branch_groups_for_end().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id, "B");
+        assert!(groups[0].steps.contains(&"B1".to_string()));
+        assert!(groups[0].steps.contains(&"B2".to_string()));
+        assert!(!groups[0].steps.contains(&"C".to_string()));
+    }
+
     #[test]
     fn matches_action_works() {
         let result = matches_action(
@@ -643,6 +845,19 @@ mod tests {
         other_action.r#type = EMOTE.to_string().to_lowercase();
         let result = matches_action(&Action::emote(Coordinates::new(1, 2), "ID"), &other_action);
         assert!(result);
+
+        let mut other_action = Action::location(Coordinates::new(10, -5));
+        other_action
+            .parameters
+            .insert("x".to_string(), "010".to_string());
+        other_action
+            .parameters
+            .insert("y".to_string(), "-05".to_string());
+        let result = matches_action(&Action::location(Coordinates::new(10, -5)), &other_action);
+        assert!(result);
+
+        let result = matches_action(&Action::custom("010"), &Action::custom("10"));
+        assert!(!result);
     }
 
     #[test]
@@ -661,7 +876,10 @@ mod tests {
                         id: "A_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![],
+                        ordered: false,
                     }],
+                    optional: false,
+                    branch_group: String::new(),
                 }],
             }),
             ..Default::default()
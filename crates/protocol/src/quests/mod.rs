@@ -1,3 +1,4 @@
+pub mod action_types;
 pub mod builders;
 pub mod graph;
 pub mod state;
@@ -64,6 +65,17 @@ impl Quest {
         definition.is_valid()
     }
 
+    /// Validates a Quest struct collecting all the errors found instead of stopping at the first one
+    ///
+    pub fn validation_report(&self) -> QuestValidationReport {
+        let Some(definition) = &self.definition else {
+            let mut report = QuestValidationReport::default();
+            report.add("definition", QuestValidationError::InvalidDefinition);
+            return report;
+        };
+        definition.validation_report()
+    }
+
     pub fn hide_actions(&mut self) {
         if let Some(definition) = self.definition.as_mut() {
             definition
@@ -75,121 +87,243 @@ impl Quest {
 }
 
 impl QuestDefinition {
+    /// Validates the definition returning the first error found
     pub fn is_valid(&self) -> Result<(), QuestValidationError> {
-        let definition = self;
-        if definition.steps.is_empty() {
-            return Err(QuestValidationError::InvalidDefinition);
+        self.validation_report().into_result()
+    }
+
+    /// Validates the definition collecting all the errors found, each one with the path to the item causing it
+    pub fn validation_report(&self) -> QuestValidationReport {
+        let mut report = QuestValidationReport::default();
+        if self.steps.is_empty() {
+            report.add("steps", QuestValidationError::InvalidDefinition);
+            return report;
         }
 
         // All connection halfs have a defined step
-        for Connection { step_from, step_to } in &definition.connections {
+        for (index, Connection { step_from, step_to }) in self.connections.iter().enumerate() {
             if !self.contains_step(step_from) {
-                return Err(QuestValidationError::MissingStepDefinition(
-                    step_from.clone(),
-                ));
+                report.add(
+                    format!("connections[{index}]"),
+                    QuestValidationError::MissingStepDefinition(step_from.clone()),
+                );
             }
 
             if !self.contains_step(step_to) {
-                return Err(QuestValidationError::MissingStepDefinition(step_to.clone()));
+                report.add(
+                    format!("connections[{index}]"),
+                    QuestValidationError::MissingStepDefinition(step_to.clone()),
+                );
             }
         }
 
+        self.validate_connections(&mut report);
+
         // Has at least one node for starting.
         let starting_nodes = self.get_steps_without_from();
         if starting_nodes.is_empty() {
-            return Err(QuestValidationError::NoStartingNode);
+            report.add("connections", QuestValidationError::NoStartingNode);
         }
 
         // Has at least one node pointing to end
         let end_nodes = self.get_steps_without_to();
         if end_nodes.is_empty() {
-            return Err(QuestValidationError::NoEndNode);
+            report.add("connections", QuestValidationError::NoEndNode);
         }
 
         // Used to check all steps/tasks have a unique ID
         let mut unique_task_ids: HashSet<String> = HashSet::new();
         let mut unique_step_ids: HashSet<String> = HashSet::new();
 
-        for step in &definition.steps {
+        for (step_index, step) in self.steps.iter().enumerate() {
+            let step_path = format!("steps[{step_index}]");
+
             // All steps should not contain Tasks::None used for START and END nodes
             if step.tasks.is_empty() {
-                return Err(QuestValidationError::MissingTasksForStep(step.id.clone()));
+                report.add(
+                    &step_path,
+                    QuestValidationError::MissingTasksForStep(step.id.clone()),
+                );
             }
 
             if !unique_step_ids.insert(step.id.to_string()) {
                 // Step with same id has been seen
-                return Err(QuestValidationError::NotUniqueIDForStep(step.id.clone()));
+                report.add(
+                    &step_path,
+                    QuestValidationError::NotUniqueIDForStep(step.id.clone()),
+                );
             }
 
             if step.description.is_empty() {
-                return Err(QuestValidationError::MissingDescriptionForStep(
-                    step.id.to_string(),
-                ));
+                report.add(
+                    &step_path,
+                    QuestValidationError::MissingDescriptionForStep(step.id.to_string()),
+                );
             }
 
             // All steps tasks (if there) have unique ID
-            for task in &step.tasks {
+            for (task_index, task) in step.tasks.iter().enumerate() {
+                let task_path = format!("{step_path}.tasks[{task_index}]");
+
                 if !unique_task_ids.insert(task.id.to_string()) {
                     // Task with same id has been seen
-                    return Err(QuestValidationError::NotUniqueIDForStepTask(
-                        step.id.clone(),
-                    ));
+                    report.add(
+                        &task_path,
+                        QuestValidationError::NotUniqueIDForStepTask(step.id.clone()),
+                    );
                 }
 
                 if task.description.is_empty() {
-                    return Err(QuestValidationError::MissingDescriptionForTask(
-                        task.id.to_string(),
-                    ));
+                    report.add(
+                        &task_path,
+                        QuestValidationError::MissingDescriptionForTask(task.id.to_string()),
+                    );
                 }
 
-                for action_item in &task.action_items {
-                    match &*action_item.r#type {
-                        "CUSTOM" => {
-                            if action_item.parameters.keys().len() == 0 {
-                                return Err(QuestValidationError::ActionItemParametersNotValid(
-                                    "CUSTOM".to_string(),
-                                ));
-                            }
-                        }
-                        "LOCATION" => {
-                            if action_item.parameters.get("x").is_none()
-                                || action_item.parameters.get("y").is_none()
-                            {
-                                return Err(QuestValidationError::ActionItemParametersNotValid(
-                                    "LOCATION".to_string(),
-                                ));
-                            }
-                        }
-                        "EMOTE" => {
-                            if action_item.parameters.get("x").is_none()
-                                || action_item.parameters.get("y").is_none()
-                                || action_item.parameters.get("id").is_none()
-                            {
-                                return Err(QuestValidationError::ActionItemParametersNotValid(
-                                    "EMOTE".to_string(),
-                                ));
-                            }
-                        }
-                        "JUMP" => {
-                            if action_item.parameters.get("x").is_none()
-                                || action_item.parameters.get("y").is_none()
+                for (action_index, action_item) in task.action_items.iter().enumerate() {
+                    let action_path = format!("{task_path}.action_items[{action_index}]");
+
+                    if action_item.count == Some(0) {
+                        report.add(
+                            &action_path,
+                            QuestValidationError::ActionItemCountNotValid(task.id.to_string()),
+                        );
+                    }
+
+                    match action_types::get_action_type(&action_item.r#type) {
+                        Some(action_type) => {
+                            if let Err(error) =
+                                action_type.validate_parameters(&action_item.parameters)
                             {
-                                return Err(QuestValidationError::ActionItemParametersNotValid(
-                                    "JUMP".to_string(),
-                                ));
+                                report.add(&action_path, error);
                             }
                         }
-                        _ => {
-                            return Err(QuestValidationError::ActionItemTypeNotValid(
-                                action_item.r#type.clone(),
-                            ));
+                        None => {
+                            report.add(
+                                &action_path,
+                                QuestValidationError::ActionItemTypeNotValid(
+                                    action_item.r#type.clone(),
+                                ),
+                            );
                         }
                     }
                 }
             }
         }
 
-        Ok(())
+        self.validate_optional_steps_and_branch_groups(&end_nodes, &mut report);
+
+        report
+    }
+
+    /// Connections can't point a step to itself, be repeated, leave steps out of the graph or form a cycle
+    fn validate_connections(&self, report: &mut QuestValidationReport) {
+        if self.connections.is_empty() {
+            return;
+        }
+
+        let mut unique_connections: HashSet<(&StepID, &StepID)> = HashSet::new();
+        let mut next_steps: HashMap<&StepID, Vec<&StepID>> = HashMap::new();
+        for (index, Connection { step_from, step_to }) in self.connections.iter().enumerate() {
+            if step_from == step_to {
+                report.add(
+                    format!("connections[{index}]"),
+                    QuestValidationError::SelfLoopConnection(step_from.clone()),
+                );
+                continue;
+            }
+            if !unique_connections.insert((step_from, step_to)) {
+                report.add(
+                    format!("connections[{index}]"),
+                    QuestValidationError::DuplicateConnection(step_from.clone(), step_to.clone()),
+                );
+                continue;
+            }
+            next_steps.entry(step_from).or_default().push(step_to);
+        }
+
+        let connected_steps: HashSet<&StepID> = self
+            .connections
+            .iter()
+            .flat_map(|connection| [&connection.step_from, &connection.step_to])
+            .collect();
+        for (index, step) in self.steps.iter().enumerate() {
+            if !connected_steps.contains(&step.id) {
+                report.add(
+                    format!("steps[{index}]"),
+                    QuestValidationError::OrphanStep(step.id.clone()),
+                );
+            }
+        }
+
+        // Only the first cycle found is reported, as the same steps may be part of many of them
+        let mut visited: HashSet<&StepID> = HashSet::new();
+        for step in &self.steps {
+            let mut path = vec![];
+            if let Some(cycle) = find_cycle(&step.id, &next_steps, &mut visited, &mut path) {
+                report.add(
+                    "connections",
+                    QuestValidationError::CycleBetweenSteps(cycle),
+                );
+                return;
+            }
+        }
+    }
+
+    /// Optional steps can only lead to other optional steps, branch groups can only be set on steps pointing to the end
+    /// and the quest has to require at least one step to be completed
+    fn validate_optional_steps_and_branch_groups(
+        &self,
+        end_nodes: &HashSet<StepID>,
+        report: &mut QuestValidationReport,
+    ) {
+        // Steps that can be reached from an optional step
+        let mut after_optional: HashSet<&StepID> = HashSet::new();
+        let mut to_visit: Vec<&StepID> = self
+            .steps
+            .iter()
+            .filter(|step| step.optional)
+            .map(|step| &step.id)
+            .collect();
+        while let Some(current) = to_visit.pop() {
+            for connection in self.connections.iter().filter(|c| c.step_from == *current) {
+                if after_optional.insert(&connection.step_to) {
+                    to_visit.push(&connection.step_to);
+                }
+            }
+        }
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let step_path = format!("steps[{index}]");
+            if !step.branch_group.is_empty() {
+                if !end_nodes.contains(&step.id) {
+                    report.add(
+                        &step_path,
+                        QuestValidationError::BranchGroupForNotEndStep(step.id.clone()),
+                    );
+                }
+                if step.optional {
+                    report.add(
+                        &step_path,
+                        QuestValidationError::OptionalStepInBranchGroup(step.id.clone()),
+                    );
+                }
+            }
+
+            if !step.optional && after_optional.contains(&step.id) {
+                report.add(
+                    &step_path,
+                    QuestValidationError::RequiredStepAfterOptionalStep(step.id.clone()),
+                );
+            }
+        }
+
+        let has_required_end = end_nodes
+            .iter()
+            .any(|step_id| matches!(self.get_step(step_id), Some(step) if !step.optional));
+        if !end_nodes.is_empty() && !has_required_end {
+            report.add("steps", QuestValidationError::NoRequiredEndStep);
+        }
     }
 
     fn contains_step(&self, step_id: &StepID) -> bool {
@@ -236,6 +370,34 @@ impl QuestDefinition {
     }
 }
 
+/// Depth-first search returning the steps that form a cycle reachable from `step`, if any.
+///
+/// `visited` keeps the steps already known not to be part of a cycle, so each step is only explored once
+fn find_cycle<'a>(
+    step: &'a StepID,
+    next_steps: &HashMap<&'a StepID, Vec<&'a StepID>>,
+    visited: &mut HashSet<&'a StepID>,
+    path: &mut Vec<&'a StepID>,
+) -> Option<Vec<StepID>> {
+    if let Some(position) = path.iter().position(|path_step| *path_step == step) {
+        return Some(path[position..].iter().map(|s| s.to_string()).collect());
+    }
+    if visited.contains(step) {
+        return None;
+    }
+
+    path.push(step);
+    for next in next_steps.get(step).into_iter().flatten() {
+        if let Some(cycle) = find_cycle(next, next_steps, visited, path) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    visited.insert(step);
+
+    None
+}
+
 impl QuestState {
     pub fn hide_actions(&mut self) {
         for step in self.current_steps.values_mut() {
@@ -249,12 +411,70 @@ impl StepContent {
         for task in &mut self.to_dos {
             task.hide_actions();
         }
+        for progress in &mut self.actions_progress {
+            progress.action = progress.action.as_ref().map(Action::hidden);
+        }
     }
 }
 
 impl Task {
+    /// Removes the action items but the ones that have to be done more than once, which are kept without type and
+    /// parameters so the required count can still be shown
     pub fn hide_actions(&mut self) {
-        self.action_items.clear();
+        self.action_items
+            .retain(|action| action.required_count() > 1);
+        for action in &mut self.action_items {
+            *action = action.hidden();
+        }
+    }
+}
+
+impl Action {
+    /// Times the action has to be done to complete it as an action item of a task
+    pub fn required_count(&self) -> u32 {
+        self.count.unwrap_or(1).max(1)
+    }
+
+    fn hidden(&self) -> Action {
+        Action {
+            r#type: String::default(),
+            parameters: HashMap::default(),
+            count: self.count,
+        }
+    }
+}
+
+/// All the errors found validating a quest definition
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct QuestValidationReport {
+    pub errors: Vec<QuestValidationIssue>,
+}
+
+/// A validation error along with the path to the item of the definition causing it, like `steps[2].tasks[0].action_items[1]`
+#[derive(Debug, PartialEq, Eq)]
+pub struct QuestValidationIssue {
+    pub path: String,
+    pub error: QuestValidationError,
+}
+
+impl QuestValidationReport {
+    fn add(&mut self, path: impl Into<String>, error: QuestValidationError) {
+        self.errors.push(QuestValidationIssue {
+            path: path.into(),
+            error,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the first error found, if any
+    pub fn into_result(self) -> Result<(), QuestValidationError> {
+        match self.errors.into_iter().next() {
+            Some(issue) => Err(issue.error),
+            None => Ok(()),
+        }
     }
 }
 
@@ -298,6 +518,36 @@ pub enum QuestValidationError {
     /// Action Item parameters should be valid
     #[error("Action Item's parameters are not valid: {0}")]
     ActionItemParametersNotValid(String),
+    /// Action Item coordinates should be inside Genesis City
+    #[error("Action Item's coordinate is out of bounds: {0} - Parameter: {1}")]
+    ActionItemCoordinateOutOfBounds(String, String),
+    /// Steps after an optional step should be optional too
+    #[error("Step after an optional step must be optional - Step ID: {0}")]
+    RequiredStepAfterOptionalStep(StepID),
+    /// Only steps pointing to the end can be part of a branch group
+    #[error("Step in a branch group must point to the end - Step ID: {0}")]
+    BranchGroupForNotEndStep(StepID),
+    /// Optional steps can't be alternatives to complete the quest
+    #[error("Optional step can't be part of a branch group - Step ID: {0}")]
+    OptionalStepInBranchGroup(StepID),
+    /// At least one step pointing to end should be required
+    #[error("Missing a required step pointing to the end of the quest")]
+    NoRequiredEndStep,
+    /// A connection can't go from a step to the same step
+    #[error("Connection from a step to itself - Step ID: {0}")]
+    SelfLoopConnection(StepID),
+    /// The same connection can't be defined twice
+    #[error("Connection is defined more than once - From Step ID: {0} - To Step ID: {1}")]
+    DuplicateConnection(StepID, StepID),
+    /// All steps should be part of a connection when the quest has connections
+    #[error("Step is not part of any connection - Step ID: {0}")]
+    OrphanStep(StepID),
+    /// Connections can't form a cycle
+    #[error("Connections form a cycle - Step IDs: {}", .0.join(", "))]
+    CycleBetweenSteps(Vec<StepID>),
+    /// Action Item count should be at least one
+    #[error("Action Item's count must be greater than zero - Task ID: {0}")]
+    ActionItemCountNotValid(String),
 }
 
 impl Connection {
@@ -352,6 +602,8 @@ mod tests {
             id: name.to_string(),
             description: "some desc".to_string(),
             tasks: vec![],
+            optional: false,
+            branch_group: String::new(),
         }
     }
 
@@ -363,7 +615,10 @@ mod tests {
                 action_items: vec![Action::location(Coordinates::new(10, 10))],
                 id: format!("{name}_1"),
                 description: "task desc".to_string(),
+                ordered: false,
             }],
+            optional: false,
+            branch_group: String::new(),
         }
     }
 
@@ -520,13 +775,17 @@ mod tests {
                             id: "A_1".to_string(),
                             description: "desc".to_string(),
                             action_items: vec![Action::location(Coordinates::new(10, 20))],
+                            ordered: false,
                         },
                         Task {
                             id: "A_1".to_string(),
                             description: "desc".to_string(),
                             action_items: vec![Action::jump(Coordinates::new(30, 20))],
+                            ordered: false,
                         },
                     ],
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 create_simple_step("B"),
                 create_simple_step("C"),
@@ -557,7 +816,10 @@ mod tests {
                         id: "A_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 create_empty_step("B"),
                 create_simple_step("C"),
@@ -577,13 +839,17 @@ mod tests {
                             id: "A_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![Action::location(Coordinates::new(10, 20))],
+                            ordered: false,
                         },
                         Task {
                             id: "A_2".to_string(),
                             description: "desc".to_string(),
                             action_items: vec![Action::location(Coordinates::new(30, 20))],
+                            ordered: false,
                         },
                     ],
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 create_empty_step("B"),
                 create_simple_step("C"),
@@ -592,4 +858,270 @@ mod tests {
         let err = QuestValidationError::MissingDescriptionForTask("A_1".to_string());
         assert_eq!(quest.is_valid().unwrap_err(), err);
     }
+
+    #[test]
+    fn quest_with_zero_count_action_should_not_be_valid() {
+        let quest = QuestBuilder::new()
+            .with_steps(vec![Step {
+                id: "A".to_string(),
+                description: "desc".to_string(),
+                tasks: vec![Task {
+                    id: "A_1".to_string(),
+                    description: "desc".to_string(),
+                    action_items: vec![Action::jump(Coordinates::new(10, 20)).times(0)],
+                    ordered: false,
+                }],
+                optional: false,
+                branch_group: String::new(),
+            }])
+            .build();
+        let err = QuestValidationError::ActionItemCountNotValid("A_1".to_string());
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        let quest = QuestBuilder::new()
+            .with_steps(vec![Step {
+                id: "A".to_string(),
+                description: "desc".to_string(),
+                tasks: vec![Task {
+                    id: "A_1".to_string(),
+                    description: "desc".to_string(),
+                    action_items: vec![Action::jump(Coordinates::new(10, 20)).times(5)],
+                    ordered: false,
+                }],
+                optional: false,
+                branch_group: String::new(),
+            }])
+            .build();
+        assert!(quest.is_valid().is_ok());
+    }
+
+    #[test]
+    fn quest_with_invalid_optional_steps_or_branch_groups_should_not_be_valid() {
+        let step = |id: &str, optional: bool, branch_group: &str| Step {
+            optional,
+            branch_group: branch_group.to_string(),
+            ..create_simple_step(id)
+        };
+
+        // required step after an optional one
+        let quest = QuestBuilder::new()
+            .with_steps(vec![
+                step("A", false, ""),
+                step("B", true, ""),
+                step("C", false, ""),
+            ])
+            .with_connections(vec![Connection::new("A", "B"), Connection::new("B", "C")])
+            .build();
+        let err = QuestValidationError::RequiredStepAfterOptionalStep("C".to_string());
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        // branch group on a step not pointing to the end
+        let quest = QuestBuilder::new()
+            .with_steps(vec![step("A", false, "G"), step("B", false, "")])
+            .with_connections(vec![Connection::new("A", "B")])
+            .build();
+        let err = QuestValidationError::BranchGroupForNotEndStep("A".to_string());
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        // optional step as an alternative
+        let quest = QuestBuilder::new()
+            .with_steps(vec![
+                step("A", false, ""),
+                step("B", false, "G"),
+                step("C", true, "G"),
+            ])
+            .with_connections(vec![Connection::new("A", "B"), Connection::new("A", "C")])
+            .build();
+        let err = QuestValidationError::OptionalStepInBranchGroup("C".to_string());
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        // nothing required to complete the quest
+        let quest = QuestBuilder::new()
+            .with_steps(vec![step("A", true, ""), step("B", true, "")])
+            .with_connections(vec![Connection::new("A", "B")])
+            .build();
+        assert_eq!(
+            quest.is_valid().unwrap_err(),
+            QuestValidationError::NoRequiredEndStep
+        );
+
+        let quest = QuestBuilder::new()
+            .with_steps(vec![
+                step("A", false, ""),
+                step("B1", false, "G"),
+                step("B2", false, "G"),
+                step("C", true, ""),
+            ])
+            .with_connections(vec![
+                Connection::new("A", "B1"),
+                Connection::new("A", "B2"),
+                Connection::new("A", "C"),
+            ])
+            .build();
+        assert!(quest.is_valid().is_ok());
+    }
+
+    #[test]
+    fn quest_with_invalid_connections_should_not_be_valid() {
+        let steps = vec![
+            create_simple_step("A"),
+            create_simple_step("B"),
+            create_simple_step("C"),
+        ];
+
+        let quest = QuestBuilder::new()
+            .with_steps(steps.clone())
+            .with_connections(vec![
+                Connection::new("A", "B"),
+                Connection::new("B", "B"),
+                Connection::new("B", "C"),
+            ])
+            .build();
+        let err = QuestValidationError::SelfLoopConnection("B".to_string());
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        let quest = QuestBuilder::new()
+            .with_steps(steps.clone())
+            .with_connections(vec![
+                Connection::new("A", "B"),
+                Connection::new("B", "C"),
+                Connection::new("A", "B"),
+            ])
+            .build();
+        let err = QuestValidationError::DuplicateConnection("A".to_string(), "B".to_string());
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        let quest = QuestBuilder::new()
+            .with_steps(steps.clone())
+            .with_connections(vec![Connection::new("A", "B")])
+            .build();
+        let err = QuestValidationError::OrphanStep("C".to_string());
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        let quest = QuestBuilder::new()
+            .with_steps(steps.clone())
+            .with_connections(vec![
+                Connection::new("A", "B"),
+                Connection::new("B", "C"),
+                Connection::new("C", "B"),
+            ])
+            .build();
+        let err = QuestValidationError::CycleBetweenSteps(vec!["B".to_string(), "C".to_string()]);
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        // all the steps in a cycle, so there is no starting step
+        let quest = QuestBuilder::new()
+            .with_steps(steps.clone())
+            .with_connections(vec![
+                Connection::new("A", "B"),
+                Connection::new("B", "C"),
+                Connection::new("C", "A"),
+            ])
+            .build();
+        let err = QuestValidationError::CycleBetweenSteps(vec![
+            "A".to_string(),
+            "B".to_string(),
+            "C".to_string(),
+        ]);
+        assert_eq!(quest.is_valid().unwrap_err(), err);
+
+        let quest = QuestBuilder::new()
+            .with_steps(steps)
+            .with_connections(vec![
+                Connection::new("A", "B"),
+                Connection::new("A", "C"),
+                Connection::new("B", "C"),
+            ])
+            .build();
+        assert!(quest.is_valid().is_ok());
+    }
+
+    #[test]
+    fn validation_report_collects_all_errors_with_paths() {
+        let mut step_b = create_simple_step("B");
+        step_b.description = "".to_string();
+        step_b.tasks[0].action_items.push(Action {
+            r#type: "UNKNOWN".to_string(),
+            parameters: HashMap::new(),
+            count: None,
+        });
+        let quest = QuestBuilder::new()
+            .with_steps(vec![
+                create_simple_step("A"),
+                step_b,
+                create_empty_step("C"),
+            ])
+            .with_connections(vec![
+                Connection::new("A", "B"),
+                Connection::new("B", "C"),
+                Connection::new("B", "D"),
+            ])
+            .build();
+
+        let report = quest.validation_report();
+        let issues = report
+            .errors
+            .iter()
+            .map(|issue| (issue.path.as_str(), &issue.error))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    "connections[2]",
+                    &QuestValidationError::MissingStepDefinition("D".to_string())
+                ),
+                (
+                    "steps[1]",
+                    &QuestValidationError::MissingDescriptionForStep("B".to_string())
+                ),
+                (
+                    "steps[1].tasks[0].action_items[1]",
+                    &QuestValidationError::ActionItemTypeNotValid("UNKNOWN".to_string())
+                ),
+                (
+                    "steps[2]",
+                    &QuestValidationError::MissingTasksForStep("C".to_string())
+                ),
+            ]
+        );
+        assert_eq!(
+            quest.is_valid().unwrap_err(),
+            QuestValidationError::MissingStepDefinition("D".to_string())
+        );
+
+        let quest = QuestBuilder::new()
+            .with_steps(vec![create_simple_step("A")])
+            .build();
+        assert!(quest.validation_report().is_empty());
+    }
+
+    #[test]
+    fn hide_actions_keeps_counters() {
+        let mut task = Task {
+            id: "A_1".to_string(),
+            description: "desc".to_string(),
+            action_items: vec![
+                Action::location(Coordinates::new(10, 20)),
+                Action::jump(Coordinates::new(10, 20)).times(5),
+            ],
+            ordered: false,
+        };
+        task.hide_actions();
+        assert_eq!(task.action_items.len(), 1);
+        assert!(task.action_items[0].r#type.is_empty());
+        assert!(task.action_items[0].parameters.is_empty());
+        assert_eq!(task.action_items[0].required_count(), 5);
+
+        let mut step_content = StepContent::new(vec![Task {
+            id: "A_1".to_string(),
+            description: "desc".to_string(),
+            action_items: vec![Action::jump(Coordinates::new(10, 20)).times(5)],
+            ordered: false,
+        }]);
+        step_content.hide_actions();
+        let action = step_content.actions_progress[0].action.as_ref().unwrap();
+        assert!(action.r#type.is_empty());
+        assert_eq!(action.required_count(), 5);
+    }
 }
@@ -2,7 +2,7 @@ use crate::{
     definitions::*,
     quests::{
         graph::{matches_action, QuestGraph},
-        END_STEP_ID, START_STEP_ID,
+        StepID, END_STEP_ID, START_STEP_ID,
     },
 };
 use std::collections::HashMap;
@@ -12,6 +12,12 @@ impl QuestState {
         self.required_steps
             .iter()
             .all(|step| self.steps_completed.contains(step))
+            && self.required_branch_groups.iter().all(|group| {
+                group
+                    .steps
+                    .iter()
+                    .any(|step| self.steps_completed.contains(step))
+            })
     }
 
     pub fn apply_event(&self, quest_graph: &QuestGraph, event: &Event) -> QuestState {
@@ -28,16 +34,18 @@ impl QuestState {
                 continue;
             }
             for (i, task) in step_content.to_dos.iter().enumerate() {
-                match task
-                    .action_items
-                    .iter()
-                    .position(|action| matches_action(action, &event_action))
-                {
+                match task.next_action_index(&event_action) {
                     Some(matched_action_index) => {
                         state
                             .current_steps
                             .entry(step_id.to_string())
                             .and_modify(|step| {
+                                let action = &task.action_items[matched_action_index];
+                                if !step.count_action(&task.id, action) {
+                                    // the action has to be done more times
+                                    return;
+                                }
+
                                 step.to_dos[i].action_items.remove(matched_action_index);
 
                                 if step.to_dos[i].action_items.is_empty() {
@@ -59,10 +67,9 @@ impl QuestState {
                     let next_steps = quest_graph.next(step_id).unwrap_or_default();
                     next_steps.iter().for_each(|step_id| {
                         if step_id != END_STEP_ID {
-                            let step_content = StepContent {
-                                to_dos: quest_graph.tasks_by_step.get(step_id).unwrap().clone(),
-                                ..Default::default()
-                            };
+                            let step_content = StepContent::new(
+                                quest_graph.tasks_by_step.get(step_id).unwrap().clone(),
+                            );
                             state.current_steps.insert(step_id.clone(), step_content);
                         }
                     });
@@ -77,6 +84,78 @@ impl QuestState {
     }
 }
 
+impl Task {
+    /// Returns the index of the action item the event action counts for. If the task is ordered, only its first
+    /// pending action item can be matched, so actions done out of order are not counted.
+    fn next_action_index(&self, event_action: &Action) -> Option<usize> {
+        if self.ordered {
+            self.action_items
+                .first()
+                .filter(|action| matches_action(action, event_action))
+                .map(|_| 0)
+        } else {
+            self.action_items
+                .iter()
+                .position(|action| matches_action(action, event_action))
+        }
+    }
+}
+
+impl StepContent {
+    /// Returns the content of a step that has just become current, with the progress of its counter actions at zero
+    pub fn new(to_dos: Vec<Task>) -> Self {
+        let actions_progress = to_dos
+            .iter()
+            .flat_map(|task| {
+                task.action_items
+                    .iter()
+                    .filter(|action| action.required_count() > 1)
+                    .map(|action| ActionProgress {
+                        task_id: task.id.clone(),
+                        action: Some(action.clone()),
+                        done: 0,
+                    })
+            })
+            .collect();
+
+        Self {
+            to_dos,
+            tasks_completed: vec![],
+            actions_progress,
+        }
+    }
+
+    /// Counts one more time the action item of a task and returns whether it has been done as many times as required
+    fn count_action(&mut self, task_id: &str, action: &Action) -> bool {
+        let required = action.required_count();
+        if required <= 1 {
+            return true;
+        }
+
+        let index = match self.actions_progress.iter().position(|progress| {
+            progress.task_id == task_id && progress.action.as_ref() == Some(action)
+        }) {
+            Some(index) => index,
+            None => {
+                self.actions_progress.push(ActionProgress {
+                    task_id: task_id.to_string(),
+                    action: Some(action.clone()),
+                    done: 0,
+                });
+                self.actions_progress.len() - 1
+            }
+        };
+
+        self.actions_progress[index].done += 1;
+        if self.actions_progress[index].done >= required {
+            self.actions_progress.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+}
+
 impl From<&QuestGraph> for QuestState {
     /// Returns the initial state of the Quest as it's not initialized
     fn from(graph: &QuestGraph) -> Self {
@@ -87,10 +166,7 @@ impl From<&QuestGraph> for QuestState {
             .map(|step| {
                 (
                     step.clone(),
-                    StepContent {
-                        to_dos: graph.tasks_by_step.get(step).unwrap().clone(),
-                        ..Default::default()
-                    },
+                    StepContent::new(graph.tasks_by_step.get(step).unwrap().clone()),
                 )
             })
             .collect::<HashMap<String, StepContent>>();
@@ -98,12 +174,94 @@ impl From<&QuestGraph> for QuestState {
         Self {
             current_steps: next_possible_steps,
             required_steps: graph.required_for_end().unwrap_or_default(),
+            required_branch_groups: graph.required_branch_groups_for_end().unwrap_or_default(),
             steps_left: graph.total_steps() as u32,
             steps_completed: Vec::default(),
         }
     }
 }
 
+/// Steps completed and tasks advanced when applying an event to a state
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EventProgress {
+    pub steps_completed: Vec<StepID>,
+    pub tasks_advanced: Vec<String>,
+}
+
+impl QuestState {
+    /// Returns the steps completed and the tasks advanced since the previous state
+    pub fn progress_since(&self, previous: &QuestState) -> EventProgress {
+        let steps_completed = self
+            .steps_completed
+            .iter()
+            .filter(|step| !previous.steps_completed.contains(step))
+            .cloned()
+            .collect();
+
+        let mut tasks_advanced = vec![];
+        for (step_id, previous_step) in &previous.current_steps {
+            let current_step = self.current_steps.get(step_id);
+            for task in &previous_step.to_dos {
+                let advanced = match current_step {
+                    // the step was completed
+                    None => true,
+                    Some(current_step) => {
+                        match current_step.to_dos.iter().find(|to_do| to_do.id == task.id) {
+                            None => true,
+                            Some(to_do) => {
+                                to_do.action_items.len() != task.action_items.len()
+                                    || !task_progress(current_step, &task.id)
+                                        .eq(task_progress(previous_step, &task.id))
+                            }
+                        }
+                    }
+                };
+                if advanced {
+                    tasks_advanced.push(task.id.clone());
+                }
+            }
+        }
+        tasks_advanced.sort();
+
+        EventProgress {
+            steps_completed,
+            tasks_advanced,
+        }
+    }
+}
+
+fn task_progress<'a>(step: &'a StepContent, task_id: &'a str) -> impl Iterator<Item = u32> + 'a {
+    step.actions_progress
+        .iter()
+        .filter(move |progress| progress.task_id == task_id)
+        .map(|progress| progress.done)
+}
+
+/// Applies the events in order, returning the progress made by each one of them
+pub fn get_events_progress(quest: &Quest, events: &[Event]) -> Vec<EventProgress> {
+    let initial_state = QuestState::from(&QuestGraph::from(quest));
+    get_events_progress_from(quest, &initial_state, events)
+}
+
+/// Applies the events in order starting from the given state, returning the progress made by each one of them
+pub fn get_events_progress_from(
+    quest: &Quest,
+    initial_state: &QuestState,
+    events: &[Event],
+) -> Vec<EventProgress> {
+    let quest_graph = QuestGraph::from(quest);
+    let mut state = initial_state.clone();
+    events
+        .iter()
+        .map(|event| {
+            let next_state = state.apply_event(&quest_graph, event);
+            let progress = next_state.progress_since(&state);
+            state = next_state;
+            progress
+        })
+        .collect()
+}
+
 pub fn get_state(quest: &Quest, events: &[Event]) -> QuestState {
     let quest_graph = QuestGraph::from(quest);
     let initial_state = (&quest_graph).into();
@@ -112,6 +270,28 @@ pub fn get_state(quest: &Quest, events: &[Event]) -> QuestState {
     })
 }
 
+/// Returns the state right after applying the event with the given id, or `None` if there is no such event
+pub fn get_state_at_event(quest: &Quest, events: &[Event], event_id: &str) -> Option<QuestState> {
+    let position = events.iter().position(|event| event.id == event_id)?;
+    Some(get_state(quest, &events[..=position]))
+}
+
+/// Returns the state after applying the events that happened until the given timestamp, included.
+///
+/// Each event comes along with the time it happened and they are expected to be in order
+pub fn get_state_at_timestamp(
+    quest: &Quest,
+    events: &[(i64, Event)],
+    timestamp: i64,
+) -> QuestState {
+    let events = events
+        .iter()
+        .take_while(|(event_timestamp, _)| *event_timestamp <= timestamp)
+        .map(|(_, event)| event.clone())
+        .collect::<Vec<_>>();
+    get_state(quest, &events)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::quests::builders::Coordinates;
@@ -142,7 +322,10 @@ mod tests {
                                 Action::location(Coordinates::new(10, 10)),
                                 Action::jump(Coordinates::new(10, 11)),
                             ],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "A2".to_string(),
@@ -151,7 +334,10 @@ mod tests {
                             id: "A2_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![Action::npc_interaction("NPC_IDEN")],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B".to_string(),
@@ -160,7 +346,10 @@ mod tests {
                             id: "B_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![Action::jump(Coordinates::new(20, 10))],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "C".to_string(),
@@ -169,7 +358,10 @@ mod tests {
                             id: "C_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![Action::jump(Coordinates::new(20, 20))],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "D".to_string(),
@@ -178,7 +370,10 @@ mod tests {
                             id: "D_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![Action::npc_interaction("OTHER_NPC")],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                 ],
             }),
@@ -191,32 +386,38 @@ mod tests {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::location(Coordinates::new(10, 10))),
+                instance_id: None,
             },
             Event {
                 // A2_1
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::jump(Coordinates::new(10, 11))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::jump(Coordinates::new(20, 10))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::jump(Coordinates::new(20, 20))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::npc_interaction("NPC_IDEN")),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::npc_interaction("OTHER_NPC")),
+                instance_id: None,
             },
         ];
 
@@ -299,6 +500,109 @@ mod tests {
         assert!(state.is_completed());
     }
 
+    #[test]
+    fn quest_graph_apply_event_task_ordered_works() {
+        let quest = Quest {
+            id: "".to_string(),
+            name: "ORDERED_QUEST".to_string(),
+            description: "".to_string(),
+            creator_address: "0xB".to_string(),
+            definition: Some(QuestDefinition {
+                connections: vec![],
+                steps: vec![Step {
+                    id: "A1".to_string(),
+                    description: "".to_string(),
+                    tasks: vec![Task {
+                        id: "A1_1".to_string(),
+                        description: "".to_string(),
+                        action_items: vec![
+                            Action::location(Coordinates::new(10, 10)),
+                            Action::emote(Coordinates::new(10, 10), "wave"),
+                        ],
+                        ordered: true,
+                    }],
+                    optional: false,
+                    branch_group: String::new(),
+                }],
+            }),
+            ..Default::default()
+        };
+        let quest_graph = QuestGraph::from(&quest);
+        let location = Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: "0xA".to_string(),
+            action: Some(Action::location(Coordinates::new(10, 10))),
+            instance_id: None,
+        };
+        let emote = Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: "0xA".to_string(),
+            action: Some(Action::emote(Coordinates::new(10, 10), "wave")),
+            instance_id: None,
+        };
+
+        let initial_state = QuestState::from(&quest_graph);
+
+        // out of order event is rejected
+        let state = initial_state.apply_event(&quest_graph, &emote);
+        assert_eq!(state, initial_state);
+
+        let state = state.apply_event(&quest_graph, &location);
+        let step = state.current_steps.get("A1").unwrap();
+        assert_eq!(step.to_dos[0].action_items.len(), 1);
+
+        let state = state.apply_event(&quest_graph, &emote);
+        assert!(state.current_steps.is_empty());
+        assert!(state.is_completed());
+    }
+
+    #[test]
+    fn quest_graph_apply_event_task_unordered_works() {
+        let quest = Quest {
+            id: "".to_string(),
+            name: "UNORDERED_QUEST".to_string(),
+            description: "".to_string(),
+            creator_address: "0xB".to_string(),
+            definition: Some(QuestDefinition {
+                connections: vec![],
+                steps: vec![Step {
+                    id: "A1".to_string(),
+                    description: "".to_string(),
+                    tasks: vec![Task {
+                        id: "A1_1".to_string(),
+                        description: "".to_string(),
+                        action_items: vec![
+                            Action::location(Coordinates::new(10, 10)),
+                            Action::emote(Coordinates::new(10, 10), "wave"),
+                        ],
+                        ordered: false,
+                    }],
+                    optional: false,
+                    branch_group: String::new(),
+                }],
+            }),
+            ..Default::default()
+        };
+        let events = vec![
+            Event {
+                id: uuid::Uuid::new_v4().to_string(),
+                address: "0xA".to_string(),
+                action: Some(Action::emote(Coordinates::new(10, 10), "wave")),
+                instance_id: None,
+            },
+            Event {
+                id: uuid::Uuid::new_v4().to_string(),
+                address: "0xA".to_string(),
+                action: Some(Action::location(Coordinates::new(10, 10))),
+                instance_id: None,
+            },
+        ];
+
+        let state = get_state(&quest, &events);
+        assert!(state.current_steps.is_empty());
+        assert!(state.is_completed());
+    }
+
     #[test]
     fn quest_graph_apply_event_task_multiple_works() {
         let quest = Quest {
@@ -320,6 +624,7 @@ mod tests {
                                     Action::jump(Coordinates::new(10, 10)),
                                     Action::location(Coordinates::new(15, 10)),
                                 ],
+                                ordered: false,
                             },
                             Task {
                                 id: "A_2".to_string(),
@@ -328,8 +633,11 @@ mod tests {
                                     Action::npc_interaction("NPC_ID"),
                                     Action::location(Coordinates::new(15, 14)),
                                 ],
+                                ordered: false,
                             },
                         ],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B".to_string(),
@@ -342,6 +650,7 @@ mod tests {
                                     Action::jump(Coordinates::new(10, 20)),
                                     Action::location(Coordinates::new(23, 14)),
                                 ],
+                                ordered: false,
                             },
                             Task {
                                 id: "B_2".to_string(),
@@ -350,8 +659,11 @@ mod tests {
                                     Action::custom("a"),
                                     Action::location(Coordinates::new(40, 10)),
                                 ],
+                                ordered: false,
                             },
                         ],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "C".to_string(),
@@ -360,7 +672,10 @@ mod tests {
                             id: "C_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![Action::jump(Coordinates::new(20, 20))],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                 ],
             }),
@@ -373,46 +688,55 @@ mod tests {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::jump(Coordinates::new(10, 10))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::location(Coordinates::new(15, 10))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::npc_interaction("NPC_ID")),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::location(Coordinates::new(15, 14))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::jump(Coordinates::new(10, 20))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::location(Coordinates::new(23, 14))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::custom("a")),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::location(Coordinates::new(40, 10))),
+                instance_id: None,
             },
             Event {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::jump(Coordinates::new(20, 20))),
+                instance_id: None,
             },
         ];
         let mut state = QuestState::from(&quest_graph);
@@ -429,6 +753,7 @@ mod tests {
                     Action::jump(Coordinates::new(10, 10)),
                     Action::location(Coordinates::new(15, 10)),
                 ],
+                ordered: false,
             }
         );
         assert_eq!(
@@ -440,6 +765,7 @@ mod tests {
                     Action::npc_interaction("NPC_ID"),
                     Action::location(Coordinates::new(15, 14)),
                 ],
+                ordered: false,
             }
         );
 
@@ -461,6 +787,7 @@ mod tests {
                 id: "A_1".to_string(),
                 description: "".to_string(),
                 action_items: vec![Action::location(Coordinates::new(15, 10))],
+                ordered: false,
             }
         );
         assert_eq!(
@@ -472,6 +799,7 @@ mod tests {
                     Action::npc_interaction("NPC_ID"),
                     Action::location(Coordinates::new(15, 14)),
                 ],
+                ordered: false,
             }
         );
         assert!(state
@@ -499,6 +827,7 @@ mod tests {
                     Action::npc_interaction("NPC_ID"),
                     Action::location(Coordinates::new(15, 14)),
                 ],
+                ordered: false,
             }
         );
         assert!(state.steps_completed.is_empty());
@@ -529,6 +858,7 @@ mod tests {
                 id: "A_2".to_string(),
                 description: "".to_string(),
                 action_items: vec![Action::location(Coordinates::new(15, 14)),],
+                ordered: false,
             }
         );
         assert!(state.steps_completed.is_empty());
@@ -563,6 +893,7 @@ mod tests {
                     Action::jump(Coordinates::new(10, 20)),
                     Action::location(Coordinates::new(23, 14)),
                 ],
+                ordered: false,
             },
         );
         assert_eq!(
@@ -574,6 +905,7 @@ mod tests {
                     Action::custom("a"),
                     Action::location(Coordinates::new(40, 10)),
                 ],
+                ordered: false,
             },
         );
 
@@ -594,6 +926,7 @@ mod tests {
                 id: "B_1".to_string(),
                 description: "".to_string(),
                 action_items: vec![Action::location(Coordinates::new(23, 14)),],
+                ordered: false,
             },
         );
         assert_eq!(
@@ -605,6 +938,7 @@ mod tests {
                     Action::custom("a"),
                     Action::location(Coordinates::new(40, 10)),
                 ],
+                ordered: false,
             },
         );
 
@@ -626,6 +960,7 @@ mod tests {
                     Action::custom("a"),
                     Action::location(Coordinates::new(40, 10)),
                 ],
+                ordered: false,
             },
         );
 
@@ -656,6 +991,7 @@ mod tests {
                 id: "B_2".to_string(),
                 description: "".to_string(),
                 action_items: vec![Action::location(Coordinates::new(40, 10)),],
+                ordered: false,
             },
         );
 
@@ -705,6 +1041,257 @@ mod tests {
         assert!(state.is_completed())
     }
 
+    #[test]
+    fn quest_graph_apply_event_task_counter_works() {
+        let quest = Quest {
+            id: "".to_string(),
+            name: "COUNTER_QUEST".to_string(),
+            description: "".to_string(),
+            creator_address: "0xB".to_string(),
+            definition: Some(QuestDefinition {
+                connections: vec![],
+                steps: vec![Step {
+                    id: "A1".to_string(),
+                    description: "".to_string(),
+                    tasks: vec![Task {
+                        id: "A1_1".to_string(),
+                        description: "".to_string(),
+                        action_items: vec![
+                            Action::jump(Coordinates::new(10, 10)).times(3),
+                            Action::npc_interaction("NPC_IDEN"),
+                        ],
+                        ordered: false,
+                    }],
+                    optional: false,
+                    branch_group: String::new(),
+                }],
+            }),
+            ..Default::default()
+        };
+        let quest_graph = QuestGraph::from(&quest);
+        let jump = Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: "0xA".to_string(),
+            action: Some(Action::jump(Coordinates::new(10, 10))),
+            instance_id: None,
+        };
+        let npc_interaction = Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: "0xA".to_string(),
+            action: Some(Action::npc_interaction("NPC_IDEN")),
+            instance_id: None,
+        };
+
+        let mut state = QuestState::from(&quest_graph);
+        let step = state.current_steps.get("A1").unwrap();
+        assert_eq!(step.actions_progress.len(), 1);
+        assert_eq!(step.actions_progress[0].task_id, "A1_1");
+        assert_eq!(step.actions_progress[0].done, 0);
+
+        state = state.apply_event(&quest_graph, &jump);
+        state = state.apply_event(&quest_graph, &npc_interaction);
+        let step = state.current_steps.get("A1").unwrap();
+        assert_eq!(step.to_dos[0].action_items.len(), 1);
+        assert_eq!(step.actions_progress[0].done, 1);
+
+        state = state.apply_event(&quest_graph, &jump);
+        let step = state.current_steps.get("A1").unwrap();
+        assert_eq!(step.actions_progress[0].done, 2);
+        assert!(!state.is_completed());
+
+        state = state.apply_event(&quest_graph, &jump);
+        assert!(state.current_steps.is_empty());
+        assert!(state.steps_completed.contains(&"A1".to_string()));
+        assert!(state.is_completed());
+
+        // the result is the same as replaying the events
+        let events = vec![jump.clone(), jump.clone(), npc_interaction, jump];
+        assert_eq!(get_state(&quest, &events), state);
+    }
+
+    #[test]
+    fn quest_graph_apply_event_branch_group_and_optional_step_works() {
+        let step = |id: &str, optional: bool, branch_group: &str| Step {
+            id: id.to_string(),
+            description: "".to_string(),
+            tasks: vec![Task {
+                id: format!("{id}_1"),
+                description: "".to_string(),
+                action_items: vec![Action::custom(&format!("{id}_1_ID"))],
+                ordered: false,
+            }],
+            optional,
+            branch_group: branch_group.to_string(),
+        };
+        let quest = Quest {
+            id: "".to_string(),
+            name: "CUSTOM_QUEST".to_string(),
+            description: "".to_string(),
+            creator_address: "0xB".to_string(),
+            definition: Some(QuestDefinition {
+                connections: vec![
+                    Connection::new("A", "B1"),
+                    Connection::new("A", "B2"),
+                    Connection::new("A", "C"),
+                ],
+                steps: vec![
+                    step("A", false, ""),
+                    step("B1", false, "B"),
+                    step("B2", false, "B"),
+                    step("C", true, ""),
+                ],
+            }),
+            ..Default::default()
+        };
+        let quest_graph = QuestGraph::from(&quest);
+        let event = |action: &str| Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: "0xA".to_string(),
+            action: Some(Action::custom(action)),
+            instance_id: None,
+        };
+
+        let mut state = QuestState::from(&quest_graph);
+        assert!(state.required_steps.is_empty());
+        assert_eq!(state.required_branch_groups.len(), 1);
+
+        state = state.apply_event(&quest_graph, &event("A_1_ID"));
+        assert!(state.current_steps.contains_key("B1"));
+        assert!(state.current_steps.contains_key("B2"));
+        assert!(state.current_steps.contains_key("C"));
+        assert!(!state.is_completed());
+
+        // only one of the alternatives is needed, the optional step is ignored
+        state = state.apply_event(&quest_graph, &event("B2_1_ID"));
+        assert!(state.steps_completed.contains(&"B2".to_string()));
+        assert!(state.current_steps.contains_key("C"));
+        assert!(state.is_completed());
+    }
+
+    #[test]
+    fn get_events_progress_works() {
+        let task = |id: &str| Task {
+            id: id.to_string(),
+            description: "".to_string(),
+            action_items: vec![Action::custom(&format!("{id}_ID"))],
+            ordered: false,
+        };
+        let quest = Quest {
+            id: "".to_string(),
+            name: "CUSTOM_QUEST".to_string(),
+            description: "".to_string(),
+            creator_address: "0xB".to_string(),
+            definition: Some(QuestDefinition {
+                connections: vec![Connection::new("A", "B")],
+                steps: vec![
+                    Step {
+                        id: "A".to_string(),
+                        description: "".to_string(),
+                        tasks: vec![task("A_1"), task("A_2")],
+                        optional: false,
+                        branch_group: String::new(),
+                    },
+                    Step {
+                        id: "B".to_string(),
+                        description: "".to_string(),
+                        tasks: vec![task("B_1")],
+                        optional: false,
+                        branch_group: String::new(),
+                    },
+                ],
+            }),
+            ..Default::default()
+        };
+        let events = ["A_1_ID", "UNKNOWN_ID", "A_2_ID", "B_1_ID"]
+            .iter()
+            .map(|action| Event {
+                id: uuid::Uuid::new_v4().to_string(),
+                address: "0xA".to_string(),
+                action: Some(Action::custom(action)),
+                instance_id: None,
+            })
+            .collect::<Vec<_>>();
+
+        let progress = get_events_progress(&quest, &events);
+        assert_eq!(
+            progress,
+            vec![
+                EventProgress {
+                    steps_completed: vec![],
+                    tasks_advanced: vec!["A_1".to_string()],
+                },
+                EventProgress::default(),
+                EventProgress {
+                    steps_completed: vec!["A".to_string()],
+                    tasks_advanced: vec!["A_2".to_string()],
+                },
+                EventProgress {
+                    steps_completed: vec!["B".to_string()],
+                    tasks_advanced: vec!["B_1".to_string()],
+                },
+            ]
+        );
+
+        let state = get_state(&quest, &events[..2]);
+        assert_eq!(
+            get_events_progress_from(&quest, &state, &events[2..]),
+            progress[2..]
+        );
+    }
+
+    #[test]
+    fn get_state_at_event_and_timestamp_works() {
+        let quest = Quest {
+            id: "".to_string(),
+            name: "CUSTOM_QUEST".to_string(),
+            description: "".to_string(),
+            creator_address: "0xB".to_string(),
+            definition: Some(QuestDefinition {
+                connections: vec![Connection::new("A", "B")],
+                steps: ["A", "B"]
+                    .iter()
+                    .map(|id| Step {
+                        id: id.to_string(),
+                        description: "".to_string(),
+                        tasks: vec![Task {
+                            id: format!("{id}_1"),
+                            description: "".to_string(),
+                            action_items: vec![Action::custom(&format!("{id}_1_ID"))],
+                            ordered: false,
+                        }],
+                        optional: false,
+                        branch_group: String::new(),
+                    })
+                    .collect(),
+            }),
+            ..Default::default()
+        };
+        let events = ["A_1_ID", "B_1_ID"]
+            .iter()
+            .map(|action| Event {
+                id: uuid::Uuid::new_v4().to_string(),
+                address: "0xA".to_string(),
+                action: Some(Action::custom(action)),
+                instance_id: None,
+            })
+            .collect::<Vec<_>>();
+
+        let states = [get_state(&quest, &events[..1]), get_state(&quest, &events)];
+        assert_eq!(states[0].steps_completed, vec!["A"]);
+        assert!(states[1].is_completed());
+
+        let state = get_state_at_event(&quest, &events, &events[0].id).unwrap();
+        assert_eq!(state, states[0]);
+        assert!(get_state_at_event(&quest, &events, "unknown").is_none());
+
+        let timed_events = vec![(10, events[0].clone()), (20, events[1].clone())];
+        let state = get_state_at_timestamp(&quest, &timed_events, 5);
+        assert!(state.steps_completed.is_empty());
+        assert!(state.current_steps.contains_key("A"));
+        assert_eq!(get_state_at_timestamp(&quest, &timed_events, 19), states[0]);
+        assert_eq!(get_state_at_timestamp(&quest, &timed_events, 20), states[1]);
+    }
+
     #[test]
     fn quest_graph_single_step_apply_event_works() {
         let quest = Quest {
@@ -721,7 +1308,10 @@ mod tests {
                         id: "A1_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::custom("A1_1_ID")],
+                        ordered: false,
                     }],
+                    optional: false,
+                    branch_group: String::new(),
                 }],
             }),
             ..Default::default()
@@ -732,6 +1322,7 @@ mod tests {
             id: uuid::Uuid::new_v4().to_string(),
             address: "0xA".to_string(),
             action: Some(Action::custom("A1_1_ID")),
+            instance_id: None,
         }];
 
         let mut state = QuestState::from(&quest_graph);
@@ -765,7 +1356,10 @@ mod tests {
                             id: "A1_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![Action::custom("A1_1_ID")],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                     Step {
                         id: "B1".to_string(),
@@ -774,7 +1368,10 @@ mod tests {
                             id: "B1_1".to_string(),
                             description: "".to_string(),
                             action_items: vec![Action::custom("B1_1_ID")],
+                            ordered: false,
                         }],
+                        optional: false,
+                        branch_group: String::new(),
                     },
                 ],
             }),
@@ -787,12 +1384,14 @@ mod tests {
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::custom("A1_1_ID")),
+                instance_id: None,
             },
             Event {
                 // A1_1
                 id: uuid::Uuid::new_v4().to_string(),
                 address: "0xA".to_string(),
                 action: Some(Action::custom("B1_1_ID")),
+                instance_id: None,
             },
         ];
 
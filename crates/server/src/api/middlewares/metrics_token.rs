@@ -7,33 +7,34 @@ use actix_web::{
 use actix_web_lab::middleware::{from_fn, Next};
 
 fn validate_token(bearer_token: String, request: &ServiceRequest) -> Result<(), Error> {
-    let path = request.path();
-    if path == "/metrics" {
-        let token = request
-            .headers()
-            .get("Authorization")
-            .map(|token| token.to_str());
+    if request.path() == "/metrics" {
+        validate_bearer_token(&bearer_token, request)?;
+    }
 
-        if bearer_token.is_empty() {
-            log::error!("missing wkc_metrics_bearer_token in configuration");
-            return Err(ErrorInternalServerError(""));
-        }
+    Ok(())
+}
 
-        match token {
-            Some(Ok(token)) if token.starts_with("Bearer") => {
-                let parts = token.splitn(2, ' ').collect::<Vec<&str>>();
-                match parts.get(1) {
-                    Some(token) if *token == bearer_token => {}
-                    _ => return Err(ErrorUnauthorized("Invalid token")),
-                }
-            }
-            _ => {
-                return Err(ErrorUnauthorized("Token not present"));
+fn validate_bearer_token(bearer_token: &str, request: &ServiceRequest) -> Result<(), Error> {
+    let token = request
+        .headers()
+        .get("Authorization")
+        .map(|token| token.to_str());
+
+    if bearer_token.is_empty() {
+        log::error!("missing wkc_metrics_bearer_token in configuration");
+        return Err(ErrorInternalServerError(""));
+    }
+
+    match token {
+        Some(Ok(token)) if token.starts_with("Bearer") => {
+            let parts = token.splitn(2, ' ').collect::<Vec<&str>>();
+            match parts.get(1) {
+                Some(token) if *token == bearer_token => Ok(()),
+                _ => Err(ErrorUnauthorized("Invalid token")),
             }
         }
+        _ => Err(ErrorUnauthorized("Token not present")),
     }
-
-    Ok(())
 }
 
 pub fn metrics_token<S, B>(
@@ -58,3 +59,27 @@ where
         }
     })
 }
+
+/// Requires the metrics bearer token on every request of the wrapped scope
+pub fn bearer_token<S, B>(
+    bearer_token: &str,
+) -> impl Transform<
+    S,
+    ServiceRequest,
+    Response = ServiceResponse<impl MessageBody>,
+    Error = Error,
+    InitError = (),
+>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    let token = bearer_token.to_owned();
+    from_fn(move |req, next: Next<B>| {
+        let token = token.clone();
+        async move {
+            validate_bearer_token(&token, &req)?;
+            next.call(req).await
+        }
+    })
+}
@@ -5,4 +5,4 @@ mod tracing;
 pub use self::tracing::initialize_telemetry;
 pub use auth::optional_auth::OptionalAuthUser;
 pub use auth::required_auth::RequiredAuthUser;
-pub use metrics_token::metrics_token;
+pub use metrics_token::{bearer_token, metrics_token};
@@ -14,22 +14,38 @@ use actix_web::{
 };
 use dcl_http_prom_metrics::HttpMetricsCollector;
 use quests_db::Database;
-use quests_message_broker::messages_queue::RedisMessagesQueue;
+use quests_message_broker::{
+    channel::RedisChannelPublisher, dead_letters::RedisDeadLetterQueue,
+    idempotency_keys::RedisIdempotencyKeys,
+};
+use quests_system::event_processing::EventsQueue;
 use tracing_actix_web::TracingLogger;
 
 pub async fn run_server(
     config: Data<Config>,
     database: Data<Database>,
-    events_queue: Data<RedisMessagesQueue>,
+    events_queue: Data<EventsQueue>,
+    dead_letters: Data<RedisDeadLetterQueue>,
+    idempotency_keys: Data<RedisIdempotencyKeys>,
+    quests_channel: Data<RedisChannelPublisher>,
     metrics_collector: Data<HttpMetricsCollector>,
 ) -> Server {
     let server_address = format!("0.0.0.0:{}", config.http_server_port);
 
     let server = HttpServer::new(move || {
-        get_app_router(&config, &database, &events_queue, &metrics_collector)
+        get_app_router(
+            &config,
+            &database,
+            &events_queue,
+            &dead_letters,
+            &idempotency_keys,
+            &quests_channel,
+            &metrics_collector,
+        )
     })
     .bind(&server_address)
     .unwrap() // Unwrap because if it's not able to bind, it doens't matter the panic
+    .disable_signals() // Stopped by the app, after the events being processed and the transports
     .run();
 
     log::info!("Quests REST API running at http://{}", server_address);
@@ -40,7 +56,10 @@ pub async fn run_server(
 pub fn get_app_router(
     config: &Data<Config>,
     database: &Data<Database>,
-    events_queue: &Data<RedisMessagesQueue>,
+    events_queue: &Data<EventsQueue>,
+    dead_letters: &Data<RedisDeadLetterQueue>,
+    idempotency_keys: &Data<RedisIdempotencyKeys>,
+    quests_channel: &Data<RedisChannelPublisher>,
     metrics_collector: &Data<HttpMetricsCollector>,
 ) -> App<
     impl ServiceFactory<
@@ -59,10 +78,15 @@ pub fn get_app_router(
         .app_data(config.clone())
         .app_data(database.clone())
         .app_data(events_queue.clone())
+        .app_data(dead_letters.clone())
+        .app_data(idempotency_keys.clone())
+        .app_data(quests_channel.clone())
         .app_data(metrics_collector.clone())
         .wrap(dcl_http_prom_metrics::metrics())
         .wrap(middlewares::metrics_token(&config.wkc_metrics_bearer_token))
         .wrap(TracingLogger::default())
         .wrap(cors)
-        .configure(routes::services)
+        .configure(|service_config| {
+            routes::services(service_config, &config.wkc_metrics_bearer_token)
+        })
 }
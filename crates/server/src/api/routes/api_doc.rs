@@ -1,4 +1,6 @@
 use super::creators;
+use super::dead_letters;
+use super::errors;
 use super::health;
 use super::quest_instances;
 use super::quests;
@@ -23,6 +25,7 @@ use utoipa_redoc::Servable;
                 quests::get_quest_stats,
                 quests::update_quest,
                 quests::create_quest,
+                quests::validate_quest,
                 quests::delete_quest,
                 quests::get_quest_stats,
                 quests::activate_quest,
@@ -31,9 +34,13 @@ use utoipa_redoc::Servable;
                 creators::get_quests_by_creator_id,
                 quest_instances::reset_quest_instance,
                 quest_instances::get_quest_instance_state,
+                quest_instances::get_quest_instance_events,
                 quest_instances::add_event_to_instance,
                 quest_instances::get_quest_instance,
                 quest_instances::remove_event_from_instance,
+                dead_letters::get_dead_letters,
+                dead_letters::replay_dead_letter,
+                dead_letters::drop_dead_letter,
         ),
         components(
                 schemas(
@@ -44,10 +51,12 @@ use utoipa_redoc::Servable;
                         quests::get_quests::GetQuestsResponse,
                         quests::update_quest::UpdateQuestRequest,
                         quests::update_quest::UpdateQuestResponse,
+                        quests::validate_quest::ValidateQuestResponse,
                         quests::get_quest_reward::GetQuestRewardResponse,
                         quests::get_quest_stats::GetQuestStatsResponse,
                         quests::get_quest_updates::GetQuestUpdatesResponse,
                         creators::get_quests_by_creator_id::GetCreatorQuestsResponse,
+                        errors::ValidationErrorDetail,
                         quests_protocol::definitions::Quest,
                         quests_protocol::definitions::QuestDefinition,
                         quests_protocol::definitions::Step,
@@ -56,25 +65,33 @@ use utoipa_redoc::Servable;
                         quests_protocol::definitions::Connection,
                         quests_protocol::definitions::QuestState,
                         quests_protocol::definitions::StepContent,
+                        quests_protocol::definitions::ActionProgress,
+                        quests_protocol::definitions::BranchGroup,
                         quests_protocol::definitions::EventRequest,
                         quests_protocol::definitions::Task,
                         quests_db::core::definitions::QuestReward,
                         quests_db::core::definitions::QuestRewardHook,
                         quests_db::core::definitions::QuestRewardItem,
+                        quests_db::core::definitions::RepeatPolicy,
                         quests_db::core::definitions::Event,
                         quests_db::core::definitions::QuestInstance,
                         quest_instances::state::GetInstanceStateResponse,
+                        quest_instances::get_events::GetInstanceEventsResponse,
+                        quest_instances::get_events::InstanceEvent,
                         quests::get_instances::GetQuestInstancesResponse,
                         quests::get_instances::GetQuestInstancesQuery,
                         quest_instances::add_event::AddEventToInstancePayload,
                         quest_instances::add_event::AddEventToInstanceResponse,
                         quest_instances::get::GetQuestInstanceResponse,
+                        dead_letters::get_dead_letters::GetDeadLettersResponse,
+                        dead_letters::get_dead_letters::DeadLetterEvent,
                 )
         ),
         tags(
             (name = "quests", description = "Quests endpoints."),
             (name = "creators", description = "Creators endpoints."),
-            (name = "quest_instances", description = "Quest Instances endpoints.")
+            (name = "quest_instances", description = "Quest Instances endpoints."),
+            (name = "dead_letters", description = "Dead-lettered events endpoints.")
         ),
 )]
 struct ApiDoc;
@@ -0,0 +1,33 @@
+use crate::api::routes::errors::CommonError;
+use actix_web::{delete, web, HttpResponse};
+use quests_message_broker::dead_letters::{DeadLetter, DeadLetterQueue, RedisDeadLetterQueue};
+use quests_protocol::definitions::Event;
+
+/// Remove an event from the dead letters without processing it. Requires the metrics bearer token
+#[utoipa::path(
+  context_path = "/dead-letters",
+  params(
+      ("event_id" = String, description = "Event ID")
+  ),
+  responses(
+      (status = 204, description = "Event was removed from the dead letters"),
+      (status = 401, description = "Unauthorized"),
+      (status = 404, description = "Event not found in the dead letters"),
+      (status = 500, description = "Internal Server Error")
+  )
+)]
+#[delete("/{event_id}")]
+pub async fn drop_dead_letter(
+    dead_letters: web::Data<RedisDeadLetterQueue>,
+    event_id: web::Path<String>,
+) -> HttpResponse {
+    let result: Result<Option<DeadLetter<Event>>, _> = dead_letters.take(&event_id).await;
+    match result {
+        Ok(Some(_)) => HttpResponse::NoContent().finish(),
+        Ok(None) => HttpResponse::from_error(CommonError::NotFound),
+        Err(err) => {
+            log::error!("Error dropping dead letter {event_id}: {err}");
+            HttpResponse::from_error(CommonError::Unknown)
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::api::routes::errors::CommonError;
+use actix_web::{get, web, HttpResponse};
+use quests_message_broker::dead_letters::{DeadLetter, DeadLetterQueue, RedisDeadLetterQueue};
+use quests_protocol::definitions::{Action, Event};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// An event that couldn't be processed after all its attempts
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DeadLetterEvent {
+    pub id: String,
+    pub address: String,
+    pub action: Option<Action>,
+    pub instance_id: Option<String>,
+    pub attempts: u32,
+    /// Error of the last attempt
+    pub reason: String,
+    /// Unix timestamp in seconds of the last attempt
+    pub failed_at: i64,
+}
+
+impl From<DeadLetter<Event>> for DeadLetterEvent {
+    fn from(dead_letter: DeadLetter<Event>) -> Self {
+        Self {
+            id: dead_letter.message.id,
+            address: dead_letter.message.address,
+            action: dead_letter.message.action,
+            instance_id: dead_letter.message.instance_id,
+            attempts: dead_letter.attempts,
+            reason: dead_letter.reason,
+            failed_at: dead_letter.failed_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GetDeadLettersResponse {
+    pub dead_letters: Vec<DeadLetterEvent>,
+}
+
+/// Get the events that were moved to the dead letters after failing all their attempts. Requires the metrics bearer token
+#[utoipa::path(
+  context_path = "/dead-letters",
+  responses(
+      (status = 200, description = "Dead-lettered events", body = GetDeadLettersResponse),
+      (status = 401, description = "Unauthorized"),
+      (status = 500, description = "Internal Server Error")
+  )
+)]
+#[get("")]
+pub async fn get_dead_letters(dead_letters: web::Data<RedisDeadLetterQueue>) -> HttpResponse {
+    let result: Result<Vec<DeadLetter<Event>>, _> = dead_letters.list().await;
+    match result {
+        Ok(dead_letters) => HttpResponse::Ok().json(GetDeadLettersResponse {
+            dead_letters: dead_letters
+                .into_iter()
+                .map(DeadLetterEvent::from)
+                .collect(),
+        }),
+        Err(err) => {
+            log::error!("Error getting dead letters: {err}");
+            HttpResponse::from_error(CommonError::Unknown)
+        }
+    }
+}
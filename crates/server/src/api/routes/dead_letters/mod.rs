@@ -0,0 +1,21 @@
+pub mod drop_dead_letter;
+pub mod get_dead_letters;
+pub mod replay_dead_letter;
+
+use actix_web::{web, Scope};
+pub use drop_dead_letter::*;
+pub use get_dead_letters::*;
+pub use replay_dead_letter::*;
+
+use crate::api::middlewares;
+
+// dead letters contain the users' events and allow to replay them, so they're only for operators
+pub fn services(api_scope: Scope, metrics_bearer_token: &str) -> Scope {
+    api_scope.service(
+        web::scope("/dead-letters")
+            .wrap(middlewares::bearer_token(metrics_bearer_token))
+            .service(get_dead_letters)
+            .service(replay_dead_letter)
+            .service(drop_dead_letter),
+    )
+}
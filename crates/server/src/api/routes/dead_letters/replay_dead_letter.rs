@@ -0,0 +1,54 @@
+use crate::api::routes::errors::CommonError;
+use actix_web::{post, web, HttpResponse};
+use quests_message_broker::dead_letters::{DeadLetter, DeadLetterQueue, RedisDeadLetterQueue};
+use quests_protocol::definitions::Event;
+use quests_system::event_processing::EventsQueue;
+
+/// Remove an event from the dead letters and push it back to the events queue with a fresh set of attempts. Requires the metrics bearer token
+#[utoipa::path(
+  context_path = "/dead-letters",
+  params(
+      ("event_id" = String, description = "Event ID")
+  ),
+  responses(
+      (status = 204, description = "Event was pushed back to the events queue"),
+      (status = 401, description = "Unauthorized"),
+      (status = 404, description = "Event not found in the dead letters"),
+      (status = 500, description = "Internal Server Error")
+  )
+)]
+#[post("/{event_id}/replay")]
+pub async fn replay_dead_letter(
+    dead_letters: web::Data<RedisDeadLetterQueue>,
+    events_queue: web::Data<EventsQueue>,
+    event_id: web::Path<String>,
+) -> HttpResponse {
+    match replay_dead_letter_controller(&dead_letters, events_queue.as_ref(), &event_id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => HttpResponse::from_error(err),
+    }
+}
+
+async fn replay_dead_letter_controller(
+    dead_letters: &RedisDeadLetterQueue,
+    events_queue: &EventsQueue,
+    event_id: &str,
+) -> Result<(), CommonError> {
+    let dead_letter: Option<DeadLetter<Event>> =
+        dead_letters.take(event_id).await.map_err(|err| {
+            log::error!("Error taking dead letter {event_id}: {err}");
+            CommonError::Unknown
+        })?;
+    let Some(dead_letter) = dead_letter else {
+        return Err(CommonError::NotFound);
+    };
+
+    if let Err(err) = events_queue.push(&dead_letter.message).await {
+        log::error!("Error pushing dead letter {event_id} to the events queue: {err}");
+        // put it back so it's not lost
+        let _ = dead_letters.push(&dead_letter).await;
+        return Err(CommonError::Unknown);
+    }
+
+    Ok(())
+}
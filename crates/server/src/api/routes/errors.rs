@@ -4,11 +4,31 @@ use quests_db::core::errors::DBError;
 use quests_protocol::definitions::*;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ErrorResponse {
     pub code: u16,
     pub message: String,
+    /// Every validation error found in the request, only present for validation errors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ValidationErrorDetail>,
+}
+
+/// A validation error and the path to the field of the request causing it, like `definition.steps[2].tasks[0]`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct ValidationErrorDetail {
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationErrorDetail {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -38,6 +58,7 @@ impl ResponseError for CommonError {
         let error_response = ErrorResponse {
             code: status_code.as_u16(),
             message: self.to_string(),
+            errors: vec![],
         };
         HttpResponse::build(status_code).json(error_response)
     }
@@ -48,9 +69,13 @@ impl ResponseError for QuestError {
         match self {
             Self::DeserializationError => StatusCode::INTERNAL_SERVER_ERROR,
             Self::CommonError(base) => base.status_code(),
-            Self::QuestValidation(_) | Self::QuestAlreadyStarted | Self::QuestAlreadyCompleted => {
-                StatusCode::BAD_REQUEST
-            }
+            Self::QuestValidation(_)
+            | Self::QuestValidationReport(_)
+            | Self::QuestAlreadyStarted
+            | Self::QuestAlreadyCompleted
+            | Self::QuestNotAvailable
+            | Self::QuestNotRepeatable(_)
+            | Self::PrerequisitesNotCompleted(_) => StatusCode::BAD_REQUEST,
             Self::NotInstanceOwner => StatusCode::FORBIDDEN,
             Self::NotFoundOrInactive => StatusCode::NOT_FOUND,
             Self::NotQuestCreator => StatusCode::FORBIDDEN,
@@ -64,9 +89,14 @@ impl ResponseError for QuestError {
 
     fn error_response(&self) -> HttpResponse {
         let status_code = self.status_code();
+        let errors = match self {
+            Self::QuestValidationReport(errors) => errors.clone(),
+            _ => vec![],
+        };
         let error_response = ErrorResponse {
             code: status_code.as_u16(),
             message: self.to_string(),
+            errors,
         };
         HttpResponse::build(status_code).json(error_response)
     }
@@ -2,6 +2,7 @@ use actix_web::web::{self, ServiceConfig};
 
 mod api_doc;
 pub mod creators;
+pub mod dead_letters;
 pub mod errors;
 mod health;
 pub mod quest_instances;
@@ -9,13 +10,14 @@ pub mod quests;
 
 pub use errors::{query_extractor_config, ErrorResponse};
 
-pub(crate) fn services(config: &mut ServiceConfig) {
+pub(crate) fn services(config: &mut ServiceConfig, metrics_bearer_token: &str) {
     api_doc::services(config);
 
     let api_scope = web::scope("/api");
     let api_scope = quests::services(api_scope);
     let api_scope = creators::services(api_scope);
     let api_scope = quest_instances::services(api_scope);
+    let api_scope = dead_letters::services(api_scope, metrics_bearer_token);
     config.service(api_scope);
 
     health::services(config);
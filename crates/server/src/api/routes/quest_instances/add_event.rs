@@ -4,8 +4,9 @@ use crate::{
 };
 use actix_web::{post, web, HttpResponse};
 use quests_db::{core::definitions::QuestsDatabase, Database};
-use quests_message_broker::messages_queue::RedisMessagesQueue;
+use quests_message_broker::idempotency_keys::RedisIdempotencyKeys;
 use quests_protocol::definitions::EventRequest;
+use quests_system::event_processing::EventsQueue;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -35,7 +36,8 @@ pub struct AddEventToInstanceResponse {
 #[post("/instances/{quest_instance}/events")]
 pub async fn add_event_to_instance(
     data: web::Data<Database>,
-    events_queue: web::Data<RedisMessagesQueue>,
+    events_queue: web::Data<EventsQueue>,
+    idempotency_keys: web::Data<RedisIdempotencyKeys>,
     quest_instance: web::Path<String>,
     event: web::Json<AddEventToInstancePayload>,
     auth_user: RequiredAuthUser,
@@ -50,7 +52,9 @@ pub async fn add_event_to_instance(
             Ok(_) => {
                 match add_event_controller(
                     events_queue.into_inner(),
+                    &idempotency_keys,
                     &instance.user_address,
+                    Some(&instance.id),
                     event.event.to_owned(),
                 )
                 .await
@@ -0,0 +1,165 @@
+use crate::{
+    api::{middlewares::RequiredAuthUser, routes::errors::CommonError},
+    domain::quests::QuestError,
+};
+use actix_web::{get, web, HttpResponse};
+use quests_db::{
+    core::definitions::{EventsFilter, QuestsDatabase},
+    Database,
+};
+use quests_protocol::{
+    definitions::{Action, Event, ProtocolMessage},
+    quests::get_events_progress_from,
+};
+use quests_system::{get_instance_state_before_event, get_quest_with_decoded_definition};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Max number of events returned in a page
+const MAX_LIMIT: i64 = 100;
+/// Latest timestamp accepted in the filters, 9999-12-31T23:59:59Z
+const MAX_TIMESTAMP: i64 = 253_402_300_799;
+
+#[derive(Deserialize, IntoParams, ToSchema)]
+pub struct GetInstanceEventsQuery {
+    offset: Option<i64>,
+    /// Between 1 and 100
+    limit: Option<i64>,
+    /// Unix timestamp in seconds of the oldest event to return
+    from: Option<i64>,
+    /// Unix timestamp in seconds of the newest event to return
+    to: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct InstanceEvent {
+    pub id: String,
+    pub timestamp: i64,
+    pub action: Option<Action>,
+    /// Steps completed by the event
+    pub steps_completed: Vec<String>,
+    /// Tasks with an action item done by the event
+    pub tasks_advanced: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GetInstanceEventsResponse {
+    pub events: Vec<InstanceEvent>,
+}
+
+/// Get the events of a Quest Instance in the order they were applied. Allowed for the Quest Creator and the user playing the Quest
+#[utoipa::path(
+  params(
+      ("quest_instance" = String, description = "Quest Instance UUID"),
+      GetInstanceEventsQuery
+  ),
+  responses(
+      (status = 200, description = "Quest Instance events", body = GetInstanceEventsResponse),
+      (status = 400, description = "Bad Request"),
+      (status = 401, description = "Unauthorized"),
+      (status = 403, description = "Forbidden"),
+      (status = 404, description = "Quest Instance not found"),
+      (status = 500, description = "Internal Server Error")
+  )
+)]
+#[get("/instances/{quest_instance}/events")]
+pub async fn get_quest_instance_events(
+    data: web::Data<Database>,
+    quest_instance: web::Path<String>,
+    query: web::Query<GetInstanceEventsQuery>,
+    auth_user: RequiredAuthUser,
+) -> HttpResponse {
+    let db = data.into_inner();
+
+    let RequiredAuthUser { address } = auth_user;
+
+    let filter = match query.to_events_filter() {
+        Ok(filter) => filter,
+        Err(err) => return HttpResponse::from_error(err),
+    };
+
+    match get_quest_instance_events_controller(db, &address, &quest_instance, &filter).await {
+        Ok(events) => HttpResponse::Ok().json(GetInstanceEventsResponse { events }),
+        Err(err) => HttpResponse::from_error(err),
+    }
+}
+
+async fn get_quest_instance_events_controller(
+    db: Arc<impl QuestsDatabase>,
+    auth_user_address: &str,
+    quest_instance_id: &str,
+    filter: &EventsFilter,
+) -> Result<Vec<InstanceEvent>, QuestError> {
+    let instance = db.get_quest_instance(quest_instance_id).await?;
+    if !instance
+        .user_address
+        .eq_ignore_ascii_case(auth_user_address)
+        && !db
+            .is_quest_creator(&instance.quest_id, auth_user_address)
+            .await?
+    {
+        return Err(QuestError::NotInstanceOwner);
+    }
+
+    let page = db.get_events_page(&instance.id, filter).await?;
+    let Some(first_event) = page.first() else {
+        return Ok(vec![]);
+    };
+
+    // the progress of each event depends on the ones before it, so the page is applied from the state right before it
+    let quest = get_quest_with_decoded_definition(db.clone(), &instance.quest_id).await?;
+    let initial_state =
+        get_instance_state_before_event(db.clone(), &quest, &instance.id, &first_event.id).await?;
+    let events = page
+        .iter()
+        .map(|event| Event::decode(event.event.as_slice()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let progress = get_events_progress_from(&quest, &initial_state, &events);
+
+    Ok(page
+        .into_iter()
+        .zip(events)
+        .zip(progress)
+        .map(|((stored_event, event), progress)| InstanceEvent {
+            id: stored_event.id,
+            timestamp: stored_event.timestamp,
+            action: event.action,
+            steps_completed: progress.steps_completed,
+            tasks_advanced: progress.tasks_advanced,
+        })
+        .collect())
+}
+
+impl GetInstanceEventsQuery {
+    fn to_events_filter(&self) -> Result<EventsFilter, QuestError> {
+        let bad_request =
+            |message: &str| QuestError::CommonError(CommonError::BadRequest(message.to_string()));
+
+        let offset = self.offset.unwrap_or(0);
+        if offset < 0 {
+            return Err(bad_request("offset can't be negative"));
+        }
+        let limit = self.limit.unwrap_or(50);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(bad_request("limit must be between 1 and 100"));
+        }
+        for timestamp in [self.from, self.to].into_iter().flatten() {
+            if !(0..=MAX_TIMESTAMP).contains(&timestamp) {
+                return Err(bad_request("from and to must be valid unix timestamps"));
+            }
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(bad_request("from can't be after to"));
+            }
+        }
+
+        Ok(EventsFilter {
+            offset,
+            limit,
+            from: self.from,
+            to: self.to,
+        })
+    }
+}
@@ -1,5 +1,6 @@
 pub mod add_event;
 pub mod get;
+pub mod get_events;
 pub mod remove_event;
 pub mod reset;
 pub mod state;
@@ -7,6 +8,7 @@ pub mod state;
 use actix_web::Scope;
 pub use add_event::*;
 pub use get::*;
+pub use get_events::*;
 pub use remove_event::*;
 pub use reset::*;
 pub use state::*;
@@ -16,6 +18,7 @@ pub fn services(api_scope: Scope) -> Scope {
         .service(reset_quest_instance)
         .service(get_quest_instance_state)
         .service(get_quest_instance)
+        .service(get_quest_instance_events)
         .service(add_event_to_instance)
         .service(remove_event_from_instance)
 }
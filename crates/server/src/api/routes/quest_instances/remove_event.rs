@@ -1,11 +1,19 @@
-use crate::{api::middlewares::RequiredAuthUser, domain::quests::QuestError};
+use crate::{
+    api::middlewares::RequiredAuthUser,
+    domain::quests::{refresh_instance_state, QuestError},
+};
 use actix_web::{delete, web, HttpResponse};
 use quests_db::{core::definitions::QuestsDatabase, Database};
+use quests_message_broker::channel::RedisChannelPublisher;
+use std::sync::Arc;
 
-/// Get Quest Instance's state. Allowed for the Quest Creator
+/// Remove an event from a Quest Instance. Allowed for the Quest Creator
+///
+/// The state of the Quest Instance is calculated again and sent to the user
 #[utoipa::path(
 params(
-    ("quest_instance" = String, description = "Quest Instance UUID")
+    ("quest_instance" = String, description = "Quest Instance UUID"),
+    ("event_id" = String, description = "Event UUID")
 ),
 responses(
     (status = 204, description = "Event removed"),
@@ -18,6 +26,7 @@ responses(
 #[delete("/instances/{quest_instance}/events/{event_id}")]
 pub async fn remove_event_from_instance(
     data: web::Data<Database>,
+    quests_channel: web::Data<RedisChannelPublisher>,
     path: web::Path<(String, String)>,
     auth_user: RequiredAuthUser,
 ) -> HttpResponse {
@@ -27,22 +36,30 @@ pub async fn remove_event_from_instance(
 
     let RequiredAuthUser { address } = auth_user;
 
-    match db.get_quest_instance(&quest_instance_id).await {
-        Ok(instance) => match db.is_quest_creator(&instance.quest_id, &address).await {
-            Ok(is_creator) if !is_creator => HttpResponse::from_error(QuestError::NotQuestCreator),
-            Ok(_) => match db.remove_event(&event_id).await {
-                // we remove it from completed instance in case it's already completed
-                Ok(_) => match db
-                    .remove_instance_from_completed_instances(&instance.id)
-                    .await
-                {
-                    Ok(_) => HttpResponse::NoContent().finish(),
-                    Err(err) => HttpResponse::from_error(QuestError::from(err)),
-                },
-                Err(err) => HttpResponse::from_error(QuestError::from(err)),
-            },
-            Err(err) => HttpResponse::from_error(QuestError::from(err)),
-        },
-        Err(err) => HttpResponse::from_error(QuestError::from(err)),
+    match remove_event_controller(db, &quests_channel, &address, &quest_instance_id, &event_id)
+        .await
+    {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => HttpResponse::from_error(err),
     }
 }
+
+async fn remove_event_controller(
+    db: Arc<impl QuestsDatabase>,
+    quests_channel: &RedisChannelPublisher,
+    auth_user_address: &str,
+    quest_instance_id: &str,
+    event_id: &str,
+) -> Result<(), QuestError> {
+    let instance = db.get_quest_instance(quest_instance_id).await?;
+    if !db
+        .is_quest_creator(&instance.quest_id, auth_user_address)
+        .await?
+    {
+        return Err(QuestError::NotQuestCreator);
+    }
+
+    db.remove_event(&instance.id, event_id).await?;
+
+    refresh_instance_state(db, quests_channel, &instance, event_id).await
+}
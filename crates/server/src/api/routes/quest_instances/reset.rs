@@ -1,6 +1,10 @@
-use crate::{api::middlewares::RequiredAuthUser, domain::quests::QuestError};
+use crate::{
+    api::middlewares::RequiredAuthUser,
+    domain::quests::{refresh_instance_state, QuestError},
+};
 use actix_web::{patch, web, HttpResponse};
 use quests_db::{core::definitions::QuestsDatabase, Database};
+use quests_message_broker::channel::RedisChannelPublisher;
 use std::sync::Arc;
 
 /// Reset a User's Quest Instance. It can only be executed by the Quest Creator
@@ -19,6 +23,7 @@ use std::sync::Arc;
 #[patch("/instances/{quest_instance}/reset")]
 pub async fn reset_quest_instance(
     data: web::Data<Database>,
+    quests_channel: web::Data<RedisChannelPublisher>,
     quest_instance: web::Path<String>,
     auth_user: RequiredAuthUser,
 ) -> HttpResponse {
@@ -26,7 +31,7 @@ pub async fn reset_quest_instance(
 
     let RequiredAuthUser { address } = auth_user;
 
-    match reset_quest_instance_controller(db, &address, &quest_instance).await {
+    match reset_quest_instance_controller(db, &quests_channel, &address, &quest_instance).await {
         Ok(_) => HttpResponse::NoContent().finish(),
         Err(err) => HttpResponse::from_error(err),
     }
@@ -34,6 +39,7 @@ pub async fn reset_quest_instance(
 
 async fn reset_quest_instance_controller(
     db: Arc<impl QuestsDatabase>,
+    quests_channel: &RedisChannelPublisher,
     auth_user_address: &str,
     quest_instance_id: &str,
 ) -> Result<(), QuestError> {
@@ -52,14 +58,7 @@ async fn reset_quest_instance_controller(
                         err
                     })?;
 
-                db.remove_instance_from_completed_instances(quest_instance_id)
-                    .await
-                    .map_err(|err| {
-                        let err: QuestError = err.into();
-                        err
-                    })?;
-
-                Ok(())
+                refresh_instance_state(db, quests_channel, &instance, "").await
             }
             Err(err) => {
                 log::error!("Error getting quest: {:?}", err);
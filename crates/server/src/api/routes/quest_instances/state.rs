@@ -1,13 +1,20 @@
-use crate::{api::middlewares::RequiredAuthUser, domain::quests::QuestError};
+use crate::{
+    api::{middlewares::RequiredAuthUser, routes::errors::CommonError},
+    domain::quests::QuestError,
+};
 use actix_web::{get, web, HttpResponse};
 use quests_db::{
     core::definitions::{Event, QuestsDatabase},
     Database,
 };
-use quests_protocol::definitions::QuestState;
+use quests_protocol::{
+    definitions::{Event as ProtocolEvent, ProtocolMessage, QuestState},
+    quests::{get_state_at_event, get_state_at_timestamp},
+};
 use quests_system::get_instance_state;
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct GetInstanceStateResponse {
@@ -15,16 +22,24 @@ pub struct GetInstanceStateResponse {
     pub events: Vec<Event>,
 }
 
+#[derive(Deserialize, IntoParams, ToSchema)]
+pub struct GetInstanceStateQuery {
+    /// Event UUID or unix timestamp in seconds. When present, the state is calculated only with the events applied
+    /// until then
+    at: Option<String>,
+}
+
 /// Get Quest Instance's state. Allowed for the Quest Creator
 #[utoipa::path(
   params(
-      ("quest_instance" = String, description = "Quest Instance UUID")
+      ("quest_instance" = String, description = "Quest Instance UUID"),
+      GetInstanceStateQuery
   ),
   responses(
       (status = 200, description = "Quest Instance state", body = GetInstanceStateResponse),
       (status = 401, description = "Unauthorized"),
       (status = 403, description = "Forbidden"),
-      (status = 404, description = "Quest Instance not found"),
+      (status = 404, description = "Quest Instance or Event not found"),
       (status = 500, description = "Internal Server Error")
   )
 )]
@@ -32,23 +47,72 @@ pub struct GetInstanceStateResponse {
 pub async fn get_quest_instance_state(
     data: web::Data<Database>,
     quest_instance: web::Path<String>,
+    query: web::Query<GetInstanceStateQuery>,
     auth_user: RequiredAuthUser,
 ) -> HttpResponse {
     let db = data.into_inner();
 
     let RequiredAuthUser { address } = auth_user;
 
-    match db.get_quest_instance(&quest_instance).await {
-        Ok(instance) => match db.is_quest_creator(&instance.quest_id, &address).await {
-            Ok(is_creator) if !is_creator => HttpResponse::from_error(QuestError::NotQuestCreator),
-            Ok(_) => match get_instance_state(db.clone(), &instance.quest_id, &instance.id).await {
-                Ok((_, state, events)) => {
-                    HttpResponse::Ok().json(GetInstanceStateResponse { state, events })
-                }
-                Err(err) => HttpResponse::from_error(QuestError::from(err)),
-            },
-            Err(err) => HttpResponse::from_error(QuestError::from(err)),
-        },
-        Err(err) => HttpResponse::from_error(QuestError::from(err)),
+    match get_quest_instance_state_controller(db, &address, &quest_instance, query.at.as_deref())
+        .await
+    {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(err) => HttpResponse::from_error(err),
     }
 }
+
+async fn get_quest_instance_state_controller(
+    db: Arc<impl QuestsDatabase>,
+    auth_user_address: &str,
+    quest_instance_id: &str,
+    at: Option<&str>,
+) -> Result<GetInstanceStateResponse, QuestError> {
+    let instance = db.get_quest_instance(quest_instance_id).await?;
+    if !db
+        .is_quest_creator(&instance.quest_id, auth_user_address)
+        .await?
+    {
+        return Err(QuestError::NotQuestCreator);
+    }
+
+    let (quest, state, events) = get_instance_state(db, &instance.quest_id, &instance.id).await?;
+    let Some(at) = at else {
+        return Ok(GetInstanceStateResponse { state, events });
+    };
+
+    let decoded_events = events
+        .iter()
+        .map(|event| ProtocolEvent::decode(event.event.as_slice()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (state, applied_events) = match at.parse::<i64>() {
+        Ok(timestamp) => {
+            let timed_events = events
+                .iter()
+                .map(|event| event.timestamp)
+                .zip(decoded_events)
+                .collect::<Vec<_>>();
+            let state = get_state_at_timestamp(&quest, &timed_events, timestamp);
+            let applied_events = events
+                .into_iter()
+                .take_while(|event| event.timestamp <= timestamp)
+                .collect();
+            (state, applied_events)
+        }
+        Err(_) => {
+            let state = get_state_at_event(&quest, &decoded_events, at)
+                .ok_or(QuestError::CommonError(CommonError::NotFound))?;
+            let mut applied_events = events;
+            if let Some(position) = applied_events.iter().position(|event| event.id == at) {
+                applied_events.truncate(position + 1);
+            }
+            (state, applied_events)
+        }
+    };
+
+    Ok(GetInstanceStateResponse {
+        state,
+        events: applied_events,
+    })
+}
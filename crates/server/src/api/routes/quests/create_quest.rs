@@ -1,17 +1,24 @@
 use super::is_url;
 use crate::{
-    api::{middlewares::RequiredAuthUser, routes::errors::CommonError},
-    domain::{quests::QuestError, types::ToCreateQuest},
+    api::{
+        middlewares::RequiredAuthUser,
+        routes::errors::{CommonError, ValidationErrorDetail},
+    },
+    domain::{
+        quests::{validate_prerequisites, QuestError},
+        types::ToCreateQuest,
+    },
 };
 use actix_web::{post, web, HttpResponse};
 use quests_db::{
-    core::definitions::{CreateQuest, QuestReward, QuestsDatabase},
+    core::definitions::{CreateQuest, QuestReward, QuestsDatabase, RepeatPolicy},
     Database,
 };
 use quests_protocol::definitions::*;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
+use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct CreateQuestResponse {
@@ -26,53 +33,127 @@ pub struct CreateQuestRequest {
     pub definition: QuestDefinition,
     pub image_url: String,
     pub reward: Option<QuestReward>,
+    /// Unix timestamp since the quest can be started
+    pub starts_at: Option<i64>,
+    /// Unix timestamp until the quest can be started
+    pub ends_at: Option<i64>,
+    /// Seconds the user has to complete the quest after starting it
+    pub instance_duration: Option<i64>,
+    /// IDs of the quests the user has to complete before starting this one
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    /// Whether the quest can be started again after completing it. By default it can't
+    #[serde(default)]
+    pub repeat_policy: RepeatPolicy,
 }
 
 impl CreateQuestRequest {
+    /// Validates the request collecting every error found, so all of them can be returned at once
     pub fn is_valid(&self) -> Result<(), QuestError> {
+        let mut errors = vec![];
+
         if self.name.trim().len() < 5 {
-            return Err(QuestError::QuestValidation(
-                "Name should be longer".to_string(),
-            ));
+            errors.push(ValidationErrorDetail::new("name", "Name should be longer"));
         }
 
         if self.description.trim().len() < 5 {
-            return Err(QuestError::QuestValidation(
-                "Description should be longer".to_string(),
+            errors.push(ValidationErrorDetail::new(
+                "description",
+                "Description should be longer",
             ));
         }
 
-        self.definition
-            .is_valid()
-            .map_err(|error| QuestError::QuestValidation(error.to_string()))?;
+        errors.extend(
+            self.definition
+                .validation_report()
+                .errors
+                .into_iter()
+                .map(|issue| {
+                    ValidationErrorDetail::new(
+                        format!("definition.{}", issue.path),
+                        issue.error.to_string(),
+                    )
+                }),
+        );
 
         if let Some(QuestReward { hook, items }) = &self.reward {
             if !is_url(&hook.webhook_url) {
-                return Err(QuestError::QuestValidation(
-                    "Webhook url is not valid".to_string(),
+                errors.push(ValidationErrorDetail::new(
+                    "reward.hook.webhook_url",
+                    "Webhook url is not valid",
+                ));
+            }
+
+            if items.is_empty() {
+                errors.push(ValidationErrorDetail::new(
+                    "reward.items",
+                    "Reward items must be at least one",
                 ));
             }
 
-            if !items.is_empty() {
-                if !items.iter().all(|item| is_url(&item.image_link)) {
-                    return Err(QuestError::QuestValidation(
-                        "Item's image link is not valid".to_string(),
+            for (index, item) in items.iter().enumerate() {
+                if !is_url(&item.image_link) {
+                    errors.push(ValidationErrorDetail::new(
+                        format!("reward.items[{index}].image_link"),
+                        "Item's image link is not valid",
                     ));
                 }
 
-                if !items.iter().all(|item| item.name.len() >= 3) {
-                    return Err(QuestError::QuestValidation(
-                        "Item name must be at least 3 characters".to_string(),
+                if item.name.len() < 3 {
+                    errors.push(ValidationErrorDetail::new(
+                        format!("reward.items[{index}].name"),
+                        "Item name must be at least 3 characters",
                     ));
                 }
-            } else {
-                return Err(QuestError::QuestValidation(
-                    "Reward items must be at least one".to_string(),
+            }
+        }
+
+        if let (Some(starts_at), Some(ends_at)) = (self.starts_at, self.ends_at) {
+            if ends_at <= starts_at {
+                errors.push(ValidationErrorDetail::new(
+                    "ends_at",
+                    "End date should be after the start date",
+                ));
+            }
+        }
+
+        if self.instance_duration.is_some_and(|duration| duration <= 0) {
+            errors.push(ValidationErrorDetail::new(
+                "instance_duration",
+                "Instance duration should be positive",
+            ));
+        }
+
+        for (index, prerequisite) in self.prerequisites.iter().enumerate() {
+            if Uuid::parse_str(prerequisite).is_err() {
+                errors.push(ValidationErrorDetail::new(
+                    format!("prerequisites[{index}]"),
+                    "Prerequisite should be a quest UUID",
                 ));
             }
         }
 
-        Ok(())
+        match self.repeat_policy {
+            RepeatPolicy::Times { count } if count <= 0 => {
+                errors.push(ValidationErrorDetail::new(
+                    "repeat_policy.count",
+                    "Repeat count should be positive",
+                ));
+            }
+            RepeatPolicy::Cooldown { seconds } if seconds <= 0 => {
+                errors.push(ValidationErrorDetail::new(
+                    "repeat_policy.seconds",
+                    "Cooldown should be positive",
+                ));
+            }
+            _ => {}
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(QuestError::QuestValidationReport(errors))
+        }
     }
 }
 
@@ -84,6 +165,11 @@ impl ToCreateQuest for CreateQuestRequest {
             definition,
             image_url,
             reward,
+            starts_at,
+            ends_at,
+            instance_duration,
+            prerequisites,
+            repeat_policy,
         } = self;
 
         Ok(CreateQuest {
@@ -92,6 +178,11 @@ impl ToCreateQuest for CreateQuestRequest {
             image_url,
             definition: definition.encode_to_vec(),
             reward: reward.to_owned(),
+            starts_at: *starts_at,
+            ends_at: *ends_at,
+            instance_duration: *instance_duration,
+            prerequisites: prerequisites.to_owned(),
+            repeat_policy: *repeat_policy,
         })
     }
 }
@@ -103,7 +194,7 @@ impl ToCreateQuest for CreateQuestRequest {
     request_body = CreateQuestRequest,
     responses(
         (status = 201, description = "Quest created", body = CreateQuestResponse),
-        (status = 400, description = "Bad Request"),
+        (status = 400, description = "Bad Request, with every validation error found in `errors`"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal Server Error")
     )
@@ -130,6 +221,7 @@ async fn create_quest_controller<DB: QuestsDatabase>(
     creator_address: &str,
 ) -> Result<String, QuestError> {
     create_quest_req.is_valid()?;
+    validate_prerequisites(db.clone(), &create_quest_req.prerequisites, None).await?;
 
     let quest = create_quest_req.to_create_quest()?;
     let id = db
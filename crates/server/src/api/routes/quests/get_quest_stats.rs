@@ -8,13 +8,18 @@ use std::{
 };
 use utoipa::ToSchema;
 
-use crate::{api::middlewares::RequiredAuthUser, domain::quests::QuestError};
+use crate::{
+    api::middlewares::RequiredAuthUser,
+    domain::quests::{unix_now, QuestError},
+};
 
 #[derive(Deserialize, Serialize, ToSchema)]
 pub struct GetQuestStatsResponse {
     pub active_players: usize,
     pub abandoned: usize,
     pub completed: usize,
+    /// Instances that reached their deadline without being completed. They aren't counted as active players
+    pub expired: usize,
     pub started_in_last_24_hours: usize,
 }
 
@@ -64,8 +69,10 @@ async fn get_quest_stats_controller<DB: QuestsDatabase>(
                     active_players: actives.len(),
                     abandoned: abandoned.len(),
                     completed: 0,
+                    expired: 0,
                     started_in_last_24_hours: 0,
                 };
+                let now = unix_now();
 
                 // TODO: All computation should be replaced by a cronjob and not done on demand
                 for active in &actives {
@@ -74,12 +81,21 @@ async fn get_quest_stats_controller<DB: QuestsDatabase>(
                     if is_within_24_hours(active.start_timestamp) {
                         stats.started_in_last_24_hours += 1;
                     }
-                    futs.push(async move { db_clone.is_completed_instance(&instance_id).await });
+                    let is_expired = active.is_expired_at(now);
+                    futs.push(async move {
+                        db_clone
+                            .is_completed_instance(&instance_id)
+                            .await
+                            .map(|is_completed| (is_completed, is_expired))
+                    });
                 }
 
-                while let Some(Ok(is_completed)) = futs.next().await {
+                while let Some(Ok((is_completed, is_expired))) = futs.next().await {
                     if is_completed {
                         stats.completed += 1;
+                    } else if is_expired {
+                        stats.expired += 1;
+                        stats.active_players -= 1;
                     }
                 }
 
@@ -8,6 +8,7 @@ pub mod get_quest_stats;
 pub mod get_quest_updates;
 pub mod get_quests;
 pub mod update_quest;
+pub mod validate_quest;
 
 pub use super::creators::get_quests_by_creator_id::get_quests_by_creator_id;
 pub use activate_quest::*;
@@ -23,11 +24,13 @@ pub use get_quest_updates::*;
 pub use get_quests::*;
 use regex::Regex;
 pub use update_quest::*;
+pub use validate_quest::*;
 
 pub fn services(api_scope: Scope) -> Scope {
     api_scope
         .service(get_quests)
         .service(create_quest)
+        .service(validate_quest)
         .service(update_quest)
         .service(delete_quest)
         .service(get_quest)
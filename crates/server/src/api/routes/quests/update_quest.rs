@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use crate::api::middlewares::RequiredAuthUser;
-use crate::domain::quests::QuestError;
+use crate::domain::quests::{validate_prerequisites, QuestError};
 use crate::domain::types::ToCreateQuest;
 
 use super::CreateQuestRequest;
@@ -30,7 +30,7 @@ pub struct UpdateQuestResponse {
     ),
     responses(
         (status = 200, description = "Quest updated", body = UpdateQuestResponse),
-        (status = 400, description = "Bad Request"),
+        (status = 400, description = "Bad Request, with every validation error found in `errors`"),
         (status = 400, description = "Requested Quest was previously updated and replaced with a new Quest"),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Quest not found"),
@@ -71,6 +71,7 @@ async fn update_quest_controller<DB: QuestsDatabase>(
             if !db.is_updatable(id).await? {
                 return Err(QuestError::QuestIsNotUpdatable);
             }
+            validate_prerequisites(db.clone(), &quest.prerequisites, Some(id)).await?;
             db.update_quest(id, &quest.to_create_quest()?, creator_address)
                 .await
                 .map_err(|error| {
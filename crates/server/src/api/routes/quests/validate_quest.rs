@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use actix_web::{post, web, HttpResponse};
+use quests_db::{core::definitions::QuestsDatabase, Database};
+use quests_protocol::{
+    definitions::*,
+    quests::{QuestGraph, START_STEP_ID},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::CreateQuestRequest;
+use crate::{
+    api::middlewares::RequiredAuthUser,
+    domain::quests::{validate_prerequisites, QuestError},
+};
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ValidateQuestResponse {
+    pub total_steps: usize,
+    pub starting_steps: Vec<String>,
+    pub required_steps: Vec<String>,
+    pub required_branch_groups: Vec<BranchGroup>,
+}
+
+/// Validate a quest without creating it.
+///
+/// Returns a summary of the quest graph
+#[utoipa::path(
+    request_body = CreateQuestRequest,
+    responses(
+        (status = 200, description = "Quest is valid", body = ValidateQuestResponse),
+        (status = 400, description = "Bad Request, with every validation error found in `errors`"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal Server Error")
+    )
+)]
+#[post("/quests/validate")]
+pub async fn validate_quest(
+    data: web::Data<Database>,
+    quest: web::Json<CreateQuestRequest>,
+    _auth_user: RequiredAuthUser,
+) -> HttpResponse {
+    let db = data.into_inner();
+
+    match validate_quest_controller(db, &quest).await {
+        Ok(summary) => HttpResponse::Ok().json(summary),
+        Err(error) => HttpResponse::from_error(error),
+    }
+}
+
+async fn validate_quest_controller<DB: QuestsDatabase>(
+    db: Arc<DB>,
+    validate_quest_req: &CreateQuestRequest,
+) -> Result<ValidateQuestResponse, QuestError> {
+    validate_quest_req.is_valid()?;
+    validate_prerequisites(db, &validate_quest_req.prerequisites, None).await?;
+
+    let quest = Quest {
+        definition: Some(validate_quest_req.definition.clone()),
+        ..Default::default()
+    };
+    let graph = QuestGraph::from(&quest);
+
+    let mut starting_steps = graph.next(START_STEP_ID).unwrap_or_default();
+    starting_steps.sort();
+    starting_steps.dedup();
+
+    Ok(ValidateQuestResponse {
+        total_steps: validate_quest_req.definition.steps.len(),
+        starting_steps,
+        required_steps: graph.required_for_end().unwrap_or_default(),
+        required_branch_groups: graph.required_branch_groups_for_end().unwrap_or_default(),
+    })
+}
@@ -1,4 +1,6 @@
-use quests_message_broker::messages_queue::MessagesQueue;
+use quests_message_broker::{
+    idempotency_keys::RedisIdempotencyKeys, messages_queue::MessagesQueue,
+};
 use quests_protocol::definitions::*;
 use std::sync::Arc;
 use thiserror::Error;
@@ -10,27 +12,74 @@ pub enum AddEventError {
     NoAction,
     #[error("Push to the queue failed")]
     PushFailed,
+    #[error("Checking the idempotency key failed")]
+    IdempotencyKeyFailed,
 }
 
+/// Pushes the event to the queue and returns its ID.
+///
+/// If it has an idempotency key that the user already sent within the window, it isn't pushed again and the ID
+/// given to the first one is returned. The key stays pending until the event is pushed, so it's pushed again by a
+/// retry if the first one never finished
 pub async fn add_event_controller(
-    events_queue: Arc<impl MessagesQueue<Event>>,
+    events_queue: Arc<impl MessagesQueue<Event> + ?Sized>,
+    idempotency_keys: &RedisIdempotencyKeys,
     user_address: &str,
+    instance_id: Option<&str>,
     event: EventRequest,
 ) -> Result<Uuid, AddEventError> {
     if let Some(action) = event.action {
         let id = Uuid::new_v4();
+        let idempotency_key = event.idempotency_key;
+        if let Some(idempotency_key) = &idempotency_key {
+            match idempotency_keys
+                .reserve(user_address, idempotency_key, &id.to_string())
+                .await
+            {
+                Ok(None) => {}
+                Ok(Some(reserved_id)) => {
+                    log::debug!("Event with idempotency key {idempotency_key} was already sent");
+                    return Uuid::parse_str(&reserved_id)
+                        .map_err(|_| AddEventError::IdempotencyKeyFailed);
+                }
+                Err(e) => {
+                    log::error!("Failed to reserve the idempotency key {e}");
+                    return Err(AddEventError::IdempotencyKeyFailed);
+                }
+            }
+        }
+
         let event = Event {
             id: id.to_string(),
             address: user_address.to_string(),
             action: Some(action),
+            instance_id: instance_id.map(|id| id.to_string()),
         };
         match events_queue.push(&event).await {
             Ok(queue_size) => {
                 log::debug!("Pushed event to the queue, queue size: {queue_size}");
+                // if it fails, the retries push the event again once the reservation times out
+                if let Some(idempotency_key) = &idempotency_key {
+                    if let Err(e) = idempotency_keys
+                        .confirm(user_address, idempotency_key, &event.id)
+                        .await
+                    {
+                        log::error!("Failed to confirm the idempotency key {e}");
+                    }
+                }
                 Ok(id)
             }
             Err(e) => {
                 log::error!("Failed to push event to the queue {e}");
+                // so the retry isn't taken as a duplicate of an event that was never pushed
+                if let Some(idempotency_key) = &idempotency_key {
+                    if let Err(e) = idempotency_keys
+                        .release(user_address, idempotency_key, &event.id)
+                        .await
+                    {
+                        log::error!("Failed to release the idempotency key {e}");
+                    }
+                }
                 Err(AddEventError::PushFailed)
             }
         }
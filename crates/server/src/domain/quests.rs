@@ -1,7 +1,12 @@
-use crate::api::routes::errors::CommonError;
-use quests_db::core::definitions::QuestsDatabase;
-use quests_system::{get_instance_state, QuestStateCalculationError};
-use std::sync::Arc;
+use crate::api::routes::errors::{CommonError, ValidationErrorDetail};
+use quests_db::core::definitions::{QuestInstance, QuestsDatabase};
+use quests_message_broker::channel::RedisChannelPublisher;
+use quests_protocol::definitions::*;
+use quests_system::{get_instance_state, recalculate_instance_state, QuestStateCalculationError};
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,16 +17,24 @@ pub enum QuestError {
     DeserializationError,
     #[error("Quest Validation Error: {0}")]
     QuestValidation(String),
+    #[error("Quest Validation Error: {}", .0.iter().map(|error| error.message.as_str()).collect::<Vec<_>>().join("; "))]
+    QuestValidationReport(Vec<ValidationErrorDetail>),
     #[error("Cannot modify a quest if you are not the quest creator")]
     NotQuestCreator,
     #[error("Cannot modify a quest instance if you are not the user playing the quest")]
     NotInstanceOwner,
     #[error("Quest doesn't exist or is inactive")]
     NotFoundOrInactive,
+    #[error("Quest can't be started now because it's outside its availability window")]
+    QuestNotAvailable,
+    #[error("Quest prerequisites not completed: {}", .0.join(", "))]
+    PrerequisitesNotCompleted(Vec<String>),
     #[error("Quest already started and active")]
     QuestAlreadyStarted,
     #[error("Quest already completed")]
     QuestAlreadyCompleted,
+    #[error("Quest already completed and it can't be started again{}", .0.map(|available_at| format!(" until {available_at}")).unwrap_or_default())]
+    QuestNotRepeatable(Option<i64>),
     #[error("Quest has no reward")]
     QuestHasNoReward,
     #[error("Requested Quest cannot be activated because it may be prevoiusly updated and replaced with a new Quest or it may be already active")]
@@ -63,13 +76,99 @@ pub async fn start_quest(
         return Err(QuestError::NotFoundOrInactive);
     }
 
+    let quest = db.get_quest(quest_id).await?;
+    if !quest.is_available_at(unix_now()) {
+        return Err(QuestError::QuestNotAvailable);
+    }
+
     if db.has_active_quest_instance(user_address, quest_id).await? {
         return Err(QuestError::QuestAlreadyStarted);
     }
 
+    let completions = db
+        .get_user_quest_completions(user_address, quest_id)
+        .await?;
+    if let Err(available_at) = quest.repeat_policy.check(&completions, unix_now()) {
+        return Err(QuestError::QuestNotRepeatable(available_at));
+    }
+
+    let missing_prerequisites = db
+        .get_missing_quest_prerequisites(quest_id, user_address)
+        .await?;
+    if !missing_prerequisites.is_empty() {
+        return Err(QuestError::PrerequisitesNotCompleted(missing_prerequisites));
+    }
+
     Ok(db.start_quest(quest_id, user_address).await?)
 }
 
+/// Checks that every prerequisite is an active quest. When a quest is updated, `quest_id` is the quest being replaced,
+/// and no prerequisite can be a version of it or require one, so prerequisites never form a cycle
+pub async fn validate_prerequisites(
+    db: Arc<impl QuestsDatabase>,
+    prerequisites: &[String],
+    quest_id: Option<&str>,
+) -> Result<(), QuestError> {
+    let mut errors = vec![];
+    for (index, prerequisite) in prerequisites.iter().enumerate() {
+        if !db.is_active_quest(prerequisite).await? {
+            errors.push(ValidationErrorDetail::new(
+                format!("prerequisites[{index}]"),
+                "Prerequisite quest doesn't exist or is inactive",
+            ));
+        } else if let Some(quest_id) = quest_id {
+            if db.is_quest_required_by(quest_id, prerequisite).await? {
+                errors.push(ValidationErrorDetail::new(
+                    format!("prerequisites[{index}]"),
+                    "Prerequisite quest is the quest itself or requires it",
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(QuestError::QuestValidationReport(errors))
+    }
+}
+
+/// Recalculates the state of a Quest Instance after its events were edited and sends it to the user.
+///
+/// `event_id` is the event that caused the edit, if any
+pub async fn refresh_instance_state(
+    db: Arc<impl QuestsDatabase>,
+    quests_channel: &RedisChannelPublisher,
+    quest_instance: &QuestInstance,
+    event_id: &str,
+) -> Result<(), QuestError> {
+    let mut quest_state =
+        recalculate_instance_state(db, &quest_instance.quest_id, &quest_instance.id).await?;
+
+    quest_state.hide_actions();
+    quests_channel
+        .publish(UserUpdate {
+            message: Some(user_update::Message::QuestStateUpdate(QuestStateUpdate {
+                instance_id: quest_instance.id.clone(),
+                quest_state: Some(quest_state),
+                event_id: event_id.to_string(),
+            })),
+            user_address: quest_instance.user_address.clone(),
+            ..Default::default()
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Current unix timestamp in seconds
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Failed to get current timestamp")
+        .as_secs() as i64
+}
+
 impl From<QuestStateCalculationError> for QuestError {
     fn from(value: QuestStateCalculationError) -> Self {
         match value {
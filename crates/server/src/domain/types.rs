@@ -26,6 +26,11 @@ impl ToQuest for StoredQuest {
             },
             active: self.active,
             created_at: self.created_at as u32,
+            starts_at: self.starts_at.map(|starts_at| starts_at as u32),
+            ends_at: self.ends_at.map(|ends_at| ends_at as u32),
+            instance_duration: self
+                .instance_duration
+                .map(|instance_duration| instance_duration as u32),
         })
     }
 }
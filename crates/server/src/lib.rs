@@ -10,11 +10,15 @@ use dcl_http_prom_metrics::HttpMetricsCollectorBuilder;
 use env_logger::init as initialize_logger;
 use quests_db::create_quests_db_component;
 use quests_message_broker::{
-    channel::{RedisChannelPublisher, RedisChannelSubscriber},
-    messages_queue::RedisMessagesQueue,
-    redis::Redis,
+    channel::RedisChannelPublisher, dead_letters::RedisDeadLetterQueue,
+    idempotency_keys::RedisIdempotencyKeys, redis::Redis,
 };
-use quests_system::{event_processing, QUESTS_CHANNEL_NAME, QUESTS_EVENTS_QUEUE_NAME};
+use quests_system::{
+    event_processing::{self, EventsQueueOptions, RetryOptions},
+    shutdown_signal, QUESTS_CHANNEL_NAME, QUESTS_EVENTS_DEAD_LETTERS_NAME,
+    QUESTS_EVENTS_IDEMPOTENCY_KEYS_NAME,
+};
+use std::time::Duration;
 use tokio::select;
 
 use crate::configuration::Config;
@@ -36,57 +40,95 @@ pub async fn run_app() {
         .expect("> run_app > Couldn't initialize redis connection");
     let redis = Arc::new(redis);
 
-    let events_queue = RedisMessagesQueue::new(redis.clone(), QUESTS_EVENTS_QUEUE_NAME);
-    let events_queue = Arc::new(events_queue);
+    let events_queue = event_processing::create_events_queue(
+        redis.clone(),
+        &EventsQueueOptions {
+            backend: config.events_queue_backend,
+            reliable: config.reliable_events_queue,
+            consumer: config.events_queue_consumer.clone(),
+            claim_min_idle: Duration::from_millis(config.events_queue_claim_idle_ms),
+        },
+    )
+    .await
+    .expect("> run_app > Couldn't initialize the events queue");
 
-    let quests_channel_publisher = Arc::new(RedisChannelPublisher::new(
+    let dead_letters = RedisDeadLetterQueue::new(redis.clone(), QUESTS_EVENTS_DEAD_LETTERS_NAME);
+    let dead_letters = Arc::new(dead_letters);
+
+    let idempotency_keys = RedisIdempotencyKeys::new(
         redis.clone(),
-        QUESTS_CHANNEL_NAME,
-    ));
-    let quests_channel_subscriber = RedisChannelSubscriber::new(redis.clone(), QUESTS_CHANNEL_NAME)
-        .await
-        .expect("> run_app > Couldn't initialize subscriber");
+        QUESTS_EVENTS_IDEMPOTENCY_KEYS_NAME,
+        Duration::from_millis(config.event_idempotency_window_ms),
+    );
+    let idempotency_keys = Arc::new(idempotency_keys);
+
+    let quests_channel_publisher = Arc::new(
+        RedisChannelPublisher::new_sharded(
+            redis.clone(),
+            QUESTS_CHANNEL_NAME,
+            config.quests_channel_shards,
+        )
+        .with_replay_log(config.quests_updates_replay_log_size),
+    );
 
     let http_metrics_collector = Arc::new(HttpMetricsCollectorBuilder::default().build());
 
-    let (warp_websocket_server, rpc_server) = rpc::run_rpc_server((
+    let mut rpc_server = rpc::run_rpc_server((
         config.clone(),
         database.clone(),
         events_queue.clone(),
         quests_channel_publisher.clone(),
-        quests_channel_subscriber,
+        redis.clone(),
     ))
     .await;
 
-    let event_processing = event_processing::run_event_processor(
+    let mut event_processing = event_processing::run_event_processor(
         database.clone(),
         events_queue.clone(),
-        quests_channel_publisher,
+        dead_letters.clone(),
+        quests_channel_publisher.clone(),
+        RetryOptions {
+            max_attempts: config.event_max_attempts,
+            backoff: Duration::from_millis(config.event_retry_backoff_ms),
+        },
+        config.event_processing_concurrency,
     );
 
+    let shutdown_timeout = Duration::from_millis(config.shutdown_timeout_ms);
     let actix_rest_api_server = api::run_server(
         config.into(),
         database.into(),
         events_queue.into(),
+        dead_letters.into(),
+        idempotency_keys.into(),
+        quests_channel_publisher.into(),
         http_metrics_collector.into(),
     )
     .await;
 
+    let actix_rest_api_server_handle = actix_rest_api_server.handle();
+
     select! {
-        _ = tokio::signal::ctrl_c() => {
-            log::info!("> run_app > SIGINT catched. Exiting...");
+        _ = shutdown_signal() => {
+            log::info!("> run_app > Stopping...");
         },
         _ = actix_rest_api_server => {
             log::info!("> run_app > REST API finished. Exiting...");
         },
-        _ = warp_websocket_server => {
+        _ = &mut rpc_server.http_server => {
             log::info!("> run_app > Warp server finished. Exiting...");
         },
-        _ = rpc_server => {
+        _ = &mut rpc_server.rpc_server => {
             log::info!("> run_app > RPC Server finished. Exiting...");
         },
-        _ = event_processing => {
+        _ = &mut event_processing => {
             log::info!("> run_app > Event processing finished. Exiting...");
         }
     }
+
+    // the events being processed may still publish updates, so they finish before closing the transports
+    event_processing.shutdown(shutdown_timeout).await;
+    rpc_server.shutdown().await;
+    actix_rest_api_server_handle.stop(true).await;
+    log::info!("> run_app > Stopped");
 }
@@ -10,19 +10,33 @@ use dcl_crypto_middleware_rs::ws_signed_headers::{
 use dcl_rpc::{
     server::RpcServer,
     stream_protocol::GeneratorYielder,
-    transports::web_sockets::{warp::WarpWebSocket, Message, WebSocket, WebSocketTransport},
+    transports::{
+        web_sockets::{warp::WarpWebSocket, Message, WebSocket, WebSocketTransport},
+        Transport,
+    },
 };
-use futures_util::lock::Mutex;
+use futures_util::{future::join_all, lock::Mutex};
 use log::{debug, error, info};
 use quests_db::Database;
 use quests_message_broker::{
-    channel::{RedisChannelPublisher, RedisChannelSubscriber},
-    messages_queue::RedisMessagesQueue,
+    channel::{RedisChannelPublisher, RedisShardedChannelSubscriber},
+    idempotency_keys::RedisIdempotencyKeys,
+    redis::Redis,
 };
 use quests_protocol::definitions::*;
+use quests_system::event_processing::EventsQueue;
+use quests_system::{QUESTS_CHANNEL_NAME, QUESTS_EVENTS_IDEMPOTENCY_KEYS_NAME};
 use service::QuestsServiceImplementation;
-use std::{collections::HashMap, sync::Arc};
-use tokio::{sync::RwLock, task::JoinHandle, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, RwLock},
+    task::JoinHandle,
+    time::Instant,
+};
 use warp::{
     http::{HeaderValue, StatusCode},
     reject::{MissingHeader, Reject},
@@ -34,29 +48,75 @@ use self::metrics_collector::MetricsCollector;
 pub struct QuestsRpcServerContext {
     pub config: Arc<Config>,
     pub db: Arc<Database>,
-    pub redis_events_queue: Arc<RedisMessagesQueue>,
+    pub redis_events_queue: Arc<EventsQueue>,
     pub redis_channel_publisher: Arc<RedisChannelPublisher>,
+    pub idempotency_keys: RedisIdempotencyKeys,
     pub transport_contexts: Arc<RwLock<HashMap<u32, TransportContext>>>,
     pub metrics_collector: Arc<MetricsCollector>,
 }
 
+pub type QuestsTransport = WebSocketTransport<WarpWebSocket, Address>;
+
+/// Updates waiting to be sent to a transport. The next ones are dropped while it's full, and the client notices the gap
+/// in the sequence of the updates
+const TRANSPORT_UPDATES_BUFFER: usize = 100;
+
 pub struct TransportContext {
-    pub yielder: Option<GeneratorYielder<UserUpdate>>,
-    pub subscription_ts: Option<Instant>,
+    pub transport: Arc<QuestsTransport>,
+    /// Locked on its own, so replaying the missed updates of a transport doesn't block the others
+    pub subscription: Arc<Mutex<Subscription>>,
+    /// Updates to be sent by the task of the transport, see [`send_updates`]
+    pub updates_sender: mpsc::Sender<UserUpdate>,
     pub quest_instance_ids: Arc<Mutex<Vec<String>>>,
     pub user_address: Address,
     pub connection_ts: Instant,
 }
 
+#[derive(Default)]
+pub struct Subscription {
+    pub yielder: Option<GeneratorYielder<UserUpdate>>,
+    pub subscription_ts: Option<Instant>,
+    /// Sequence of the last update sent when subscribing, the updates up to it aren't sent again
+    pub replayed_sequence: u64,
+}
+
+pub type RpcServerDependencies = (
+    Arc<Config>,
+    Arc<Database>,
+    Arc<EventsQueue>,
+    Arc<RedisChannelPublisher>,
+    Arc<Redis>,
+);
+
+/// Tasks running the RPC server, and the transports connected to it
+pub struct RpcServerHandle {
+    pub http_server: JoinHandle<()>,
+    pub rpc_server: JoinHandle<()>,
+    transport_contexts: Arc<RwLock<HashMap<u32, TransportContext>>>,
+}
+
+impl RpcServerHandle {
+    /// Stops accepting connections and closes the connected transports, so clients get a close frame
+    pub async fn shutdown(self) {
+        self.http_server.abort();
+
+        let transports = self
+            .transport_contexts
+            .read()
+            .await
+            .values()
+            .map(|transport_context| transport_context.transport.clone())
+            .collect::<Vec<_>>();
+        info!("Closing {} transports...", transports.len());
+        join_all(transports.iter().map(|transport| transport.close())).await;
+
+        self.rpc_server.abort();
+    }
+}
+
 pub async fn run_rpc_server(
-    (config, db, redis_events_queue, redis_channel_publisher, mut redis_channel_subscriber): (
-        Arc<Config>,
-        Arc<Database>,
-        Arc<RedisMessagesQueue>,
-        Arc<RedisChannelPublisher>,
-        RedisChannelSubscriber,
-    ),
-) -> (JoinHandle<()>, JoinHandle<()>) {
+    (config, db, redis_events_queue, redis_channel_publisher, redis): RpcServerDependencies,
+) -> RpcServerHandle {
     let ws_server_address = (
         [0, 0, 0, 0],
         config.ws_server_port.parse::<u16>().unwrap_or(5001),
@@ -64,18 +124,67 @@ pub async fn run_rpc_server(
 
     let metrics_collector = Arc::new(MetricsCollector::new());
     let metrics_token = config.wkc_metrics_bearer_token.clone();
+    let quests_channel_shards = config.quests_channel_shards;
 
+    let idempotency_keys = RedisIdempotencyKeys::new(
+        redis.clone(),
+        QUESTS_EVENTS_IDEMPOTENCY_KEYS_NAME,
+        Duration::from_millis(config.event_idempotency_window_ms),
+    );
     let ctx = QuestsRpcServerContext {
         config,
         db,
         redis_events_queue,
         redis_channel_publisher,
+        idempotency_keys,
         transport_contexts: Arc::new(RwLock::new(HashMap::new())),
         metrics_collector: metrics_collector.clone(),
     };
 
     let transport_contexts = ctx.transport_contexts.clone();
-    let transport_by_user_address = Arc::new(RwLock::new(HashMap::new()));
+    let rpc_transport_contexts = transport_contexts.clone();
+    // a user may be connected from more than one place at the same time, and every transport gets the updates
+    let transport_by_user_address: Arc<RwLock<HashMap<String, HashSet<u32>>>> =
+        Arc::new(RwLock::new(HashMap::new()));
+
+    let cloned_transport_by_user_address = transport_by_user_address.clone();
+    let cloned_transport_contexts = transport_contexts.clone();
+    let redis_channel_subscriber = Arc::new(RedisShardedChannelSubscriber::new(
+        redis,
+        QUESTS_CHANNEL_NAME,
+        quests_channel_shards,
+        move |user_update: UserUpdate| {
+            let cloned_transport_by_user_address = cloned_transport_by_user_address.clone();
+            let cloned_transports_ctx = cloned_transport_contexts.clone();
+            async move {
+                info!("> update received: {:?}", user_update);
+                let Some(transport_ids) = cloned_transport_by_user_address
+                    .read()
+                    .await
+                    .get(&user_update.user_address)
+                    .cloned()
+                else {
+                    return;
+                };
+
+                // each transport sends its updates on its own, so the shard never waits for a client
+                let transports_ctx = cloned_transports_ctx.read().await;
+                for transport_id in transport_ids {
+                    if let Some(ctx) = transports_ctx.get(&transport_id) {
+                        info!(
+                            "> transport found for user: {} - TID: {}",
+                            user_update.user_address, transport_id
+                        );
+                        if ctx.updates_sender.try_send(user_update.clone()).is_err() {
+                            error!(
+                                "User Update received > Transport {transport_id} isn't taking updates, dropping it"
+                            );
+                        }
+                    }
+                }
+            }
+        },
+    ));
 
     let mut rpc_server = RpcServer::create(ctx);
 
@@ -150,11 +259,13 @@ pub async fn run_rpc_server(
 
     let cloned_transport_contexts_closes = transport_contexts.clone();
     let cloned_transport_by_user_address = transport_by_user_address.clone();
+    let cloned_redis_channel_subscriber = redis_channel_subscriber.clone();
     let metrics_collector_cloned = metrics_collector.clone();
 
     rpc_server.set_on_transport_closes_handler(move |_, transport_id| {
         let transport_contexts = cloned_transport_contexts_closes.clone();
         let transport_by_user_address = cloned_transport_by_user_address.clone();
+        let redis_channel_subscriber = cloned_redis_channel_subscriber.clone();
         metrics_collector_cloned.client_disconnected();
 
         let metrics_collector = metrics_collector_cloned.clone();
@@ -162,36 +273,58 @@ pub async fn run_rpc_server(
         tokio::spawn(async move {
             let transport = transport_contexts.write().await.remove(&transport_id);
             if let Some(transport) = transport {
-                transport_by_user_address
-                    .write()
-                    .await
-                    .remove(&transport.user_address.to_string().to_ascii_lowercase());
+                let user_address = transport.user_address.to_string().to_ascii_lowercase();
+                {
+                    let mut transport_by_user_address = transport_by_user_address.write().await;
+                    if let Some(transport_ids) = transport_by_user_address.get_mut(&user_address) {
+                        transport_ids.remove(&transport_id);
+                        if transport_ids.is_empty() {
+                            transport_by_user_address.remove(&user_address);
+                        }
+                    }
+                }
+                redis_channel_subscriber.stop_listening(&user_address).await;
                 metrics_collector
                     .record_client_duration(transport.connection_ts.elapsed().as_secs_f64());
-                if let Some(instant) = transport.subscription_ts {
+                if let Some(instant) = transport.subscription.lock().await.subscription_ts {
                     metrics_collector.record_subscribe_duration(instant.elapsed().as_secs_f64())
                 }
             }
         });
     });
 
-    let cloned_transport_contexts = transport_contexts.clone();
-    let cloned_transport_by_user_address = transport_by_user_address.clone();
     rpc_server.set_on_transport_connected_handler(move |transport, transport_id| {
-        let transport_contexts = cloned_transport_contexts.clone();
-        let transport_by_user_address = cloned_transport_by_user_address.clone();
+        let transport_contexts = transport_contexts.clone();
+        let transport_by_user_address = transport_by_user_address.clone();
+        let redis_channel_subscriber = redis_channel_subscriber.clone();
         metrics_collector.client_connected();
         tokio::spawn(async move {
             debug!("> OnConnected > Address: {:?}", transport.context);
-            transport_by_user_address.write().await.insert(
-                transport.context.to_string().to_ascii_lowercase(),
-                transport_id,
-            );
+            let user_address = transport.context.to_string().to_ascii_lowercase();
+            if let Err(err) = redis_channel_subscriber.listen(&user_address).await {
+                error!(
+                    "> OnConnected > Couldn't subscribe to the updates of {user_address}, closing the transport: {err:?}"
+                );
+                // it isn't registered, so closing it doesn't stop listening to a shard it didn't listen to
+                transport.close().await;
+                return;
+            }
+            transport_by_user_address
+                .write()
+                .await
+                .entry(user_address)
+                .or_default()
+                .insert(transport_id);
+            let subscription = Arc::new(Mutex::new(Subscription::default()));
+            let (updates_sender, updates_receiver) = mpsc::channel(TRANSPORT_UPDATES_BUFFER);
+            // it finishes once the transport is closed, as the sender is dropped along with its context
+            tokio::spawn(send_updates(subscription.clone(), updates_receiver));
             transport_contexts.write().await.insert(
                 transport_id,
                 TransportContext {
-                    yielder: None,
-                    subscription_ts: None,
+                    transport: transport.clone(),
+                    subscription,
+                    updates_sender,
                     quest_instance_ids: Arc::new(Mutex::new(vec![])),
                     user_address: transport.context,
                     connection_ts: Instant::now(),
@@ -200,37 +333,6 @@ pub async fn run_rpc_server(
         });
     });
 
-    tokio::spawn(async move {
-        redis_channel_subscriber
-            .on_new_message(move |user_update: UserUpdate| {
-                let cloned_transport_by_user_address = transport_by_user_address.clone();
-                let cloned_transports_ctx = transport_contexts.clone();
-                async move {
-                    info!("> update received: {:?}", user_update);
-                    if let Some(transport_id) = cloned_transport_by_user_address
-                        .read()
-                        .await
-                        .get(&user_update.user_address)
-                    {
-                        if let Some(ctx) = cloned_transports_ctx.read().await.get(transport_id) {
-                            info!(
-                                "> transport found for user: {} - TID: {}",
-                                user_update.user_address, transport_id
-                            );
-                            if let Some(yielder) = &ctx.yielder {
-                                if yielder.r#yield(user_update).await.is_err() {
-                                    error!(
-                                    "User Update received > Couldn't send update to subscriptors"
-                                );
-                                }
-                            }
-                        }
-                    }
-                }
-            })
-            .await
-    });
-
     let rpc_server_handle = tokio::spawn(async move {
         rpc_server.run().await;
     });
@@ -240,7 +342,30 @@ pub async fn run_rpc_server(
         warp::serve(routes).run(ws_server_address).await;
     });
 
-    (http_server_handle, rpc_server_handle)
+    RpcServerHandle {
+        http_server: http_server_handle,
+        rpc_server: rpc_server_handle,
+        transport_contexts: rpc_transport_contexts,
+    }
+}
+
+/// Sends the updates of a transport once it's subscribed. It waits for the missed updates while the transport is
+/// subscribing, so they're sent first, and a slow client only delays its own updates
+async fn send_updates(
+    subscription: Arc<Mutex<Subscription>>,
+    mut updates_receiver: mpsc::Receiver<UserUpdate>,
+) {
+    while let Some(user_update) = updates_receiver.recv().await {
+        let subscription = subscription.lock().await;
+        if user_update.sequence != 0 && user_update.sequence <= subscription.replayed_sequence {
+            continue;
+        }
+        if let Some(yielder) = &subscription.yielder {
+            if yielder.r#yield(user_update).await.is_err() {
+                error!("User Update received > Couldn't send update to subscriptors");
+            }
+        }
+    }
 }
 
 fn ping_every_30s(websocket: Arc<WarpWebSocket>) {
@@ -11,15 +11,77 @@ use dcl_rpc::{
     stream_protocol::Generator,
 };
 use log::error;
+use quests_db::Database;
 use quests_protocol::definitions::*;
 use quests_system::get_instance_state;
-use quests_system::{get_all_quest_states_by_user_address, get_quest_with_decoded_definition};
+use quests_system::{
+    get_all_quest_states_by_user_address, get_completed_quest_states_by_user_address,
+    get_quest_with_decoded_definition, QuestStateCalculationError,
+};
+use std::sync::Arc;
 use tokio::time::Instant;
 
 pub struct QuestsServiceImplementation;
 
 type QuestRpcResult<T> = Result<T, ServiceError>;
 
+/// Returns the updates sent to the user after `last_seen_sequence` and the sequence of the last one sent.
+///
+/// The last sequence is read first, so the updates appended meanwhile are left to be received from the channel
+async fn get_missed_updates(
+    server_context: &QuestsRpcServerContext,
+    user_address: &str,
+    last_seen_sequence: Option<u64>,
+) -> (Vec<UserUpdate>, u64) {
+    let Some(replay_log) = server_context.redis_channel_publisher.replay_log() else {
+        return (vec![], 0);
+    };
+
+    let last_sequence = match replay_log.last_sequence(user_address).await {
+        Ok(last_sequence) => last_sequence,
+        Err(err) => {
+            error!("QuestsServiceImplementation > Subscribe Error > Getting last sequence > {err}");
+            return (vec![], 0);
+        }
+    };
+
+    let Some(last_seen_sequence) = last_seen_sequence else {
+        return (vec![], last_sequence);
+    };
+
+    match replay_log
+        .since::<UserUpdate>(user_address, last_seen_sequence)
+        .await
+    {
+        Ok(updates) => (
+            updates
+                .into_iter()
+                .filter(|update| update.sequence <= last_sequence)
+                .collect(),
+            last_sequence,
+        ),
+        Err(err) => {
+            error!(
+                "QuestsServiceImplementation > Subscribe Error > Getting missed updates > {err}"
+            );
+            (vec![], last_sequence)
+        }
+    }
+}
+
+/// Returns the states of the instances the user is playing, and the completed ones if `include_completed` is set
+async fn get_user_quest_states(
+    db: Arc<Database>,
+    user_address: &str,
+    include_completed: bool,
+) -> Result<Vec<(String, (Quest, QuestState, Option<String>))>, QuestStateCalculationError> {
+    let mut quest_states = get_all_quest_states_by_user_address(db.clone(), user_address).await?;
+    if include_completed {
+        quest_states.extend(get_completed_quest_states_by_user_address(db, user_address).await?);
+    }
+    Ok(quest_states)
+}
+
 #[async_trait::async_trait]
 impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsServiceImplementation {
     async fn start_quest(
@@ -79,6 +141,7 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
                                 state: Some(quest_state),
                             })),
                             user_address: transport_context.user_address.to_string(),
+                            ..Default::default()
                         };
                         context
                             .server_context
@@ -113,7 +176,7 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
             Err(err) => {
                 error!("QuestsServiceImplementation > StartQuest Error > QuestID: {quest_id} > {err:?}");
                 match err {
-                    QuestError::NotFoundOrInactive => {
+                    QuestError::NotFoundOrInactive | QuestError::QuestNotAvailable => {
                         context
                             .server_context
                             .metrics_collector
@@ -158,6 +221,56 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
 
                         Ok(response)
                     }
+                    QuestError::QuestNotRepeatable(available_at) => {
+                        context
+                            .server_context
+                            .metrics_collector
+                            .record_procedure_call(
+                                Procedure::StartQuest,
+                                Status::QuestNotRepeatable,
+                            );
+
+                        record_procedure_duration(Status::QuestNotRepeatable);
+
+                        let response = StartQuestResponse::quest_not_repeatable(
+                            available_at.map(|available_at| available_at as u32),
+                        );
+
+                        context
+                            .server_context
+                            .metrics_collector
+                            .record_out_procedure_call_size(
+                                Procedure::StartQuest,
+                                Status::QuestNotRepeatable,
+                                response.encoded_len(),
+                            );
+
+                        Ok(response)
+                    }
+                    QuestError::PrerequisitesNotCompleted(quest_ids) => {
+                        context
+                            .server_context
+                            .metrics_collector
+                            .record_procedure_call(
+                                Procedure::StartQuest,
+                                Status::PrerequisitesNotCompleted,
+                            );
+
+                        record_procedure_duration(Status::PrerequisitesNotCompleted);
+
+                        let response = StartQuestResponse::prerequisites_not_completed(quest_ids);
+
+                        context
+                            .server_context
+                            .metrics_collector
+                            .record_out_procedure_call_size(
+                                Procedure::StartQuest,
+                                Status::PrerequisitesNotCompleted,
+                                response.encoded_len(),
+                            );
+
+                        Ok(response)
+                    }
                     QuestError::CommonError(CommonError::NotUUID) => {
                         context
                             .server_context
@@ -387,7 +500,9 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
 
         match add_event_controller(
             context.server_context.redis_events_queue.clone(),
+            &context.server_context.idempotency_keys,
             &user_address,
+            None,
             request,
         )
         .await
@@ -437,7 +552,7 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
 
                         Ok(response)
                     }
-                    AddEventError::PushFailed => {
+                    AddEventError::PushFailed | AddEventError::IdempotencyKeyFailed => {
                         context
                             .server_context
                             .metrics_collector
@@ -468,6 +583,7 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
 
     async fn subscribe(
         &self,
+        request: SubscribeRequest,
         context: ProcedureContext<QuestsRpcServerContext>,
     ) -> QuestRpcResult<ServerStreamResponse<UserUpdate>> {
         let record_procedure_duration = context
@@ -488,12 +604,24 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
         };
 
         let user_address = transport_context.user_address.to_string();
+        let subscription = transport_context.subscription.clone();
         drop(transport_contexts);
+
+        // the subscription is locked until the yielder is set, so the updates received meanwhile are sent after the missed ones
+        let mut subscription = subscription.lock().await;
         let (generator, generator_yielder) = Generator::create();
 
+        let (missed_updates, last_sequence) = get_missed_updates(
+            &context.server_context,
+            &user_address,
+            request.last_seen_sequence,
+        )
+        .await;
+
         let accepted_response = UserUpdate {
             message: Some(user_update::Message::Subscribed(true)),
             user_address: user_address.clone(),
+            sequence: last_sequence,
         };
 
         context
@@ -511,16 +639,17 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
             return Err(ServiceError::InternalError);
         }
 
-        context
-            .server_context
-            .transport_contexts
-            .write()
-            .await
-            .entry(context.transport_id)
-            .and_modify(|current_context| {
-                current_context.yielder = Some(generator_yielder);
-                current_context.subscription_ts = Some(Instant::now());
-            });
+        for missed_update in missed_updates {
+            if let Err(err) = generator_yielder.r#yield(missed_update).await {
+                error!("QuestsServiceImplementation > Subscribe Error > Replaying missed updates > {err:?}");
+                return Err(ServiceError::InternalError);
+            }
+        }
+
+        subscription.yielder = Some(generator_yielder);
+        subscription.subscription_ts = Some(Instant::now());
+        subscription.replayed_sequence = last_sequence;
+        drop(subscription);
 
         context
             .server_context
@@ -533,6 +662,7 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
 
     async fn get_all_quests(
         &self,
+        request: GetAllQuestsRequest,
         context: ProcedureContext<QuestsRpcServerContext>,
     ) -> QuestRpcResult<GetAllQuestsResponse> {
         let record_procedure_duration = context
@@ -555,12 +685,17 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
         let user_address = transport_context.user_address.to_string();
         drop(transport_contexts);
 
-        match get_all_quest_states_by_user_address(context.server_context.db.clone(), &user_address)
-            .await
-        {
+        let quest_states = get_user_quest_states(
+            context.server_context.db.clone(),
+            &user_address,
+            request.include_completed,
+        )
+        .await;
+
+        match quest_states {
             Ok(mut quest_states) => {
                 let mut quests = Vec::new();
-                for (instance_id, (ref mut quest, state)) in quest_states.iter_mut() {
+                for (instance_id, (ref mut quest, state, _)) in quest_states.iter_mut() {
                     // quest.hide_actions();
                     let quest_definition_and_state = QuestInstance {
                         id: instance_id.to_string(),
@@ -590,7 +725,9 @@ impl QuestsServiceServer<QuestsRpcServerContext, ServiceError> for QuestsService
                 Ok(response)
             }
             Err(err) => {
-                error!("QuestsServiceImplementation > GetAllQuests > get_all_quest_states_by_user_address > {err:?}");
+                error!(
+                    "QuestsServiceImplementation > GetAllQuests > get_user_quest_states > {err:?}"
+                );
                 context
                     .server_context
                     .metrics_collector
@@ -732,6 +869,8 @@ enum Status {
     NotExistsTransportID,
     NotFound,
     QuestAlreadyStarted,
+    PrerequisitesNotCompleted,
+    QuestNotRepeatable,
     Ignored,
     // Stream,
 }
@@ -745,6 +884,8 @@ impl<'a> From<Status> for &'a str {
             Status::InternalServerError => "INTERNAL_SERVER_ERROR",
             Status::NotFound => "NOT_FOUND",
             Status::QuestAlreadyStarted => "QUEST_ALREADY_STARTED",
+            Status::PrerequisitesNotCompleted => "PREREQUISITES_NOT_COMPLETED",
+            Status::QuestNotRepeatable => "QUEST_NOT_REPEATABLE",
             Status::NotExistsTransportID => "NOT_EXISTS_TRANSPORT_ID",
             Status::Ignored => "IGNORED",
             // Status::Stream => "STREAM",
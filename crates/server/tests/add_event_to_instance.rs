@@ -2,7 +2,7 @@ mod common;
 use actix_web::http::StatusCode;
 use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
 pub use common::*;
-use quests_db::core::definitions::{CreateQuest, QuestsDatabase};
+use quests_db::core::definitions::{CreateQuest, QuestsDatabase, RepeatPolicy};
 use quests_db::create_quests_db_component;
 use quests_protocol::definitions::*;
 use quests_server::api::routes::quest_instances::{
@@ -25,6 +25,11 @@ async fn add_event_to_instance_should_be_200() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -55,7 +60,9 @@ async fn add_event_to_instance_should_be_200() {
                     .unwrap()
                     .parameters
                     .clone(),
+                count: None,
             }),
+            idempotency_key: None,
         },
     };
 
@@ -94,6 +101,11 @@ async fn add_event_to_instance_should_be_403() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -126,7 +138,9 @@ async fn add_event_to_instance_should_be_403() {
                     .unwrap()
                     .parameters
                     .clone(),
+                count: None,
             }),
+            idempotency_key: None,
         },
     };
 
@@ -1,6 +1,7 @@
 pub mod quest_samples;
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use actix_web::body::MessageBody;
 use actix_web::dev::ServiceFactory;
@@ -11,11 +12,18 @@ use dcl_crypto::Identity;
 use dcl_http_prom_metrics::HttpMetricsCollectorBuilder;
 use quests_db::core::ops::{Connect, GetConnection, Migrate};
 use quests_db::{create_quests_db_component, DatabaseOptions, Executor};
+use quests_message_broker::channel::RedisChannelPublisher;
+use quests_message_broker::dead_letters::RedisDeadLetterQueue;
+use quests_message_broker::idempotency_keys::RedisIdempotencyKeys;
 use quests_message_broker::messages_queue::RedisMessagesQueue;
 use quests_message_broker::redis::Redis;
 use quests_server::api::get_app_router;
 use quests_server::configuration::Config;
-use quests_system::QUESTS_EVENTS_QUEUE_NAME;
+use quests_system::event_processing::EventsQueue;
+use quests_system::{
+    QUESTS_CHANNEL_NAME, QUESTS_EVENTS_DEAD_LETTERS_NAME, QUESTS_EVENTS_IDEMPOTENCY_KEYS_NAME,
+    QUESTS_EVENTS_QUEUE_NAME,
+};
 
 pub async fn get_configuration(redis_switch_db: Option<u8>) -> Config {
     let mut config = Config::new().expect("Couldn't read the configuration file");
@@ -46,12 +54,26 @@ pub async fn build_app(
     let redis = Redis::new(&config.redis_url)
         .await
         .unwrap_or_else(|_| panic!("> tests > failed to initialize redis {}", config.redis_url));
-    let events_queue = RedisMessagesQueue::new(redis.into(), QUESTS_EVENTS_QUEUE_NAME);
+    let redis = Arc::new(redis);
+    let events_queue: Arc<EventsQueue> = Arc::new(RedisMessagesQueue::new(
+        redis.clone(),
+        QUESTS_EVENTS_QUEUE_NAME,
+    ));
+    let dead_letters = RedisDeadLetterQueue::new(redis.clone(), QUESTS_EVENTS_DEAD_LETTERS_NAME);
+    let idempotency_keys = RedisIdempotencyKeys::new(
+        redis.clone(),
+        QUESTS_EVENTS_IDEMPOTENCY_KEYS_NAME,
+        Duration::from_millis(config.event_idempotency_window_ms),
+    );
+    let quests_channel = RedisChannelPublisher::new(redis, QUESTS_CHANNEL_NAME);
 
     get_app_router(
         &Data::new(config.clone()),
         &Data::new(db),
-        &Data::new(events_queue),
+        &Data::from(events_queue),
+        &Data::new(dead_letters),
+        &Data::new(idempotency_keys),
+        &Data::new(quests_channel),
         &Data::new(HttpMetricsCollectorBuilder::default().build()),
     )
 }
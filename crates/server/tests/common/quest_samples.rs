@@ -20,8 +20,11 @@ pub fn grab_some_apples() -> Quest {
                         id: "A_1".to_string(),
                         description: "A_1 Desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "A Step Description".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -29,8 +32,11 @@ pub fn grab_some_apples() -> Quest {
                         id: "B_1".to_string(),
                         description: "B_1 DESC".to_string(),
                         action_items: vec![Action::location(Coordinates::new(13, 20))],
+                        ordered: false,
                     }],
                     description: "B Desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -38,8 +44,11 @@ pub fn grab_some_apples() -> Quest {
                         id: "C_1".to_string(),
                         description: "C_1 Desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 24))],
+                        ordered: false,
                     }],
                     description: "C Desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -47,8 +56,11 @@ pub fn grab_some_apples() -> Quest {
                         id: "D_1".to_string(),
                         description: "D_1 Desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(40, 20))],
+                        ordered: false,
                     }],
                     description: "D desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         }),
@@ -76,8 +88,11 @@ pub fn grab_some_pies() -> Quest {
                         id: "A_1".to_string(),
                         description: "A_1 desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "A desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -85,8 +100,11 @@ pub fn grab_some_pies() -> Quest {
                         id: "B_1".to_string(),
                         description: "B_1 desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(30, 20))],
+                        ordered: false,
                     }],
                     description: "B Desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -94,8 +112,11 @@ pub fn grab_some_pies() -> Quest {
                         id: "C_1".to_string(),
                         description: "C_1 desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "C desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -103,8 +124,11 @@ pub fn grab_some_pies() -> Quest {
                         id: "D_1".to_string(),
                         description: "D_1 desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(14, 20))],
+                        ordered: false,
                     }],
                     description: "D Desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         }),
@@ -118,6 +142,7 @@ pub fn one_step_quest() -> (Quest, Action) {
     let action = Action {
         r#type: "Jump".to_string(),
         parameters: HashMap::new(),
+        count: None,
     };
     let quest = Quest {
         id: "2e9a8bbf-2223-4f51-b7e5-660d35cedef4".to_string(),
@@ -132,8 +157,11 @@ pub fn one_step_quest() -> (Quest, Action) {
                     id: "Jump once".to_string(),
                     description: "Jump once desc".to_string(),
                     action_items: vec![action.clone()],
+                    ordered: false,
                 }],
                 description: "Jump desc".to_string(),
+                optional: false,
+                branch_group: String::new(),
             }],
         }),
         ..Default::default()
@@ -5,17 +5,22 @@ use actix_web_lab::__reexports::serde_json;
 use common::*;
 use quests_db::{
     core::{
-        definitions::{QuestReward, QuestRewardHook, QuestRewardItem, QuestsDatabase},
+        definitions::{
+            QuestReward, QuestRewardHook, QuestRewardItem, QuestsDatabase, RepeatPolicy,
+        },
         errors::DBError,
     },
     create_quests_db_component,
 };
 use quests_protocol::definitions::*;
-use quests_server::api::routes::{
-    quests::{CreateQuestRequest, CreateQuestResponse},
-    ErrorResponse,
+use quests_server::{
+    api::routes::{
+        quests::{CreateQuestRequest, CreateQuestResponse},
+        ErrorResponse,
+    },
+    domain::quests::{start_quest, QuestError},
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 #[actix_web::test]
 async fn create_quest_should_be_200_without_reward() {
@@ -39,6 +44,11 @@ async fn create_quest_should_be_200_without_reward() {
         description,
         image_url,
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let headers = get_signed_headers(
@@ -71,6 +81,227 @@ async fn create_quest_should_be_200_without_reward() {
     assert!(matches!(quest_reward, DBError::RowNotFound));
 }
 
+#[actix_web::test]
+async fn create_quest_with_prerequisites_should_be_200() {
+    let config = get_configuration(None).await;
+    let db = create_quests_db_component(&config.database_url, true)
+        .await
+        .unwrap();
+    let app = init_service(build_app(&config).await).await;
+
+    let Quest {
+        name,
+        description,
+        definition,
+        image_url,
+        ..
+    } = quest_samples::grab_some_apples();
+    let definition = definition.unwrap();
+
+    let prerequisite_request = CreateQuestRequest {
+        name: name.clone(),
+        definition: definition.clone(),
+        description: description.clone(),
+        image_url: image_url.clone(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "post",
+        "/api/quests",
+        serde_json::to_string(&prerequisite_request)
+            .unwrap()
+            .as_str(),
+    );
+
+    let req = TestRequest::post()
+        .uri("/api/quests")
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .set_json(&prerequisite_request)
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert!(response.status().is_success());
+    let CreateQuestResponse {
+        id: prerequisite_id,
+    } = read_body_json(response).await;
+
+    let create_quest_request = CreateQuestRequest {
+        prerequisites: vec![prerequisite_id.clone()],
+        ..prerequisite_request
+    };
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "post",
+        "/api/quests",
+        serde_json::to_string(&create_quest_request)
+            .unwrap()
+            .as_str(),
+    );
+
+    let req = TestRequest::post()
+        .uri("/api/quests")
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .set_json(&create_quest_request)
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert!(response.status().is_success());
+    let CreateQuestResponse { id } = read_body_json(response).await;
+
+    assert_eq!(
+        db.get_quest_prerequisites(&id).await.unwrap(),
+        vec![prerequisite_id.clone()]
+    );
+
+    let db = Arc::new(db);
+    let error = start_quest(db.clone(), "0xA", &id).await.unwrap_err();
+    assert!(
+        matches!(error, QuestError::PrerequisitesNotCompleted(quest_ids) if quest_ids == vec![prerequisite_id.clone()])
+    );
+
+    let prerequisite_instance_id = start_quest(db.clone(), "0xA", &prerequisite_id)
+        .await
+        .unwrap();
+    db.complete_quest_instance(&prerequisite_instance_id)
+        .await
+        .unwrap();
+
+    assert!(start_quest(db.clone(), "0xA", &id).await.is_ok());
+}
+
+#[actix_web::test]
+async fn create_repeatable_quest_should_be_200() {
+    let config = get_configuration(None).await;
+    let db = create_quests_db_component(&config.database_url, true)
+        .await
+        .unwrap();
+    let app = init_service(build_app(&config).await).await;
+
+    let Quest {
+        name,
+        description,
+        definition,
+        image_url,
+        ..
+    } = quest_samples::grab_some_apples();
+
+    let create_quest_request = CreateQuestRequest {
+        name,
+        definition: definition.unwrap(),
+        description,
+        image_url,
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Times { count: 2 },
+    };
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "post",
+        "/api/quests",
+        serde_json::to_string(&create_quest_request)
+            .unwrap()
+            .as_str(),
+    );
+
+    let req = TestRequest::post()
+        .uri("/api/quests")
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .set_json(&create_quest_request)
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert!(response.status().is_success());
+    let CreateQuestResponse { id } = read_body_json(response).await;
+
+    let db = Arc::new(db);
+    for _ in 0..2 {
+        let instance_id = start_quest(db.clone(), "0xA", &id).await.unwrap();
+        let error = start_quest(db.clone(), "0xA", &id).await.unwrap_err();
+        assert!(matches!(error, QuestError::QuestAlreadyStarted));
+        db.complete_quest_instance(&instance_id).await.unwrap();
+    }
+
+    let error = start_quest(db.clone(), "0xA", &id).await.unwrap_err();
+    assert!(matches!(error, QuestError::QuestNotRepeatable(None)));
+}
+
+#[actix_web::test]
+async fn create_quest_should_be_400_with_unknown_prerequisite() {
+    let config = get_configuration(None).await;
+    let app = init_service(build_app(&config).await).await;
+
+    let Quest {
+        name,
+        description,
+        definition,
+        image_url,
+        ..
+    } = quest_samples::grab_some_apples();
+
+    let create_quest_request = CreateQuestRequest {
+        name,
+        definition: definition.unwrap(),
+        description,
+        image_url,
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![uuid::Uuid::new_v4().to_string()],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "post",
+        "/api/quests",
+        serde_json::to_string(&create_quest_request)
+            .unwrap()
+            .as_str(),
+    );
+
+    let req = TestRequest::post()
+        .uri("/api/quests")
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .set_json(&create_quest_request)
+        .to_request();
+
+    let response = call_service(&app, req).await;
+
+    assert!(response.status().is_client_error());
+    let body: ErrorResponse = read_body_json(response).await;
+    assert_eq!(body.errors.len(), 1);
+    assert_eq!(body.errors[0].path, "prerequisites[0]");
+}
+
 #[actix_web::test]
 async fn create_quest_should_be_200_with_reward() {
     let config = get_configuration(None).await;
@@ -99,6 +330,11 @@ async fn create_quest_should_be_200_with_reward() {
             },
             items: vec![QuestRewardItem { name: "SunGlasses".to_string(), image_link: "https://github.com/decentraland".to_string() }]
         }),
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let headers = get_signed_headers(
@@ -154,6 +390,11 @@ async fn create_quest_should_be_400_quest_validation_error_missing_definition()
         },
         reward: None,
         image_url: "".to_string(),
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let headers = get_signed_headers(
@@ -183,6 +424,73 @@ async fn create_quest_should_be_400_quest_validation_error_missing_definition()
         .contains("Quest Validation Error: Missing the definition for the quest"));
 }
 
+#[actix_web::test]
+async fn create_quest_should_be_400_with_all_validation_errors() {
+    let config = get_configuration(None).await;
+    let app = init_service(build_app(&config).await).await;
+    let Quest { definition, .. } = quest_samples::grab_some_apples();
+    let mut definition = definition.unwrap();
+    definition.steps[0].description = "".to_string();
+    definition.steps[1].tasks[0].action_items[0].r#type = "UNKNOWN".to_string();
+
+    let create_quest_request = CreateQuestRequest {
+        name: "Q".to_string(),
+        description: "Grab some apples".to_string(),
+        definition,
+        reward: None,
+        image_url: "".to_string(),
+        starts_at: Some(200),
+        ends_at: Some(100),
+        instance_duration: Some(0),
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "post",
+        "/api/quests",
+        serde_json::to_string(&create_quest_request)
+            .unwrap()
+            .as_str(),
+    );
+
+    let req = TestRequest::post()
+        .uri("/api/quests")
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .set_json(&create_quest_request)
+        .to_request();
+
+    let response = call_service(&app, req).await;
+
+    assert!(response.status().is_client_error());
+    let body: ErrorResponse = read_body_json(response).await;
+    assert_eq!(body.code, 400);
+    let paths = body
+        .errors
+        .iter()
+        .map(|error| error.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            "name",
+            "definition.steps[0]",
+            "definition.steps[1].tasks[0].action_items[0]",
+            "ends_at",
+            "instance_duration"
+        ]
+    );
+    assert_eq!(body.errors[0].message, "Name should be longer");
+    assert!(body
+        .message
+        .contains("Quest Validation Error: Name should be longer"));
+}
+
 #[actix_web::test]
 async fn create_quest_should_be_400_quest_validation_error_rewards_webhook() {
     let config = get_configuration(None).await;
@@ -207,6 +515,11 @@ async fn create_quest_should_be_400_quest_validation_error_rewards_webhook() {
             },
             items: vec![QuestRewardItem { name: "SunGlasses".to_string(), image_link: "https://github.com/decentraland".to_string() }]
         }),
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let headers = get_signed_headers(
@@ -262,6 +575,11 @@ async fn create_quest_should_be_400_quest_validation_error_rewards_items() {
             },
             items: vec![QuestRewardItem { name: "SunGlasses".to_string(), image_link: "github/decentraland".to_string() }]
         }),
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let headers = get_signed_headers(
@@ -367,6 +685,11 @@ async fn create_quest_should_be_401() {
         },
         image_url: "".to_string(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let req = TestRequest::post()
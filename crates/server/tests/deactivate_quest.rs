@@ -2,7 +2,7 @@ mod common;
 
 use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
 pub use common::*;
-use quests_db::core::definitions::{CreateQuest, QuestsDatabase};
+use quests_db::core::definitions::{CreateQuest, QuestsDatabase, RepeatPolicy};
 use quests_db::create_quests_db_component;
 use quests_protocol::definitions::*;
 use quests_server::api::routes::quests::CreateQuestRequest;
@@ -24,6 +24,11 @@ async fn deactivate_quest_should_be_200() {
             steps: vec![],       // not needed for this test
         },
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let create_quest = CreateQuest {
@@ -32,6 +37,11 @@ async fn deactivate_quest_should_be_200() {
         image_url: &quest_definition.image_url,
         definition: vec![],
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -111,6 +121,11 @@ async fn deactivate_quest_should_be_403() {
         },
         image_url: "".to_string(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let create_quest = CreateQuest {
@@ -119,6 +134,11 @@ async fn deactivate_quest_should_be_403() {
         image_url: &quest_definition.image_url,
         definition: vec![],
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db.create_quest(&create_quest, "0xA").await.unwrap();
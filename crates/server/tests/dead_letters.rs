@@ -0,0 +1,92 @@
+mod common;
+use std::sync::Arc;
+
+use actix_web::test::{call_service, init_service, read_body_json, try_call_service, TestRequest};
+pub use common::*;
+use quests_message_broker::{
+    dead_letters::{DeadLetter, DeadLetterQueue, RedisDeadLetterQueue},
+    messages_queue::{MessagesQueue, RedisMessagesQueue},
+    redis::Redis,
+};
+use quests_protocol::{definitions::*, quests::Coordinates};
+use quests_server::api::routes::dead_letters::GetDeadLettersResponse;
+use quests_system::{QUESTS_EVENTS_DEAD_LETTERS_NAME, QUESTS_EVENTS_QUEUE_NAME};
+
+#[actix_web::test]
+async fn dead_letters_can_be_listed_replayed_and_dropped() {
+    let config = get_configuration(Some(9)).await;
+    let app = init_service(build_app(&config).await).await;
+
+    let redis = Arc::new(Redis::new(&config.redis_url).await.unwrap());
+    let dead_letters = RedisDeadLetterQueue::new(redis.clone(), QUESTS_EVENTS_DEAD_LETTERS_NAME);
+    let events_queue = RedisMessagesQueue::new(redis, QUESTS_EVENTS_QUEUE_NAME);
+
+    let mut events = vec![];
+    for x in 0..2 {
+        let event = Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: "0xA".to_string(),
+            action: Some(Action::location(Coordinates::new(x, 0))),
+            instance_id: None,
+        };
+        dead_letters
+            .push(&DeadLetter {
+                id: event.id.clone(),
+                message: event.clone(),
+                attempts: 5,
+                reason: "Serialization".to_string(),
+                failed_at: 0,
+            })
+            .await
+            .unwrap();
+        events.push(event);
+    }
+
+    // only allowed with the bearer token
+    let req = TestRequest::get().uri("/api/dead-letters").to_request();
+    let error = try_call_service(&app, req).await.err().unwrap();
+    assert_eq!(error.as_response_error().status_code(), 401);
+
+    // an encoded path is routed to the dead letters too, so it needs the token as well
+    let req = TestRequest::get().uri("/api/dead%2Dletters").to_request();
+    let error = try_call_service(&app, req).await.err().unwrap();
+    assert_eq!(error.as_response_error().status_code(), 401);
+
+    let req = TestRequest::get()
+        .uri("/api/dead-letters")
+        .append_header(("Authorization", "Bearer DEV"))
+        .to_request();
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status(), 200);
+    let body: GetDeadLettersResponse = read_body_json(response).await;
+    assert_eq!(body.dead_letters.len(), 2);
+    assert_eq!(body.dead_letters[0].id, events[0].id);
+    assert_eq!(body.dead_letters[0].attempts, 5);
+    assert_eq!(body.dead_letters[0].reason, "Serialization");
+
+    let req = TestRequest::post()
+        .uri(&format!("/api/dead-letters/{}/replay", events[0].id))
+        .append_header(("Authorization", "Bearer DEV"))
+        .to_request();
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status(), 204);
+    let replayed: Event = events_queue.pop().await.unwrap();
+    assert_eq!(replayed, events[0]);
+
+    let req = TestRequest::delete()
+        .uri(&format!("/api/dead-letters/{}", events[1].id))
+        .append_header(("Authorization", "Bearer DEV"))
+        .to_request();
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status(), 204);
+
+    let req = TestRequest::delete()
+        .uri(&format!("/api/dead-letters/{}", events[1].id))
+        .append_header(("Authorization", "Bearer DEV"))
+        .to_request();
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status(), 404);
+
+    let remaining: Vec<DeadLetter<Event>> = dead_letters.list().await.unwrap();
+    assert!(remaining.is_empty());
+}
@@ -2,10 +2,12 @@ mod common;
 use actix_web::http::StatusCode;
 use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
 pub use common::*;
-use quests_db::core::definitions::{CreateQuest, QuestsDatabase};
+use quests_db::core::definitions::{AddEvent, CreateQuest, QuestsDatabase, RepeatPolicy};
 use quests_db::create_quests_db_component;
 use quests_protocol::definitions::*;
+use quests_protocol::quests::Coordinates;
 use quests_server::api::routes::quest_instances::GetInstanceStateResponse;
+use uuid::Uuid;
 
 #[actix_web::test]
 async fn get_instance_state_should_be_200() {
@@ -23,6 +25,11 @@ async fn get_instance_state_should_be_200() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -53,6 +60,116 @@ async fn get_instance_state_should_be_200() {
     assert_eq!(json.events.len(), 0)
 }
 
+#[actix_web::test]
+async fn get_instance_state_at_event_should_be_200() {
+    let config = get_configuration(None).await;
+    let db = create_quests_db_component(&config.database_url, true)
+        .await
+        .unwrap();
+
+    let app = init_service(build_app(&config).await).await;
+    let quest = quest_samples::grab_some_apples();
+
+    let create_quest = CreateQuest {
+        name: &quest.name,
+        description: &quest.description,
+        image_url: &quest.image_url,
+        definition: quest.definition.as_ref().unwrap().encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let id = db
+        .create_quest(&create_quest, "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1bf5")
+        .await
+        .unwrap();
+
+    let quest_instance_id = db.start_quest(&id, "0xA").await.unwrap();
+
+    let actions = [
+        Action::location(Coordinates::new(10, 20)),
+        Action::location(Coordinates::new(13, 20)),
+    ];
+    let mut event_ids = vec![];
+    for action in actions {
+        let event = Event {
+            id: Uuid::new_v4().to_string(),
+            address: "0xA".to_string(),
+            action: Some(action),
+            instance_id: None,
+        };
+        db.add_event(
+            &AddEvent {
+                id: event.id.clone(),
+                user_address: "0xA",
+                event: event.encode_to_vec(),
+            },
+            &quest_instance_id,
+        )
+        .await
+        .unwrap();
+        event_ids.push(event.id);
+    }
+
+    let path = format!("/api/instances/{}/state", quest_instance_id);
+
+    // the query string is not part of the signed path
+    let headers = get_signed_headers(create_test_identity(), "get", &path, "");
+
+    let req = TestRequest::get()
+        .uri(&format!("{path}?at={}", event_ids[0]))
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    let json: GetInstanceStateResponse = read_body_json(response).await;
+    assert_eq!(json.state.steps_completed, vec!["A"]);
+    assert_eq!(json.events.len(), 1);
+    assert_eq!(json.events[0].id, event_ids[0]);
+
+    let headers = get_signed_headers(create_test_identity(), "get", &path, "");
+
+    let req = TestRequest::get()
+        .uri(&format!("{path}?at=0"))
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    let json: GetInstanceStateResponse = read_body_json(response).await;
+    assert_eq!(json.state.steps_completed.len(), 0);
+    assert_eq!(json.events.len(), 0);
+
+    let headers = get_signed_headers(create_test_identity(), "get", &path, "");
+
+    let req = TestRequest::get()
+        .uri(&format!("{path}?at={}", Uuid::new_v4()))
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+}
+
 #[actix_web::test]
 async fn get_instance_state_should_be_403() {
     let config = get_configuration(None).await;
@@ -69,6 +186,11 @@ async fn get_instance_state_should_be_403() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -2,7 +2,7 @@ mod common;
 use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
 use actix_web_lab::__reexports::serde_json;
 use common::*;
-use quests_db::core::definitions::{CreateQuest, QuestsDatabase};
+use quests_db::core::definitions::{CreateQuest, QuestsDatabase, RepeatPolicy};
 use quests_db::create_quests_db_component;
 use quests_protocol::definitions::*;
 use quests_server::api::routes::quests::GetQuestResponse;
@@ -28,6 +28,11 @@ async fn get_quest_with_defintiions_should_be_200() {
             .unwrap()
             .encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -98,6 +103,11 @@ async fn get_quest_without_defintiions_should_be_200() {
             .unwrap()
             .encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -0,0 +1,232 @@
+mod common;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+pub use common::*;
+use quests_db::core::definitions::{
+    AddEvent, CreateQuest, QuestsDatabase, RepeatPolicy, SaveQuestInstanceState,
+};
+use quests_db::create_quests_db_component;
+use quests_protocol::definitions::*;
+use quests_protocol::quests::{get_state, Coordinates};
+use quests_server::api::routes::quest_instances::GetInstanceEventsResponse;
+use uuid::Uuid;
+
+#[actix_web::test]
+async fn get_quest_instance_events_should_be_200() {
+    let config = get_configuration(None).await;
+    let db = create_quests_db_component(&config.database_url, true)
+        .await
+        .unwrap();
+
+    let app = init_service(build_app(&config).await).await;
+    let quest = quest_samples::grab_some_apples();
+
+    let create_quest = CreateQuest {
+        name: &quest.name,
+        description: &quest.description,
+        image_url: &quest.image_url,
+        definition: quest.definition.as_ref().unwrap().encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let id = db
+        .create_quest(&create_quest, "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1bf5")
+        .await
+        .unwrap();
+
+    let quest_instance_id = db.start_quest(&id, "0xA").await.unwrap();
+
+    let actions = [
+        Action::location(Coordinates::new(10, 20)),
+        Action::jump(Coordinates::new(0, 0)),
+        Action::location(Coordinates::new(13, 20)),
+    ];
+    let mut event_ids = vec![];
+    for action in actions {
+        let event = Event {
+            id: Uuid::new_v4().to_string(),
+            address: "0xA".to_string(),
+            action: Some(action),
+            instance_id: None,
+        };
+        db.add_event(
+            &AddEvent {
+                id: event.id.clone(),
+                user_address: "0xA",
+                event: event.encode_to_vec(),
+            },
+            &quest_instance_id,
+        )
+        .await
+        .unwrap();
+        event_ids.push(event.id.clone());
+
+        // a snapshot taken after the first event, so the pages after it are applied from it
+        if event_ids.len() == 1 {
+            let state = get_state(&quest, &[event]);
+            let saved = db
+                .save_quest_instance_state(
+                    &quest_instance_id,
+                    &SaveQuestInstanceState {
+                        state: state.encode_to_vec(),
+                        previous_event_id: None,
+                        applied_event_ids: vec![&event_ids[0]],
+                    },
+                )
+                .await
+                .unwrap();
+            assert!(saved);
+        }
+    }
+
+    let path = format!("/api/instances/{}/events", quest_instance_id);
+
+    let headers = get_signed_headers(create_test_identity(), "get", &path, "");
+
+    let req = TestRequest::get()
+        .uri(&path)
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let body: GetInstanceEventsResponse = read_body_json(response).await;
+    assert_eq!(body.events.len(), 3);
+    assert_eq!(body.events[0].id, event_ids[0]);
+    assert_eq!(
+        body.events[0].action,
+        Some(Action::location(Coordinates::new(10, 20)))
+    );
+    assert_eq!(body.events[0].steps_completed, vec!["A"]);
+    assert_eq!(body.events[0].tasks_advanced, vec!["A_1"]);
+    assert!(body.events[1].steps_completed.is_empty());
+    assert!(body.events[1].tasks_advanced.is_empty());
+    assert_eq!(body.events[2].steps_completed, vec!["B"]);
+
+    // the query string is not part of the signed path
+    let headers = get_signed_headers(create_test_identity(), "get", &path, "");
+
+    let req = TestRequest::get()
+        .uri(&format!("{path}?offset=2&limit=1"))
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let body: GetInstanceEventsResponse = read_body_json(response).await;
+    assert_eq!(body.events.len(), 1);
+    assert_eq!(body.events[0].id, event_ids[2]);
+    assert_eq!(body.events[0].steps_completed, vec!["B"]);
+
+    let req = TestRequest::get()
+        .uri(&format!("{path}?offset=1&limit=2"))
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let body: GetInstanceEventsResponse = read_body_json(response).await;
+    assert_eq!(body.events.len(), 2);
+    assert_eq!(body.events[0].id, event_ids[1]);
+    assert!(body.events[0].steps_completed.is_empty());
+    assert_eq!(body.events[1].id, event_ids[2]);
+    assert_eq!(body.events[1].steps_completed, vec!["B"]);
+}
+
+#[actix_web::test]
+async fn get_quest_instance_events_should_be_400() {
+    let config = get_configuration(None).await;
+    let app = init_service(build_app(&config).await).await;
+
+    let path = format!("/api/instances/{}/events", Uuid::new_v4());
+    let headers = get_signed_headers(create_test_identity(), "get", &path, "");
+
+    for query in [
+        "offset=-1",
+        "limit=0",
+        "limit=101",
+        "from=-1",
+        "to=99999999999999",
+        "from=20&to=10",
+    ] {
+        let req = TestRequest::get()
+            .uri(&format!("{path}?{query}"))
+            .append_header(headers[0].clone())
+            .append_header(headers[1].clone())
+            .append_header(headers[2].clone())
+            .append_header(headers[3].clone())
+            .append_header(headers[4].clone())
+            .to_request();
+
+        let response = call_service(&app, req).await;
+        assert_eq!(
+            response.status().as_u16(),
+            StatusCode::BAD_REQUEST,
+            "{query}"
+        );
+    }
+}
+
+#[actix_web::test]
+async fn get_quest_instance_events_should_be_403() {
+    let config = get_configuration(None).await;
+    let db = create_quests_db_component(&config.database_url, true)
+        .await
+        .unwrap();
+
+    let app = init_service(build_app(&config).await).await;
+    let quest = quest_samples::grab_some_apples();
+
+    let create_quest = CreateQuest {
+        name: &quest.name,
+        description: &quest.description,
+        image_url: &quest.image_url,
+        definition: quest.definition.as_ref().unwrap().encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let id = db
+        .create_quest(&create_quest, "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1ba5")
+        .await
+        .unwrap();
+
+    let quest_instance_id = db.start_quest(&id, "0xA").await.unwrap();
+
+    let path = format!("/api/instances/{}/events", quest_instance_id);
+
+    let headers = get_signed_headers(create_test_identity(), "get", &path, "");
+
+    let req = TestRequest::get()
+        .uri(&path)
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+}
@@ -7,7 +7,7 @@ use actix_web::{
 use common::*;
 use quests_db::{
     core::definitions::{
-        CreateQuest, QuestReward, QuestRewardHook, QuestRewardItem, QuestsDatabase,
+        CreateQuest, QuestReward, QuestRewardHook, QuestRewardItem, QuestsDatabase, RepeatPolicy,
     },
     create_quests_db_component,
 };
@@ -38,6 +38,11 @@ async fn get_quest_rewards_should_be_200() {
                 definition: definition.unwrap().encode_to_vec(),
                 image_url: "",
                 reward: None,
+                starts_at: None,
+                ends_at: None,
+                instance_duration: None,
+                prerequisites: vec![],
+                repeat_policy: RepeatPolicy::Never,
             },
             "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1bf5", // identity address
         )
@@ -90,6 +95,11 @@ async fn quest_has_no_rewards() {
                 definition: definition.unwrap().encode_to_vec(),
                 image_url: "",
                 reward: None,
+                starts_at: None,
+                ends_at: None,
+                instance_duration: None,
+                prerequisites: vec![],
+                repeat_policy: RepeatPolicy::Never,
             },
             "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1bf5", // identity address
         )
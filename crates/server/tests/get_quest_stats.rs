@@ -3,7 +3,7 @@ mod common;
 use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
 use common::*;
 use quests_db::{
-    core::definitions::{CreateQuest, QuestsDatabase},
+    core::definitions::{CreateQuest, QuestsDatabase, RepeatPolicy},
     create_quests_db_component,
 };
 use quests_protocol::definitions::*;
@@ -32,6 +32,11 @@ async fn get_quest_stats_should_be_200() {
                 definition: definition.unwrap().encode_to_vec(),
                 image_url: "",
                 reward: None,
+                starts_at: None,
+                ends_at: None,
+                instance_duration: None,
+                prerequisites: vec![],
+                repeat_policy: RepeatPolicy::Never,
             },
             "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1bf5", // identity address
         )
@@ -70,6 +75,72 @@ async fn get_quest_stats_should_be_200() {
     assert_eq!(response.abandoned, 1);
     assert_eq!(response.started_in_last_24_hours, 2);
     assert_eq!(response.completed, 0);
+    assert_eq!(response.expired, 0);
+}
+
+#[actix_web::test]
+async fn get_quest_stats_should_count_expired_instances() {
+    let config = get_configuration(None).await;
+    let db = create_quests_db_component(&config.database_url, true)
+        .await
+        .unwrap();
+    let app = init_service(build_app(&config).await).await;
+
+    let Quest {
+        name,
+        description,
+        definition,
+        ..
+    } = quest_samples::grab_some_apples();
+
+    // instances expire as soon as they are started
+    let id = db
+        .create_quest(
+            &CreateQuest {
+                name: &name,
+                description: &description,
+                definition: definition.unwrap().encode_to_vec(),
+                image_url: "",
+                reward: None,
+                starts_at: None,
+                ends_at: None,
+                instance_duration: Some(0),
+                prerequisites: vec![],
+                repeat_policy: RepeatPolicy::Never,
+            },
+            "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1bf5", // identity address
+        )
+        .await
+        .unwrap();
+
+    db.start_quest(&id, "0xA").await.unwrap();
+    db.start_quest(&id, "0xB").await.unwrap();
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "get",
+        format!("/api/quests/{}/stats", id).as_str(),
+        "{}",
+    );
+
+    let req = TestRequest::get()
+        .uri(format!("/api/quests/{}/stats", id).as_str())
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+
+    assert!(response.status().is_success());
+
+    let response: GetQuestStatsResponse = read_body_json(response).await;
+
+    assert_eq!(response.active_players, 0);
+    assert_eq!(response.expired, 2);
+    assert_eq!(response.completed, 0);
 }
 
 #[actix_web::test]
@@ -95,6 +166,11 @@ async fn get_quest_stats_should_be_403() {
                 definition: definition.unwrap().encode_to_vec(),
                 image_url: "",
                 reward: None,
+                starts_at: None,
+                ends_at: None,
+                instance_duration: None,
+                prerequisites: vec![],
+                repeat_policy: RepeatPolicy::Never,
             },
             "0xB",
         )
@@ -147,6 +223,11 @@ async fn get_quest_stats_should_be_401() {
                 definition: definition.unwrap().encode_to_vec(),
                 image_url: "",
                 reward: None,
+                starts_at: None,
+                ends_at: None,
+                instance_duration: None,
+                prerequisites: vec![],
+                repeat_policy: RepeatPolicy::Never,
             },
             "0xB",
         )
@@ -2,7 +2,7 @@ mod common;
 use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
 pub use common::*;
 use quests_db::{
-    core::definitions::{CreateQuest, QuestsDatabase},
+    core::definitions::{CreateQuest, QuestsDatabase, RepeatPolicy},
     create_quests_db_component,
 };
 use quests_protocol::definitions::ProtocolMessage;
@@ -24,6 +24,11 @@ async fn get_quests_should_be_200() {
         description: &quest_definition.description,
         image_url: &quest_definition.image_url,
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     db.create_quest(&quest, "0xA").await.unwrap();
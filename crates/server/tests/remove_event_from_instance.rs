@@ -2,7 +2,7 @@ mod common;
 use actix_web::http::StatusCode;
 use actix_web::test::{call_service, init_service, TestRequest};
 pub use common::*;
-use quests_db::core::definitions::{AddEvent, CreateQuest, QuestsDatabase};
+use quests_db::core::definitions::{AddEvent, CreateQuest, QuestsDatabase, RepeatPolicy};
 use quests_db::create_quests_db_component;
 use quests_protocol::definitions::*;
 use uuid::Uuid;
@@ -23,6 +23,11 @@ async fn remove_event_from_instance_should_be_200() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -44,6 +49,11 @@ async fn remove_event_from_instance_should_be_200() {
     .await
     .unwrap();
 
+    // recorded as completed, but the state calculated after removing the event is not
+    db.complete_quest_instance(&quest_instance_id)
+        .await
+        .unwrap();
+
     let path = format!("/api/instances/{}/events/{}", quest_instance_id, event_id);
 
     let headers = get_signed_headers(create_test_identity(), "delete", &path, "");
@@ -59,6 +69,8 @@ async fn remove_event_from_instance_should_be_200() {
 
     let response = call_service(&app, req).await;
     assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    assert!(db.get_events(&quest_instance_id).await.unwrap().is_empty());
+    assert!(!db.is_completed_instance(&quest_instance_id).await.unwrap());
 }
 
 #[actix_web::test]
@@ -77,6 +89,11 @@ async fn remove_event_from_instance_should_be_403() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -123,6 +140,11 @@ async fn remove_event_from_instance_should_be_404() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -171,4 +193,38 @@ async fn remove_event_from_instance_should_be_404() {
 
     let response = call_service(&app, req).await;
     assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    // the event belongs to another instance
+    let other_quest_instance_id = db.start_quest(&id, "0xB").await.unwrap();
+    let event_id = Uuid::new_v4().to_string();
+    db.add_event(
+        &AddEvent {
+            id: event_id.clone(),
+            user_address: "0xB",
+            event: vec![],
+        },
+        &other_quest_instance_id,
+    )
+    .await
+    .unwrap();
+
+    let path = format!("/api/instances/{}/events/{}", quest_instance_id, event_id);
+
+    let headers = get_signed_headers(create_test_identity(), "delete", &path, "");
+
+    let req = TestRequest::delete()
+        .uri(&path)
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .to_request();
+
+    let response = call_service(&app, req).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        db.get_events(&other_quest_instance_id).await.unwrap().len(),
+        1
+    );
 }
@@ -2,7 +2,7 @@ mod common;
 use actix_web::http::StatusCode;
 use actix_web::test::{call_service, init_service, TestRequest};
 pub use common::*;
-use quests_db::core::definitions::{AddEvent, CreateQuest, QuestsDatabase};
+use quests_db::core::definitions::{AddEvent, CreateQuest, QuestsDatabase, RepeatPolicy};
 use quests_db::create_quests_db_component;
 use quests_protocol::definitions::*;
 
@@ -22,6 +22,11 @@ async fn reset_quest_instance_should_be_204() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -86,6 +91,11 @@ async fn reset_quest_instance_should_be_403() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
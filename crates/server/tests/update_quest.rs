@@ -2,7 +2,7 @@ mod common;
 use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
 use actix_web_lab::__reexports::serde_json;
 pub use common::*;
-use quests_db::core::definitions::{CreateQuest, QuestsDatabase};
+use quests_db::core::definitions::{CreateQuest, QuestsDatabase, RepeatPolicy};
 use quests_db::create_quests_db_component;
 use quests_protocol::definitions::*;
 use quests_protocol::quests::Coordinates;
@@ -25,6 +25,11 @@ async fn update_quest_should_be_200() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db
@@ -49,8 +54,11 @@ async fn update_quest_should_be_200() {
                         id: "A_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -58,8 +66,11 @@ async fn update_quest_should_be_200() {
                         id: "B_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -67,8 +78,11 @@ async fn update_quest_should_be_200() {
                         id: "C_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -76,12 +90,20 @@ async fn update_quest_should_be_200() {
                         id: "D_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         },
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let path = format!("/api/quests/{}", id);
@@ -143,8 +165,11 @@ async fn update_quest_should_be_400_uuid_bad_format() {
                         id: "A_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -152,8 +177,11 @@ async fn update_quest_should_be_400_uuid_bad_format() {
                         id: "B_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -161,8 +189,11 @@ async fn update_quest_should_be_400_uuid_bad_format() {
                         id: "C_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -170,12 +201,20 @@ async fn update_quest_should_be_400_uuid_bad_format() {
                         id: "D_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         },
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let quest_update = CreateQuestRequest {
@@ -221,6 +260,11 @@ async fn update_quest_should_be_400_quest_validation_error() {
         },
         image_url: "".to_string(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let quest_update = CreateQuestRequest {
@@ -281,8 +325,11 @@ async fn update_quest_should_be_401() {
                         id: "A_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -290,8 +337,11 @@ async fn update_quest_should_be_401() {
                         id: "B_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -299,8 +349,11 @@ async fn update_quest_should_be_401() {
                         id: "C_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -308,12 +361,20 @@ async fn update_quest_should_be_401() {
                         id: "D_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         },
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let path = format!("/api/quests/{}", uuid::Uuid::new_v4());
@@ -344,6 +405,11 @@ async fn update_quest_should_be_403() {
         image_url: &quest.image_url,
         definition: quest.definition.as_ref().unwrap().encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let id = db.create_quest(&create_quest, "0xA").await.unwrap();
@@ -365,8 +431,11 @@ async fn update_quest_should_be_403() {
                         id: "A_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -374,8 +443,11 @@ async fn update_quest_should_be_403() {
                         id: "B_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -383,8 +455,11 @@ async fn update_quest_should_be_403() {
                         id: "C_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -392,12 +467,20 @@ async fn update_quest_should_be_403() {
                         id: "D_1".to_string(),
                         description: "desc".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "desc".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         },
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let path = format!("/api/quests/{}", id);
@@ -426,3 +509,85 @@ async fn update_quest_should_be_403() {
     assert_eq!(body.code, 403);
     assert!(body.message.contains("Cannot modify a quest"));
 }
+
+#[actix_web::test]
+async fn update_quest_should_be_400_with_cyclic_prerequisites() {
+    let config = get_configuration(None).await;
+    let db = create_quests_db_component(&config.database_url, true)
+        .await
+        .unwrap();
+
+    let app = init_service(build_app(&config).await).await;
+    let quest = quest_samples::grab_some_apples();
+
+    let create_quest = CreateQuest {
+        name: &quest.name,
+        description: &quest.description,
+        image_url: &quest.image_url,
+        definition: quest.definition.as_ref().unwrap().encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let id = db
+        .create_quest(&create_quest, "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1bf5")
+        .await
+        .unwrap();
+    let chained_id = db
+        .create_quest(
+            &CreateQuest {
+                prerequisites: vec![id.clone()],
+                ..create_quest.clone()
+            },
+            "0x7949f9f239d1a0816ce5eb364a1f588ae9cc1bf5",
+        )
+        .await
+        .unwrap();
+
+    // the quest itself and a quest that requires it
+    for prerequisite in [&id, &chained_id] {
+        let quest_update = CreateQuestRequest {
+            name: quest.name.clone(),
+            description: quest.description.clone(),
+            image_url: quest.image_url.clone(),
+            definition: quest.definition.clone().unwrap(),
+            reward: None,
+            starts_at: None,
+            ends_at: None,
+            instance_duration: None,
+            prerequisites: vec![prerequisite.clone()],
+            repeat_policy: RepeatPolicy::Never,
+        };
+
+        let path = format!("/api/quests/{}", id);
+
+        let headers = get_signed_headers(
+            create_test_identity(),
+            "put",
+            &path,
+            &serde_json::to_string(&quest_update).unwrap(),
+        );
+
+        let req = TestRequest::put()
+            .uri(&path)
+            .append_header(headers[0].clone())
+            .append_header(headers[1].clone())
+            .append_header(headers[2].clone())
+            .append_header(headers[3].clone())
+            .append_header(headers[4].clone())
+            .set_json(&quest_update)
+            .to_request();
+
+        let response = call_service(&app, req).await;
+        assert_eq!(response.status(), 400);
+        let body: ErrorResponse = read_body_json(response).await;
+        assert_eq!(body.errors.len(), 1);
+        assert_eq!(body.errors[0].path, "prerequisites[0]");
+    }
+
+    assert!(db.is_active_quest(&id).await.unwrap());
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+use actix_web_lab::__reexports::serde_json;
+use common::*;
+use quests_db::core::definitions::RepeatPolicy;
+use quests_protocol::definitions::*;
+use quests_server::api::routes::{
+    quests::{CreateQuestRequest, ValidateQuestResponse},
+    ErrorResponse,
+};
+
+#[actix_web::test]
+async fn validate_quest_should_be_200() {
+    let config = get_configuration(None).await;
+    let app = init_service(build_app(&config).await).await;
+    let Quest {
+        name,
+        description,
+        definition,
+        image_url,
+        ..
+    } = quest_samples::grab_some_apples();
+
+    let validate_quest_request = CreateQuestRequest {
+        name,
+        description,
+        definition: definition.unwrap(),
+        image_url,
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "post",
+        "/api/quests/validate",
+        serde_json::to_string(&validate_quest_request)
+            .unwrap()
+            .as_str(),
+    );
+
+    let req = TestRequest::post()
+        .uri("/api/quests/validate")
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .set_json(&validate_quest_request)
+        .to_request();
+
+    let response = call_service(&app, req).await;
+
+    assert!(response.status().is_success());
+    let body: ValidateQuestResponse = read_body_json(response).await;
+    assert_eq!(body.total_steps, 4);
+    assert_eq!(body.starting_steps, vec!["A"]);
+    assert_eq!(body.required_steps, vec!["D"]);
+    assert!(body.required_branch_groups.is_empty());
+}
+
+#[actix_web::test]
+async fn validate_quest_should_be_400() {
+    let config = get_configuration(None).await;
+    let app = init_service(build_app(&config).await).await;
+    let Quest {
+        name,
+        description,
+        definition,
+        image_url,
+        ..
+    } = quest_samples::grab_some_apples();
+    let mut definition = definition.unwrap();
+    definition.connections.push(Connection::new("D", "A"));
+
+    let validate_quest_request = CreateQuestRequest {
+        name,
+        description,
+        definition,
+        image_url,
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "post",
+        "/api/quests/validate",
+        serde_json::to_string(&validate_quest_request)
+            .unwrap()
+            .as_str(),
+    );
+
+    let req = TestRequest::post()
+        .uri("/api/quests/validate")
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .set_json(&validate_quest_request)
+        .to_request();
+
+    let response = call_service(&app, req).await;
+
+    assert_eq!(response.status(), 400);
+    let body: ErrorResponse = read_body_json(response).await;
+    assert_eq!(body.code, 400);
+    assert!(body
+        .errors
+        .iter()
+        .any(|error| error.path == "definition.connections"
+            && error.message.contains("Connections form a cycle")));
+}
+
+#[actix_web::test]
+async fn validate_quest_should_be_400_with_unknown_prerequisite() {
+    let config = get_configuration(None).await;
+    let app = init_service(build_app(&config).await).await;
+    let Quest {
+        name,
+        description,
+        definition,
+        image_url,
+        ..
+    } = quest_samples::grab_some_apples();
+
+    let validate_quest_request = CreateQuestRequest {
+        name,
+        description,
+        definition: definition.unwrap(),
+        image_url,
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![uuid::Uuid::new_v4().to_string()],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let headers = get_signed_headers(
+        create_test_identity(),
+        "post",
+        "/api/quests/validate",
+        serde_json::to_string(&validate_quest_request)
+            .unwrap()
+            .as_str(),
+    );
+
+    let req = TestRequest::post()
+        .uri("/api/quests/validate")
+        .append_header(headers[0].clone())
+        .append_header(headers[1].clone())
+        .append_header(headers[2].clone())
+        .append_header(headers[3].clone())
+        .append_header(headers[4].clone())
+        .set_json(&validate_quest_request)
+        .to_request();
+
+    let response = call_service(&app, req).await;
+
+    assert_eq!(response.status(), 400);
+    let body: ErrorResponse = read_body_json(response).await;
+    assert_eq!(body.errors.len(), 1);
+    assert_eq!(body.errors[0].path, "prerequisites[0]");
+}
@@ -9,6 +9,8 @@ pub async fn start_server(port: &str) -> Server {
     let server = HttpServer::new(|| App::new().configure(routes::services))
         .bind(&server_address)
         .unwrap()
+        // it's stopped by the app once the events being processed finish
+        .disable_signals()
         .run();
 
     log::info!("Quests System REST API running at http://{server_address}");
@@ -1,46 +1,174 @@
 use crate::rewards::give_rewards_to_user;
-use log::{debug, error, info};
+use futures_util::FutureExt;
+use log::{debug, error, info, warn};
 use quests_db::{
     core::{
-        definitions::{AddEvent, QuestsDatabase},
+        definitions::{AddEvent, QuestsDatabase, SaveQuestInstanceState},
         errors::DBError,
     },
     create_quests_db_component, Database,
 };
 use quests_message_broker::{
     channel::RedisChannelPublisher,
-    messages_queue::{MessagesQueue, RedisMessagesQueue},
+    dead_letters::{DeadLetter, DeadLetterQueue, RedisDeadLetterQueue},
+    messages_queue::{Delivery, MessagesQueue, RedisMessagesQueue},
     redis::Redis,
+    streams_messages_queue::RedisStreamsMessagesQueue,
 };
 use quests_protocol::{definitions::*, quests::*};
-use std::sync::Arc;
-use tokio::task::JoinHandle;
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    select,
+    sync::{
+        oneshot::{self, error::TryRecvError},
+        watch, OwnedSemaphorePermit, Semaphore,
+    },
+    task::{JoinError, JoinHandle, JoinSet},
+    time::Instant,
+};
 
 use crate::{
-    configuration::Config,
+    configuration::{Config, EventsQueueBackend},
     quests::{get_all_quest_states_by_user_address, QuestStateCalculationError},
-    QUESTS_CHANNEL_NAME, QUESTS_EVENTS_QUEUE_NAME,
+    QUESTS_CHANNEL_NAME, QUESTS_EVENTS_DEAD_LETTERS_NAME, QUESTS_EVENTS_GROUP_NAME,
+    QUESTS_EVENTS_QUEUE_NAME, QUESTS_EVENTS_STREAM_NAME,
 };
 
 pub type Error = String;
 pub type EventProcessingResult<T> = Result<T, Error>;
 
+/// How long a pop waits for an event before checking if the processing was stopped. The pop isn't cancelled
+/// instead, as the event could still be handed to its blocked connection
+const POP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often the events left by consumers that stopped are reclaimed. It's also how a consumer of a reliable list
+/// queue lets the others know it's alive, so it has to be shorter than the claim idle time
+const RECLAIM_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How many popped events may wait for the previous event of their user, for each worker. Popping stops while they're
+/// all taken, so the events don't pile up in memory and, under streams, don't go idle long enough to be claimed
+pub const WAITING_EVENTS_PER_WORKER: usize = 10;
+
+/// How many times a failed event is processed before moving it to the dead letters, and how long to wait before each retry
+#[derive(Debug, Clone, Copy)]
+pub struct RetryOptions {
+    pub max_attempts: u32,
+    pub backoff: Duration,
+}
+
+impl RetryOptions {
+    /// The backoff is doubled on every failed attempt
+    fn backoff_for(&self, attempts: u32) -> Duration {
+        self.backoff * 2u32.pow(attempts.saturating_sub(1).min(10))
+    }
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl From<&Config> for RetryOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_attempts: config.event_max_attempts,
+            backoff: Duration::from_millis(config.event_retry_backoff_ms),
+        }
+    }
+}
+
+/// The events queue, whichever backend is configured
+pub type EventsQueue = dyn MessagesQueue<Event>;
+
+/// Which queue backs the events and how it's consumed
+#[derive(Debug, Clone)]
+pub struct EventsQueueOptions {
+    pub backend: EventsQueueBackend,
+    /// Only used by the list backend, the streams one is always reliable
+    pub reliable: bool,
+    pub consumer: String,
+    pub claim_min_idle: Duration,
+}
+
+impl From<&Config> for EventsQueueOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            backend: config.events_queue_backend,
+            reliable: config.reliable_events_queue,
+            consumer: config.events_queue_consumer.clone(),
+            claim_min_idle: Duration::from_millis(config.events_queue_claim_idle_ms),
+        }
+    }
+}
+
+pub async fn create_events_queue(
+    redis: Arc<Redis>,
+    options: &EventsQueueOptions,
+) -> EventProcessingResult<Arc<EventsQueue>> {
+    match options.backend {
+        EventsQueueBackend::List if options.reliable => {
+            Ok(Arc::new(RedisMessagesQueue::new_reliable(
+                redis,
+                QUESTS_EVENTS_QUEUE_NAME,
+                &options.consumer,
+                options.claim_min_idle,
+            )))
+        }
+        EventsQueueBackend::List => Ok(Arc::new(RedisMessagesQueue::new(
+            redis,
+            QUESTS_EVENTS_QUEUE_NAME,
+        ))),
+        EventsQueueBackend::Streams => {
+            let events_queue = RedisStreamsMessagesQueue::new(
+                redis,
+                QUESTS_EVENTS_STREAM_NAME,
+                QUESTS_EVENTS_GROUP_NAME,
+                &options.consumer,
+                options.claim_min_idle,
+            )
+            .await?;
+            Ok(Arc::new(events_queue))
+        }
+    }
+}
+
 pub struct EventProcessor {
-    pub events_queue: Arc<RedisMessagesQueue>,
+    pub events_queue: Arc<EventsQueue>,
+    pub dead_letters: Arc<RedisDeadLetterQueue>,
     quests_channel: Arc<RedisChannelPublisher>,
     database: Arc<Database>,
+    retry_options: RetryOptions,
+    /// Events processed at the same time, at most. The events of a user are always processed one at a time
+    concurrency: usize,
 }
 
 impl EventProcessor {
     pub fn from(
-        events_queue: Arc<RedisMessagesQueue>,
+        events_queue: Arc<EventsQueue>,
+        dead_letters: Arc<RedisDeadLetterQueue>,
         quests_channel: Arc<RedisChannelPublisher>,
         database: Arc<Database>,
+        retry_options: RetryOptions,
+        concurrency: usize,
     ) -> Self {
         Self {
             events_queue,
+            dead_letters,
             quests_channel,
             database,
+            retry_options,
+            concurrency,
         }
     }
 
@@ -50,10 +178,18 @@ impl EventProcessor {
             .map_err(|_| "Couldn't initialize redis connection".to_string())?;
         let redis = Arc::new(redis);
 
-        let events_queue = RedisMessagesQueue::new(redis.clone(), QUESTS_EVENTS_QUEUE_NAME);
-        let events_queue = Arc::new(events_queue);
+        let events_queue = create_events_queue(redis.clone(), &config.into()).await?;
+
+        let dead_letters =
+            RedisDeadLetterQueue::new(redis.clone(), QUESTS_EVENTS_DEAD_LETTERS_NAME);
+        let dead_letters = Arc::new(dead_letters);
 
-        let quests_channel = RedisChannelPublisher::new(redis.clone(), QUESTS_CHANNEL_NAME);
+        let quests_channel = RedisChannelPublisher::new_sharded(
+            redis.clone(),
+            QUESTS_CHANNEL_NAME,
+            config.quests_channel_shards,
+        )
+        .with_replay_log(config.quests_updates_replay_log_size);
         let quests_channel = Arc::new(quests_channel);
 
         let database = create_quests_db_component(&config.database_url, false)
@@ -63,14 +199,71 @@ impl EventProcessor {
 
         Ok(Self {
             events_queue,
+            dead_letters,
             quests_channel,
             database,
+            retry_options: config.into(),
+            concurrency: config.event_processing_concurrency,
         })
     }
 
     pub async fn process(self: Arc<Self>) -> Result<JoinHandle<ProcessEventResult>, Error> {
-        let event = self.events_queue.pop().await?;
-        Ok(tokio::spawn(self.process_event(event)))
+        let delivery = self.events_queue.pop_delivery().await?;
+        let workers = Arc::new(Semaphore::new(1));
+        let worker = workers
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the workers semaphore is never closed");
+        Ok(tokio::spawn(
+            self.process_delivery(delivery, workers, worker),
+        ))
+    }
+
+    /// The event is retried until it's applied or moved to the dead letters, and acked then. The worker is released
+    /// while the event waits to be retried, so other events are processed meanwhile, but the next events of the user
+    /// keep waiting for it, as they're processed once it's done
+    async fn process_delivery(
+        self: Arc<Self>,
+        delivery: Delivery<Event>,
+        workers: Arc<Semaphore>,
+        mut worker: OwnedSemaphorePermit,
+    ) -> ProcessEventResult {
+        let result = loop {
+            let err = match self.clone().process_event(delivery.message.clone()).await {
+                Ok(instances_applied) => break Ok(instances_applied),
+                Err(err) => err,
+            };
+            let Some(backoff) = self.record_failure(&delivery.message, &err).await else {
+                break Err(err);
+            };
+
+            drop(worker);
+            tokio::time::sleep(backoff).await;
+            worker = workers
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("the workers semaphore is never closed");
+        };
+
+        // the failures may have been recorded by another process before, so it's always cleared
+        if result.is_ok() {
+            if let Err(err) = self.dead_letters.clear_failures(&delivery.message.id).await {
+                error!(
+                    "Processing event > Couldn't clear the failed attempts of event {}: {err}",
+                    delivery.message.id
+                );
+            }
+        }
+
+        if let Err(err) = self.events_queue.ack(&delivery).await {
+            error!(
+                "Processing event > Couldn't ack event {}: {err}",
+                delivery.message.id
+            );
+        }
+        result
     }
 
     pub async fn process_event(self: Arc<Self>, event: Event) -> ProcessEventResult {
@@ -81,13 +274,51 @@ impl EventProcessor {
                 info!("Processing event > Event applied to {instances_applied} instances")
             }
             Err(err) => {
-                let _ = self.events_queue.push(&event).await;
-                error!("Processing event > Couldn't process event {:?}", err);
+                error!("Processing event > Couldn't process event {:?}", err)
             }
         }
         result
     }
 
+    /// Records the failed attempt and returns how long to wait before retrying the event, or `None` if it was moved to
+    /// the dead letters because it's out of attempts
+    async fn record_failure(&self, event: &Event, err: &ProcessEventError) -> Option<Duration> {
+        let attempts = match self.dead_letters.record_failure(&event.id).await {
+            Ok(attempts) => attempts,
+            Err(err) => {
+                error!("Processing event > Couldn't record failed attempt: {err}");
+                1
+            }
+        };
+
+        if attempts >= self.retry_options.max_attempts {
+            let failed_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs() as i64)
+                .unwrap_or_default();
+            let dead_letter = DeadLetter {
+                id: event.id.clone(),
+                message: event.clone(),
+                attempts,
+                reason: format!("{err:?}"),
+                failed_at,
+            };
+            match self.dead_letters.push(&dead_letter).await {
+                Ok(_) => warn!(
+                    "Processing event > Event {} moved to the dead letters after {attempts} attempts",
+                    event.id
+                ),
+                Err(err) => error!(
+                    "Processing event > Couldn't move event {} to the dead letters: {err}",
+                    event.id
+                ),
+            }
+            return None;
+        }
+
+        Some(self.retry_options.backoff_for(attempts))
+    }
+
     async fn do_process_event(self: &Arc<Self>, event: &Event) -> Result<usize, ProcessEventError> {
         let quest_instances =
             get_all_quest_states_by_user_address(self.database.clone(), &event.address).await?;
@@ -98,9 +329,22 @@ impl EventProcessor {
         );
 
         let mut event_applied_to_instances = 0;
-        for (instance_id, (quest, quest_state)) in quest_instances {
+        // a redelivered event isn't applied again, nor reported as ignored
+        let mut event_already_applied = false;
+        for (instance_id, (quest, quest_state, last_event_id)) in quest_instances {
             debug!("Processing event > for instance {:?}", instance_id);
 
+            if let Some(target_instance_id) = &event.instance_id {
+                if target_instance_id != &instance_id {
+                    continue;
+                }
+            }
+
+            if last_event_id.as_deref() == Some(event.id.as_str()) {
+                event_already_applied = true;
+                continue;
+            }
+
             if quest_state.is_completed() {
                 continue;
             }
@@ -111,10 +355,17 @@ impl EventProcessor {
             let new_state = quest_state.apply_event(&quest_graph, event);
             if new_state != quest_state {
                 match self
-                    .add_event_and_notify(event, &quest.id, &instance_id, new_state)
+                    .add_event_and_notify(
+                        event,
+                        &quest.id,
+                        &instance_id,
+                        last_event_id.as_deref(),
+                        new_state,
+                    )
                     .await
                 {
-                    Ok(_) => event_applied_to_instances += 1,
+                    Ok(true) => event_applied_to_instances += 1,
+                    Ok(false) => event_already_applied = true,
                     Err(err) => {
                         error!(
                             "Processing event > Couldn't add event to instance {}: {err:?}",
@@ -125,11 +376,12 @@ impl EventProcessor {
             }
         }
 
-        if event_applied_to_instances == 0 {
+        if event_applied_to_instances == 0 && !event_already_applied {
             self.quests_channel
                 .publish(UserUpdate {
                     user_address: event.address.clone(),
                     message: Some(user_update::Message::EventIgnored(event.id.clone())),
+                    ..Default::default()
                 })
                 .await;
             info!("Processing event > Event was ignored");
@@ -138,13 +390,16 @@ impl EventProcessor {
         Ok(event_applied_to_instances)
     }
 
+    /// Returns whether the event was added, `false` if the instance already had it, so the new state is neither
+    /// stored nor published again
     async fn add_event_and_notify(
         self: &Arc<Self>,
         event: &Event,
         quest_id: &str,
         quest_instance_id: &str,
+        previous_event_id: Option<&str>,
         mut quest_state: QuestState,
-    ) -> Result<(), ProcessEventError> {
+    ) -> Result<bool, ProcessEventError> {
         debug!("Processing event > event applied with new state: {quest_state:?}");
         let add_event = AddEvent {
             id: event.id.clone(),
@@ -156,9 +411,33 @@ impl EventProcessor {
             "Processing event > adding event for instance: {:?}",
             quest_instance_id
         );
-        self.database
+        if !self
+            .database
             .add_event(&add_event, quest_instance_id)
-            .await?;
+            .await?
+        {
+            debug!(
+                "Processing event > event was already added to instance: {:?}",
+                quest_instance_id
+            );
+            return Ok(false);
+        }
+
+        let save_state = SaveQuestInstanceState {
+            state: quest_state.encode_to_vec(),
+            previous_event_id,
+            applied_event_ids: vec![&event.id],
+        };
+        // if the snapshot can't be stored, the state is calculated from the last one stored
+        if let Err(err) = self
+            .database
+            .save_quest_instance_state(quest_instance_id, &save_state)
+            .await
+        {
+            error!(
+                "Processing event > Couldn't store state snapshot for instance {quest_instance_id}: {err:?}"
+            );
+        }
 
         if quest_state.is_completed() {
             debug!("Processing event > Calling rewards hook");
@@ -184,37 +463,188 @@ impl EventProcessor {
                     event_id: event.id.clone(),
                 })),
                 user_address: event.address.clone(),
+                ..Default::default()
             })
             .await;
 
-        Ok(())
+        Ok(true)
+    }
+}
+
+/// Handle of the main processing task. It can be awaited, and it finishes only if the processing fails
+pub struct EventProcessingHandle {
+    task: JoinHandle<EventProcessingResult<()>>,
+    stop_sender: watch::Sender<bool>,
+}
+
+impl EventProcessingHandle {
+    /// Stops popping events and waits for the ones being processed to finish, up to the given timeout.
+    ///
+    /// The events still running after it are cut, a reliable queue hands them again on the next start
+    pub async fn shutdown(mut self, timeout: Duration) {
+        if self.stop_sender.send(true).is_err() {
+            // the task already finished
+            return;
+        }
+
+        match tokio::time::timeout(timeout, &mut self.task).await {
+            Ok(Ok(Err(err))) => error!("Event processing finished with error: {err}"),
+            Ok(Err(err)) => error!("Event processing task failed: {err}"),
+            Ok(Ok(Ok(()))) => info!("Event processing stopped"),
+            Err(_) => {
+                warn!("Events being processed didn't finish in {timeout:?}, cutting them");
+                self.task.abort();
+            }
+        }
+    }
+}
+
+impl Future for EventProcessingHandle {
+    type Output = Result<EventProcessingResult<()>, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.task).poll(cx)
     }
 }
 
 pub fn run_event_processor(
     database: Arc<Database>,
-    events_queue: Arc<RedisMessagesQueue>,
+    events_queue: Arc<EventsQueue>,
+    dead_letters: Arc<RedisDeadLetterQueue>,
     quests_channel_publisher: Arc<RedisChannelPublisher>,
-) -> JoinHandle<EventProcessingResult<()>> {
-    let event_processor = EventProcessor::from(events_queue, quests_channel_publisher, database);
+    retry_options: RetryOptions,
+    concurrency: usize,
+) -> EventProcessingHandle {
+    let event_processor = EventProcessor::from(
+        events_queue,
+        dead_letters,
+        quests_channel_publisher,
+        database,
+        retry_options,
+        concurrency,
+    );
 
     start_event_processing(event_processor)
 }
 
 /// Starts the main processing task which reads events from the queue, updates the quest states and
 /// publishes the changes.
-pub(crate) fn start_event_processing(
-    event_processor: EventProcessor,
-) -> JoinHandle<EventProcessingResult<()>> {
+///
+/// Events are only popped while there is a free worker, so the rest wait in the queue. The events of the same user
+/// are processed in the order they were popped, each one after the previous finishes, as they change the same state,
+/// and they don't take a worker while waiting for it. Only [`WAITING_EVENTS_PER_WORKER`] events per worker wait at
+/// once, the popping stops until one of them is done waiting instead.
+///
+/// The events left unprocessed by consumers that stopped are reclaimed on start and periodically.
+pub fn start_event_processing(event_processor: EventProcessor) -> EventProcessingHandle {
     let event_processor = Arc::new(event_processor);
-    tokio::spawn(async move {
+    let (stop_sender, mut stop_receiver) = watch::channel(false);
+    let task = tokio::spawn(async move {
+        reclaim_events(event_processor.events_queue.as_ref()).await;
+        // it's aborted when the processing finishes, as the set is dropped
+        let mut reclaiming = JoinSet::new();
+        reclaiming.spawn(reclaim_events_periodically(
+            event_processor.events_queue.clone(),
+        ));
+
         info!("Listening for events to process...");
-        loop {
-            if let Err(err) = event_processor.clone().process().await {
-                error!("Couldn't spawn task to process event due error: {err:?}");
-            }
+        let workers = Arc::new(Semaphore::new(event_processor.concurrency.max(1)));
+        // taken before the worker, so the events waiting for a worker never hold up the ones waiting for their turn
+        let waiting = Arc::new(Semaphore::new(
+            event_processor.concurrency.max(1) * WAITING_EVENTS_PER_WORKER,
+        ));
+        // signals when the last event popped for each user is done, so the next one waits for it
+        let mut last_event_by_user: HashMap<String, oneshot::Receiver<()>> = HashMap::new();
+        let mut processing_events = JoinSet::new();
+        while !*stop_receiver.borrow() {
+            let waiting_slot = select! {
+                slot = waiting.clone().acquire_owned() => slot.expect("the waiting semaphore is never closed"),
+                Ok(()) = stop_receiver.changed() => break,
+            };
+            let worker = select! {
+                worker = workers.clone().acquire_owned() => worker.expect("the workers semaphore is never closed"),
+                Ok(()) = stop_receiver.changed() => break,
+            };
+
+            // finished tasks are collected, so they don't pile up
+            while let Some(Some(_)) = processing_events.join_next().now_or_never() {}
+            last_event_by_user
+                .retain(|_, event_done| matches!(event_done.try_recv(), Err(TryRecvError::Empty)));
+
+            let delivery = match event_processor
+                .events_queue
+                .try_pop_delivery(POP_TIMEOUT)
+                .await
+            {
+                Ok(Some(delivery)) => delivery,
+                Ok(None) => continue,
+                Err(err) => {
+                    error!("Couldn't pop event to process due error: {err:?}");
+                    continue;
+                }
+            };
+
+            let (event_done_sender, event_done) = oneshot::channel();
+            let previous_event_done = last_event_by_user
+                .insert(delivery.message.address.to_ascii_lowercase(), event_done);
+            let event_processor = event_processor.clone();
+            let workers = workers.clone();
+            processing_events.spawn(async move {
+                let worker = match previous_event_done {
+                    Some(previous_event_done) => {
+                        // the worker is released while waiting, so the events of other users are processed meanwhile
+                        drop(worker);
+                        // it's also resolved if the previous one was cut
+                        let _ = previous_event_done.await;
+                        drop(waiting_slot);
+                        workers
+                            .clone()
+                            .acquire_owned()
+                            .await
+                            .expect("the workers semaphore is never closed")
+                    }
+                    None => {
+                        drop(waiting_slot);
+                        worker
+                    }
+                };
+                let result = event_processor
+                    .process_delivery(delivery, workers, worker)
+                    .await;
+                let _ = event_done_sender.send(());
+                result
+            });
         }
-    })
+
+        info!(
+            "Stopped popping events, waiting for {} being processed...",
+            processing_events.len()
+        );
+        while processing_events.join_next().await.is_some() {}
+
+        Ok(())
+    });
+
+    EventProcessingHandle { task, stop_sender }
+}
+
+/// Takes back the events left unprocessed by this consumer on its first call, or by the others that stopped, so
+/// they're popped again
+async fn reclaim_events(events_queue: &EventsQueue) {
+    match events_queue.reclaim().await {
+        Ok(0) => {}
+        Ok(reclaimed) => info!("Reclaimed {reclaimed} events left unprocessed"),
+        Err(err) => error!("Couldn't reclaim events left unprocessed: {err}"),
+    }
+}
+
+async fn reclaim_events_periodically(events_queue: Arc<EventsQueue>) {
+    let mut interval =
+        tokio::time::interval_at(Instant::now() + RECLAIM_INTERVAL, RECLAIM_INTERVAL);
+    loop {
+        interval.tick().await;
+        reclaim_events(events_queue.as_ref()).await;
+    }
 }
 
 #[derive(Debug)]
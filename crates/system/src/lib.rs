@@ -1,4 +1,5 @@
 use configuration::Config;
+use std::time::Duration;
 use tokio::{select, signal};
 
 pub mod api;
@@ -9,27 +10,65 @@ pub use quests::*;
 mod rewards;
 
 pub const QUESTS_EVENTS_QUEUE_NAME: &str = "events:queue";
+pub const QUESTS_EVENTS_DEAD_LETTERS_NAME: &str = "events:dead_letters";
+pub const QUESTS_EVENTS_IDEMPOTENCY_KEYS_NAME: &str = "events:idempotency_keys";
+pub const QUESTS_EVENTS_STREAM_NAME: &str = "events:stream";
+pub const QUESTS_EVENTS_GROUP_NAME: &str = "events:processors";
 pub const QUESTS_CHANNEL_NAME: &str = "QUEST_UPDATES";
 
 pub async fn run_app(config: &Config) {
     let server = api::start_server(&config.http_server_port).await;
+    let server_handle = server.handle();
     let Ok(event_processor) = event_processing::EventProcessor::from_config(config).await else {
         return;
     };
 
-    let event_processing = event_processing::start_event_processing(event_processor);
+    let mut event_processing = event_processing::start_event_processing(event_processor);
 
     select! {
         _ = server => {},
-        result = event_processing => {
+        result = &mut event_processing => {
             match result {
                 Ok(_) => {},
                 Err(e) => log::debug!("> run_app > Event processing failed due {e:?}"),
             }
             log::info!("> run_app > Event processing finished");
+            return;
         },
-        _ = signal::ctrl_c() => {
-            log::info!("> run_app > SIGINT catched. Exiting...");
+        _ = shutdown_signal() => {
+            log::info!("> run_app > Stopping...");
         }
     }
+
+    event_processing
+        .shutdown(Duration::from_millis(config.shutdown_timeout_ms))
+        .await;
+    server_handle.stop(true).await;
+    log::info!("> run_app > Stopped");
+}
+
+/// Waits for the process to be asked to stop, with SIGINT (ctrl-c) or SIGTERM
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut sigterm = match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(sigterm) => sigterm,
+            Err(err) => {
+                log::error!("> shutdown_signal > Couldn't listen for SIGTERM: {err}");
+                let _ = signal::ctrl_c().await;
+                return;
+            }
+        };
+
+        select! {
+            _ = signal::ctrl_c() => log::info!("> shutdown_signal > SIGINT received"),
+            _ = sigterm.recv() => log::info!("> shutdown_signal > SIGTERM received"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = signal::ctrl_c().await;
+        log::info!("> shutdown_signal > SIGINT received");
+    }
 }
@@ -1,13 +1,14 @@
 use std::sync::Arc;
 
 use futures_util::future::join_all;
+use log::{debug, error};
 use quests_db::core::{
-    definitions::{Event as StoredEvent, QuestsDatabase},
+    definitions::{Event as StoredEvent, QuestInstance, QuestsDatabase, SaveQuestInstanceState},
     errors::DBError,
 };
 use quests_protocol::{
     definitions::{Event, ProtocolMessage, Quest, QuestDefinition, QuestState},
-    quests::get_state,
+    quests::{get_state, QuestGraph},
 };
 
 #[derive(Debug)]
@@ -38,26 +39,53 @@ pub async fn get_quest_with_decoded_definition(
         image_url: quest.image_url,
         active: quest.active,
         created_at: quest.created_at as u32,
+        starts_at: quest.starts_at.map(|starts_at| starts_at as u32),
+        ends_at: quest.ends_at.map(|ends_at| ends_at as u32),
+        instance_duration: quest
+            .instance_duration
+            .map(|instance_duration| instance_duration as u32),
     };
     Ok(quest)
 }
 
+/// Returns the latest state of every active instance of the user along with the last event applied to it
 pub async fn get_all_quest_states_by_user_address(
     database: Arc<impl QuestsDatabase + 'static>,
     user_address: &str,
-) -> Result<Vec<(String, (Quest, QuestState))>, QuestStateCalculationError> {
+) -> Result<Vec<(String, (Quest, QuestState, Option<String>))>, QuestStateCalculationError> {
     let quest_instances = database
         .get_active_user_quest_instances(user_address)
         .await
         .map_err(QuestStateCalculationError::DatabaseError)?;
 
+    get_quest_states(database, quest_instances).await
+}
+
+/// Returns the latest state of every instance the user completed along with the last event applied to it
+pub async fn get_completed_quest_states_by_user_address(
+    database: Arc<impl QuestsDatabase + 'static>,
+    user_address: &str,
+) -> Result<Vec<(String, (Quest, QuestState, Option<String>))>, QuestStateCalculationError> {
+    let quest_instances = database
+        .get_completed_user_quest_instances(user_address)
+        .await
+        .map_err(QuestStateCalculationError::DatabaseError)?;
+
+    get_quest_states(database, quest_instances).await
+}
+
+async fn get_quest_states(
+    database: Arc<impl QuestsDatabase + 'static>,
+    quest_instances: Vec<QuestInstance>,
+) -> Result<Vec<(String, (Quest, QuestState, Option<String>))>, QuestStateCalculationError> {
     let mut join_handles = vec![];
     for quest_instance in quest_instances {
         let database = database.clone();
         let handle = tokio::spawn(async move {
             (
                 quest_instance.id.clone(),
-                get_instance_state(database, &quest_instance.quest_id, &quest_instance.id).await,
+                get_latest_instance_state(database, &quest_instance.quest_id, &quest_instance.id)
+                    .await,
             )
         });
         join_handles.push(handle);
@@ -67,7 +95,7 @@ pub async fn get_all_quest_states_by_user_address(
     for join_result in join_results {
         match join_result {
             Ok((id, state_result)) => match state_result {
-                Ok((quest, state, _)) => states.push((id, (quest, state))),
+                Ok(result) => states.push((id, result)),
                 Err(quest_error) => return Err(quest_error),
             },
             Err(_) => return Err(QuestStateCalculationError::StateError),
@@ -97,3 +125,168 @@ pub async fn get_instance_state(
 
     Ok((quest, state, stored_events))
 }
+
+/// Calculates the state of a Quest Instance starting from its stored snapshot, so only the events added after it
+/// are applied. If there is no snapshot, all the events are applied. It also returns the id of the last event applied.
+pub async fn get_latest_instance_state(
+    database: Arc<impl QuestsDatabase>,
+    quest_id: &str,
+    quest_instance: &str,
+) -> Result<(Quest, QuestState, Option<String>), QuestStateCalculationError> {
+    let quest = get_quest_with_decoded_definition(database.clone(), quest_id).await?;
+
+    let snapshot = get_instance_snapshot(database.clone(), quest_instance).await?;
+
+    let (initial_state, snapshot_event_id, stored_events) = match snapshot {
+        Some((state, last_event_id)) => {
+            let stored_events = database
+                .get_events_after(quest_instance, &last_event_id)
+                .await
+                .map_err(QuestStateCalculationError::DatabaseError)?;
+            (state, Some(last_event_id), stored_events)
+        }
+        None => {
+            let stored_events = database
+                .get_events(quest_instance)
+                .await
+                .map_err(QuestStateCalculationError::DatabaseError)?;
+            (
+                QuestState::from(&QuestGraph::from(&quest)),
+                None,
+                stored_events,
+            )
+        }
+    };
+
+    let Some(last_stored_event) = stored_events.last() else {
+        return Ok((quest, initial_state, snapshot_event_id));
+    };
+    let last_event_id = last_stored_event.id.clone();
+
+    let events = stored_events
+        .iter()
+        .map(|event| Event::decode(event.event.as_slice()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| QuestStateCalculationError::DefinitionError)?;
+
+    let quest_graph = QuestGraph::from(&quest);
+    let state = events.iter().fold(initial_state, |state, event| {
+        state.apply_event(&quest_graph, event)
+    });
+
+    // store the state calculated so the next time it can start from here
+    let save_state = SaveQuestInstanceState {
+        state: state.encode_to_vec(),
+        previous_event_id: snapshot_event_id.as_deref(),
+        applied_event_ids: stored_events
+            .iter()
+            .map(|event| event.id.as_str())
+            .collect(),
+    };
+    match database
+        .save_quest_instance_state(quest_instance, &save_state)
+        .await
+    {
+        Ok(saved) => debug!("Quest instance {quest_instance} state snapshot stored: {saved}"),
+        Err(err) => {
+            error!("Couldn't store quest instance {quest_instance} state snapshot: {err:?}")
+        }
+    }
+
+    Ok((quest, state, Some(last_event_id)))
+}
+
+/// Calculates the state of a Quest Instance right before the given event was applied. It starts from the stored
+/// snapshot if it was taken before the event, so only the events in between are applied.
+pub async fn get_instance_state_before_event(
+    database: Arc<impl QuestsDatabase>,
+    quest: &Quest,
+    quest_instance: &str,
+    event_id: &str,
+) -> Result<QuestState, QuestStateCalculationError> {
+    let quest_graph = QuestGraph::from(quest);
+
+    let mut from_snapshot = None;
+    if let Some((state, last_event_id)) =
+        get_instance_snapshot(database.clone(), quest_instance).await?
+    {
+        let mut stored_events = database
+            .get_events_after(quest_instance, &last_event_id)
+            .await
+            .map_err(QuestStateCalculationError::DatabaseError)?;
+        if let Some(position) = stored_events.iter().position(|event| event.id == event_id) {
+            stored_events.truncate(position);
+            from_snapshot = Some((state, stored_events));
+        }
+    }
+
+    // without a snapshot taken before the event, every event before it is applied
+    let (initial_state, stored_events) = match from_snapshot {
+        Some(from_snapshot) => from_snapshot,
+        None => (
+            QuestState::from(&quest_graph),
+            database
+                .get_events_before(quest_instance, event_id)
+                .await
+                .map_err(QuestStateCalculationError::DatabaseError)?,
+        ),
+    };
+
+    let events = stored_events
+        .iter()
+        .map(|event| Event::decode(event.event.as_slice()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| QuestStateCalculationError::DefinitionError)?;
+
+    Ok(events.iter().fold(initial_state, |state, event| {
+        state.apply_event(&quest_graph, event)
+    }))
+}
+
+/// Returns the stored snapshot of the Quest Instance along with the last event applied to it, if there is a valid one
+async fn get_instance_snapshot(
+    database: Arc<impl QuestsDatabase>,
+    quest_instance: &str,
+) -> Result<Option<(QuestState, String)>, QuestStateCalculationError> {
+    match database.get_quest_instance_state(quest_instance).await {
+        Ok(snapshot) => match QuestState::decode(snapshot.state.as_slice()) {
+            Ok(state) => Ok(Some((state, snapshot.last_event_id))),
+            Err(_) => {
+                error!("Quest instance {quest_instance} has a corrupted state snapshot");
+                Ok(None)
+            }
+        },
+        Err(DBError::RowNotFound) => Ok(None),
+        Err(err) => Err(QuestStateCalculationError::DatabaseError(err)),
+    }
+}
+
+/// Calculates the state of a Quest Instance again after its events were edited, recording or clearing its completion
+/// according to the new state
+pub async fn recalculate_instance_state(
+    database: Arc<impl QuestsDatabase>,
+    quest_id: &str,
+    quest_instance: &str,
+) -> Result<QuestState, QuestStateCalculationError> {
+    let (_, state, _) =
+        get_latest_instance_state(database.clone(), quest_id, quest_instance).await?;
+
+    let is_recorded_as_completed = database
+        .is_completed_instance(quest_instance)
+        .await
+        .map_err(QuestStateCalculationError::DatabaseError)?;
+
+    if state.is_completed() && !is_recorded_as_completed {
+        database
+            .complete_quest_instance(quest_instance)
+            .await
+            .map_err(QuestStateCalculationError::DatabaseError)?;
+    } else if !state.is_completed() && is_recorded_as_completed {
+        database
+            .remove_instance_from_completed_instances(quest_instance)
+            .await
+            .map_err(QuestStateCalculationError::DatabaseError)?;
+    }
+
+    Ok(state)
+}
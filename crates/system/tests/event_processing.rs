@@ -1,14 +1,18 @@
 mod common;
 use crate::common::database::create_test_db;
 use quests_db::{core::definitions::*, create_quests_db_component};
-use quests_message_broker::messages_queue::MessagesQueue;
+use quests_message_broker::dead_letters::{DeadLetter, DeadLetterQueue};
 use quests_protocol::{
     definitions::{Event as ProtoEvent, *},
     quests::Coordinates,
 };
-use quests_system::{configuration::Config, event_processing::EventProcessor};
+use quests_system::{
+    configuration::{Config, EventsQueueBackend},
+    event_processing::{start_event_processing, EventProcessor, WAITING_EVENTS_PER_WORKER},
+    get_instance_state, get_latest_instance_state,
+};
 use serde_json::json;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use wiremock::{
     matchers::{body_json, method, path},
     Mock, MockServer, ResponseTemplate,
@@ -40,8 +44,11 @@ async fn can_process_events() {
                         id: "A_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -49,8 +56,11 @@ async fn can_process_events() {
                         id: "B_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(13, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "C".to_string(),
@@ -58,8 +68,11 @@ async fn can_process_events() {
                         id: "C_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 24))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "D".to_string(),
@@ -67,8 +80,11 @@ async fn can_process_events() {
                         id: "D_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(40, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         }),
@@ -85,6 +101,11 @@ async fn can_process_events() {
             .unwrap()
             .encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let result = db.create_quest(&create_quest, "0xA").await;
@@ -109,6 +130,7 @@ async fn can_process_events() {
         id: uuid::Uuid::new_v4().to_string(),
         address: user_address.to_string(),
         action: Some(action),
+        instance_id: None,
     };
 
     event_processor
@@ -158,8 +180,11 @@ async fn should_call_rewards_hook_when_user_completes_a_quest() {
                         id: "A_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(10, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
                 Step {
                     id: "B".to_string(),
@@ -167,8 +192,11 @@ async fn should_call_rewards_hook_when_user_completes_a_quest() {
                         id: "B_1".to_string(),
                         description: "".to_string(),
                         action_items: vec![Action::location(Coordinates::new(13, 20))],
+                        ordered: false,
                     }],
                     description: "".to_string(),
+                    optional: false,
+                    branch_group: String::new(),
                 },
             ],
         }),
@@ -185,6 +213,11 @@ async fn should_call_rewards_hook_when_user_completes_a_quest() {
             .unwrap()
             .encode_to_vec(),
         reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
     };
 
     let quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
@@ -233,6 +266,7 @@ async fn should_call_rewards_hook_when_user_completes_a_quest() {
         id: uuid::Uuid::new_v4().to_string(),
         address: user_address.to_string(),
         action: Some(action),
+        instance_id: None,
     };
 
     event_processor
@@ -247,6 +281,7 @@ async fn should_call_rewards_hook_when_user_completes_a_quest() {
         id: uuid::Uuid::new_v4().to_string(),
         address: user_address.to_string(),
         action: Some(action),
+        instance_id: None,
     };
 
     event_processor
@@ -283,3 +318,1038 @@ async fn should_call_rewards_hook_when_user_completes_a_quest() {
 
     mocked_server.verify().await;
 }
+
+#[tokio::test]
+async fn can_process_events_targeting_a_single_instance() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    let quest_definition = QuestDefinition {
+        connections: vec![Connection::new("A", "B")],
+        steps: vec![
+            Step {
+                id: "A".to_string(),
+                tasks: vec![Task {
+                    id: "A_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(10, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+            Step {
+                id: "B".to_string(),
+                tasks: vec![Task {
+                    id: "B_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(13, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+        ],
+    };
+
+    let create_quest = CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let first_quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
+
+    let create_quest = CreateQuest {
+        name: "QUEST-2",
+        description: "Grab some oranges",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let second_quest_id = db.create_quest(&create_quest, "0xC").await.unwrap();
+
+    let user_address = "0xB";
+    let first_instance_id = db.start_quest(&first_quest_id, user_address).await.unwrap();
+    let second_instance_id = db
+        .start_quest(&second_quest_id, user_address)
+        .await
+        .unwrap();
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/4".to_string();
+    config.database_url = db_url;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+
+    let event = ProtoEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        address: user_address.to_string(),
+        action: Some(Action::location(Coordinates::new(10, 20))),
+        instance_id: Some(second_instance_id.clone()),
+    };
+
+    event_processor
+        .events_queue
+        .push(&event)
+        .await
+        .expect("can push event");
+
+    let event_processor = Arc::new(event_processor);
+
+    let result = event_processor
+        .process()
+        .await
+        .expect("can spawn task to process event")
+        .await
+        .expect("can await join handle")
+        .expect("can process event");
+
+    assert_eq!(result, 1);
+
+    let events = db
+        .get_events(&first_instance_id)
+        .await
+        .expect("can retrieve events");
+    assert!(events.is_empty());
+
+    let events = db
+        .get_events(&second_instance_id)
+        .await
+        .expect("can retrieve events");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, event.id);
+}
+
+#[tokio::test]
+async fn does_not_apply_events_to_expired_instances() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    let quest_definition = QuestDefinition {
+        connections: vec![Connection::new("A", "B")],
+        steps: vec![
+            Step {
+                id: "A".to_string(),
+                tasks: vec![Task {
+                    id: "A_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(10, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+            Step {
+                id: "B".to_string(),
+                tasks: vec![Task {
+                    id: "B_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(13, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+        ],
+    };
+
+    let create_quest = CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
+
+    // instances of this quest expire as soon as they are started
+    let create_quest = CreateQuest {
+        name: "QUEST-2",
+        description: "Grab some apples in no time",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: Some(0),
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let timed_quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
+
+    let user_address = "0xB";
+    let instance_id = db.start_quest(&quest_id, user_address).await.unwrap();
+    let expired_instance_id = db.start_quest(&timed_quest_id, user_address).await.unwrap();
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/7".to_string();
+    config.database_url = db_url;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+
+    let event = ProtoEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        address: user_address.to_string(),
+        action: Some(Action::location(Coordinates::new(10, 20))),
+        instance_id: None,
+    };
+
+    event_processor
+        .events_queue
+        .push(&event)
+        .await
+        .expect("can push event");
+
+    let event_processor = Arc::new(event_processor);
+
+    let result = event_processor
+        .process()
+        .await
+        .expect("can spawn task to process event")
+        .await
+        .expect("can await join handle")
+        .expect("can process event");
+
+    assert_eq!(result, 1);
+
+    let events = db
+        .get_events(&instance_id)
+        .await
+        .expect("can retrieve events");
+    assert_eq!(events.len(), 1);
+
+    let events = db
+        .get_events(&expired_instance_id)
+        .await
+        .expect("can retrieve events");
+    assert!(events.is_empty());
+}
+
+#[tokio::test]
+async fn state_from_snapshot_matches_full_replay() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+    let db = Arc::new(db);
+
+    let quest_definition = QuestDefinition {
+        connections: vec![Connection::new("A", "B"), Connection::new("B", "C")],
+        steps: vec![
+            Step {
+                id: "A".to_string(),
+                tasks: vec![Task {
+                    id: "A_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![
+                        Action::location(Coordinates::new(10, 20)),
+                        Action::jump(Coordinates::new(10, 20)),
+                    ],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+            Step {
+                id: "B".to_string(),
+                tasks: vec![Task {
+                    id: "B_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(13, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+            Step {
+                id: "C".to_string(),
+                tasks: vec![Task {
+                    id: "C_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(40, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+        ],
+    };
+
+    let create_quest = CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
+
+    let user_address = "0xB";
+    let quest_instance_id = db.start_quest(&quest_id, user_address).await.unwrap();
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/6".to_string();
+    config.database_url = db_url;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+    let event_processor = Arc::new(event_processor);
+
+    let actions = [
+        Action::location(Coordinates::new(10, 20)),
+        Action::jump(Coordinates::new(10, 20)),
+        Action::location(Coordinates::new(13, 20)),
+    ];
+    let mut event_ids = vec![];
+    for action in actions {
+        let event = ProtoEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: user_address.to_string(),
+            action: Some(action),
+            instance_id: None,
+        };
+        event_ids.push(event.id.clone());
+
+        event_processor
+            .events_queue
+            .push(&event)
+            .await
+            .expect("can push event");
+
+        let result = event_processor
+            .clone()
+            .process()
+            .await
+            .expect("can spawn task to process event")
+            .await
+            .expect("can await join handle")
+            .expect("can process event");
+        assert_eq!(result, 1);
+    }
+
+    let snapshot = db
+        .get_quest_instance_state(&quest_instance_id)
+        .await
+        .expect("snapshot is stored");
+    assert_eq!(&snapshot.last_event_id, event_ids.last().unwrap());
+
+    let (_, snapshot_state, last_event_id) =
+        get_latest_instance_state(db.clone(), &quest_id, &quest_instance_id)
+            .await
+            .unwrap();
+    let (_, replayed_state, _) = get_instance_state(db.clone(), &quest_id, &quest_instance_id)
+        .await
+        .unwrap();
+    assert_eq!(snapshot_state, replayed_state);
+    assert_eq!(last_event_id.as_ref(), event_ids.last());
+    assert_eq!(snapshot_state.steps_completed, vec!["A", "B"]);
+
+    // removing an event invalidates the snapshot and the state is calculated from scratch
+    db.remove_event(&quest_instance_id, &event_ids[2])
+        .await
+        .unwrap();
+    let (_, snapshot_state, last_event_id) =
+        get_latest_instance_state(db.clone(), &quest_id, &quest_instance_id)
+            .await
+            .unwrap();
+    let (_, replayed_state, _) = get_instance_state(db.clone(), &quest_id, &quest_instance_id)
+        .await
+        .unwrap();
+    assert_eq!(snapshot_state, replayed_state);
+    assert_eq!(last_event_id.as_ref(), Some(&event_ids[1]));
+    assert_eq!(snapshot_state.steps_completed, vec!["A"]);
+
+    let snapshot = db
+        .get_quest_instance_state(&quest_instance_id)
+        .await
+        .expect("snapshot is stored again");
+    assert_eq!(snapshot.last_event_id, event_ids[1]);
+}
+
+#[tokio::test]
+async fn moves_event_to_dead_letters_after_max_attempts() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    // the stored definition can't be decoded, so the event can never be processed
+    let create_quest = CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition: vec![255, 255, 255],
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
+
+    let user_address = "0xB";
+    db.start_quest(&quest_id, user_address).await.unwrap();
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/8".to_string();
+    config.database_url = db_url;
+    config.event_max_attempts = 2;
+    config.event_retry_backoff_ms = 1;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+
+    let event = ProtoEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        address: user_address.to_string(),
+        action: Some(Action::location(Coordinates::new(10, 20))),
+        instance_id: None,
+    };
+
+    event_processor
+        .events_queue
+        .push(&event)
+        .await
+        .expect("can push event");
+
+    let event_processor = Arc::new(event_processor);
+
+    // it's retried in the same task, and the last failure moves it to the dead letters
+    let result = event_processor
+        .clone()
+        .process()
+        .await
+        .expect("can spawn task to process event")
+        .await
+        .expect("can await join handle");
+    assert!(result.is_err());
+
+    let dead_letters: Vec<DeadLetter<ProtoEvent>> =
+        event_processor.dead_letters.list().await.unwrap();
+    let dead_letter = dead_letters
+        .iter()
+        .find(|dead_letter| dead_letter.id == event.id)
+        .expect("event is in the dead letters");
+    assert_eq!(dead_letter.message, event);
+    assert_eq!(dead_letter.attempts, 2);
+
+    // it wasn't pushed back to the queue
+    assert!(event_processor
+        .events_queue
+        .try_pop_delivery(Duration::from_secs(1))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn can_process_events_from_a_stream() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    let quest_definition = QuestDefinition {
+        connections: vec![Connection::new("A", "B")],
+        steps: vec![
+            Step {
+                id: "A".to_string(),
+                tasks: vec![Task {
+                    id: "A_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(10, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+            Step {
+                id: "B".to_string(),
+                tasks: vec![Task {
+                    id: "B_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(13, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+        ],
+    };
+
+    let create_quest = CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
+
+    let user_address = "0xB";
+    let instance_id = db.start_quest(&quest_id, user_address).await.unwrap();
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/1".to_string();
+    config.database_url = db_url;
+    config.events_queue_backend = EventsQueueBackend::Streams;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+
+    let event = ProtoEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        address: user_address.to_string(),
+        action: Some(Action::location(Coordinates::new(10, 20))),
+        instance_id: None,
+    };
+
+    event_processor
+        .events_queue
+        .push(&event)
+        .await
+        .expect("can push event");
+
+    let event_processor = Arc::new(event_processor);
+
+    let result = event_processor
+        .clone()
+        .process()
+        .await
+        .expect("can spawn task to process event")
+        .await
+        .expect("can await join handle")
+        .expect("can process event");
+    assert_eq!(result, 1);
+
+    let events = db
+        .get_events(&instance_id)
+        .await
+        .expect("can retrieve events");
+    assert_eq!(events.len(), 1);
+
+    // the event was acked, so there is nothing left to reclaim
+    assert_eq!(event_processor.events_queue.reclaim().await, Ok(0));
+}
+
+#[tokio::test]
+async fn stops_popping_events_on_shutdown() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    let quest_definition = QuestDefinition {
+        connections: vec![Connection::new("A", "B")],
+        steps: vec![
+            Step {
+                id: "A".to_string(),
+                tasks: vec![Task {
+                    id: "A_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(10, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+            Step {
+                id: "B".to_string(),
+                tasks: vec![Task {
+                    id: "B_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(13, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+        ],
+    };
+
+    let create_quest = CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
+
+    let user_address = "0xB";
+    let instance_id = db.start_quest(&quest_id, user_address).await.unwrap();
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/0".to_string();
+    config.database_url = db_url;
+    config.reliable_events_queue = true;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+    let events_queue = event_processor.events_queue.clone();
+
+    let build_event = |coordinates| ProtoEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        address: user_address.to_string(),
+        action: Some(Action::location(coordinates)),
+        instance_id: None,
+    };
+
+    let event_processing = start_event_processing(event_processor);
+
+    events_queue
+        .push(&build_event(Coordinates::new(10, 20)))
+        .await
+        .expect("can push event");
+
+    // waits for the event to be processed
+    let mut events = vec![];
+    for _ in 0..50 {
+        events = db.get_events(&instance_id).await.unwrap();
+        if !events.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(events.len(), 1);
+
+    event_processing.shutdown(Duration::from_secs(5)).await;
+
+    let event = build_event(Coordinates::new(13, 20));
+    events_queue.push(&event).await.expect("can push event");
+
+    // it's left in the queue for the next start
+    let delivery = events_queue
+        .try_pop_delivery(Duration::from_secs(1))
+        .await
+        .unwrap()
+        .expect("event is still in the queue");
+    assert_eq!(delivery.message, event);
+    events_queue.ack(&delivery).await.unwrap();
+
+    assert_eq!(db.get_events(&instance_id).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn processes_the_events_of_a_user_in_order() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    let step = |id: &str, coordinates: Coordinates| Step {
+        id: id.to_string(),
+        tasks: vec![Task {
+            id: format!("{id}_1"),
+            description: "".to_string(),
+            action_items: vec![Action::location(coordinates)],
+            ordered: false,
+        }],
+        description: "".to_string(),
+        optional: false,
+        branch_group: String::new(),
+    };
+    let coordinates = [
+        Coordinates::new(10, 20),
+        Coordinates::new(13, 20),
+        Coordinates::new(10, 24),
+    ];
+    let quest_definition = QuestDefinition {
+        connections: vec![Connection::new("A", "B"), Connection::new("B", "C")],
+        steps: vec![
+            step("A", coordinates[0].clone()),
+            step("B", coordinates[1].clone()),
+            step("C", coordinates[2].clone()),
+        ],
+    };
+
+    let create_quest = CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    let quest_id = db.create_quest(&create_quest, "0xA").await.unwrap();
+
+    let user_addresses = ["0xB", "0xC"];
+    let mut instance_ids = vec![];
+    for user_address in user_addresses {
+        instance_ids.push(db.start_quest(&quest_id, user_address).await.unwrap());
+    }
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/1".to_string();
+    config.database_url = db_url;
+    config.event_processing_concurrency = 4;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+    let events_queue = event_processor.events_queue.clone();
+
+    // each event is only valid once the previous one was applied
+    for coordinates in &coordinates {
+        for user_address in user_addresses {
+            let event = ProtoEvent {
+                id: uuid::Uuid::new_v4().to_string(),
+                address: user_address.to_string(),
+                action: Some(Action::location(coordinates.clone())),
+                instance_id: None,
+            };
+            events_queue.push(&event).await.expect("can push event");
+        }
+    }
+
+    let event_processing = start_event_processing(event_processor);
+
+    let db = Arc::new(db);
+    for instance_id in &instance_ids {
+        let mut events = vec![];
+        for _ in 0..50 {
+            events = db.get_events(instance_id).await.unwrap();
+            if events.len() == coordinates.len() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(events.len(), coordinates.len());
+
+        let (_, state, _) = get_instance_state(db.clone(), &quest_id, instance_id)
+            .await
+            .unwrap();
+        assert_eq!(state.steps_completed, vec!["A", "B", "C"]);
+    }
+
+    event_processing.shutdown(Duration::from_secs(5)).await;
+}
+
+#[tokio::test]
+async fn processes_the_events_of_other_users_while_waiting_for_the_previous_one() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    let quest_definition = QuestDefinition {
+        connections: vec![],
+        steps: vec![Step {
+            id: "A".to_string(),
+            tasks: vec![Task {
+                id: "A_1".to_string(),
+                description: "".to_string(),
+                action_items: vec![Action::location(Coordinates::new(10, 20))],
+                ordered: false,
+            }],
+            description: "".to_string(),
+            optional: false,
+            branch_group: String::new(),
+        }],
+    };
+    let create_quest = |definition| CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition,
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    // the events of the first user can never be processed, as its quest definition can't be decoded
+    let broken_quest_id = db
+        .create_quest(&create_quest(vec![255, 255, 255]), "0xA")
+        .await
+        .unwrap();
+    let quest_id = db
+        .create_quest(&create_quest(quest_definition.encode_to_vec()), "0xA")
+        .await
+        .unwrap();
+
+    db.start_quest(&broken_quest_id, "0xB").await.unwrap();
+    let instance_id = db.start_quest(&quest_id, "0xC").await.unwrap();
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/2".to_string();
+    config.database_url = db_url;
+    config.events_queue_backend = EventsQueueBackend::Streams;
+    config.event_processing_concurrency = 1;
+    config.event_max_attempts = 2;
+    config.event_retry_backoff_ms = 5000;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+    let events_queue = event_processor.events_queue.clone();
+
+    // the second event of the first user waits for the first one, which is retried
+    for user_address in ["0xB", "0xB", "0xC"] {
+        let event = ProtoEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: user_address.to_string(),
+            action: Some(Action::location(Coordinates::new(10, 20))),
+            instance_id: None,
+        };
+        events_queue.push(&event).await.expect("can push event");
+    }
+
+    let event_processing = start_event_processing(event_processor);
+
+    // the event of the other user is processed before the first one is retried
+    let mut events = vec![];
+    for _ in 0..30 {
+        events = db.get_events(&instance_id).await.unwrap();
+        if !events.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(events.len(), 1);
+
+    event_processing.shutdown(Duration::from_millis(100)).await;
+}
+
+#[tokio::test]
+async fn stops_popping_events_while_too_many_wait_for_the_previous_one() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    let quest_definition = QuestDefinition {
+        connections: vec![],
+        steps: vec![Step {
+            id: "A".to_string(),
+            tasks: vec![Task {
+                id: "A_1".to_string(),
+                description: "".to_string(),
+                action_items: vec![Action::location(Coordinates::new(10, 20))],
+                ordered: false,
+            }],
+            description: "".to_string(),
+            optional: false,
+            branch_group: String::new(),
+        }],
+    };
+    let create_quest = |definition| CreateQuest {
+        name: "QUEST-1",
+        description: "Grab some apples",
+        image_url: "",
+        definition,
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+    // the events of the first user can never be processed, as its quest definition can't be decoded
+    let broken_quest_id = db
+        .create_quest(&create_quest(vec![255, 255, 255]), "0xA")
+        .await
+        .unwrap();
+    let quest_id = db
+        .create_quest(&create_quest(quest_definition.encode_to_vec()), "0xA")
+        .await
+        .unwrap();
+
+    db.start_quest(&broken_quest_id, "0xB").await.unwrap();
+    let instance_id = db.start_quest(&quest_id, "0xC").await.unwrap();
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/4".to_string();
+    config.database_url = db_url;
+    config.events_queue_backend = EventsQueueBackend::Streams;
+    config.event_processing_concurrency = 1;
+    config.event_max_attempts = 2;
+    config.event_retry_backoff_ms = 5000;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+    let events_queue = event_processor.events_queue.clone();
+
+    // the first event of the first user is retried, and all the waiting ones are taken by its next events
+    let user_addresses = std::iter::repeat("0xB")
+        .take(WAITING_EVENTS_PER_WORKER + 2)
+        .chain(["0xC"]);
+    for user_address in user_addresses {
+        let event = ProtoEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            address: user_address.to_string(),
+            action: Some(Action::location(Coordinates::new(10, 20))),
+            instance_id: None,
+        };
+        events_queue.push(&event).await.expect("can push event");
+    }
+
+    let event_processing = start_event_processing(event_processor);
+
+    // the event of the other user stays in the queue until the first one is done
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(db.get_events(&instance_id).await.unwrap().is_empty());
+
+    event_processing.shutdown(Duration::from_millis(100)).await;
+}
+
+#[tokio::test]
+async fn applies_an_event_once_to_every_instance() {
+    let _ = env_logger::try_init();
+    let db_url = create_test_db().await;
+    let db = create_quests_db_component(&db_url, true)
+        .await
+        .expect("can create db");
+
+    let quest_definition = QuestDefinition {
+        connections: vec![Connection::new("A", "B")],
+        steps: vec![
+            Step {
+                id: "A".to_string(),
+                tasks: vec![Task {
+                    id: "A_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(10, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+            Step {
+                id: "B".to_string(),
+                tasks: vec![Task {
+                    id: "B_1".to_string(),
+                    description: "".to_string(),
+                    action_items: vec![Action::location(Coordinates::new(13, 20))],
+                    ordered: false,
+                }],
+                description: "".to_string(),
+                optional: false,
+                branch_group: String::new(),
+            },
+        ],
+    };
+    let create_quest = |name| CreateQuest {
+        name,
+        description: "Grab some apples",
+        image_url: "",
+        definition: quest_definition.encode_to_vec(),
+        reward: None,
+        starts_at: None,
+        ends_at: None,
+        instance_duration: None,
+        prerequisites: vec![],
+        repeat_policy: RepeatPolicy::Never,
+    };
+
+    let user_address = "0xB";
+    let mut instance_ids = vec![];
+    for name in ["QUEST-1", "QUEST-2"] {
+        let quest_id = db.create_quest(&create_quest(name), "0xA").await.unwrap();
+        instance_ids.push(db.start_quest(&quest_id, user_address).await.unwrap());
+    }
+
+    let mut config = Config::new().expect("Can parse config");
+    config.redis_url = "127.0.0.1:6379/3".to_string();
+    config.database_url = db_url;
+    config.events_queue_backend = EventsQueueBackend::Streams;
+    let event_processor = EventProcessor::from_config(&config)
+        .await
+        .expect("can initialize event processor");
+    let event_processor = Arc::new(event_processor);
+
+    let event = ProtoEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        address: user_address.to_string(),
+        action: Some(Action::location(Coordinates::new(10, 20))),
+        instance_id: None,
+    };
+
+    // the second time the event is delivered again, and it's skipped
+    for expected_instances_applied in [2, 0] {
+        event_processor
+            .events_queue
+            .push(&event)
+            .await
+            .expect("can push event");
+
+        let instances_applied = event_processor
+            .clone()
+            .process()
+            .await
+            .expect("can spawn task to process event")
+            .await
+            .expect("can await join handle")
+            .expect("can process event");
+        assert_eq!(instances_applied, expected_instances_applied);
+
+        for instance_id in &instance_ids {
+            let events = db.get_events(instance_id).await.unwrap();
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].id, event.id);
+        }
+    }
+}